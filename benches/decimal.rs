@@ -0,0 +1,102 @@
+//! Cost of exact decimals over `f64` on the per-bar hot paths: parsing a quoted
+//! price, folding 1m bars into a longer one, and the volatility percentage.
+//!
+//! Run with `cargo bench --bench decimal`.
+
+use std::hint::black_box;
+
+use criterion::{Criterion, criterion_group, criterion_main};
+
+// The crate is a binary, so the types are compiled in from their source file.
+#[allow(dead_code, unused_imports)]
+#[path = "../src/types/decimal.rs"]
+mod decimal;
+
+use decimal::{Price, Volume};
+
+const QUOTES: [&str; 8] = [
+    "42283.58000000",
+    "42295.91000000",
+    "42238.51000000",
+    "0.00001234",
+    "0.00001247",
+    "1.00010000",
+    "3150.27000000",
+    "139.05655000",
+];
+
+fn parse(c: &mut Criterion) {
+    let mut group = c.benchmark_group("parse");
+    group.bench_function("f64", |b| {
+        b.iter(|| {
+            for quote in QUOTES {
+                black_box(black_box(quote).parse::<f64>().unwrap());
+            }
+        })
+    });
+    group.bench_function("decimal", |b| {
+        b.iter(|| {
+            for quote in QUOTES {
+                black_box(black_box(quote).parse::<Price>().unwrap());
+            }
+        })
+    });
+    group.finish();
+}
+
+fn merge(c: &mut Criterion) {
+    // An hour of 1m highs, lows and volumes folded into one bar.
+    let highs: Vec<&str> = QUOTES.iter().cycle().take(60).copied().collect();
+    let float_bars: Vec<(f64, f64)> = highs
+        .iter()
+        .map(|quote| (quote.parse().unwrap(), quote.parse().unwrap()))
+        .collect();
+    let decimal_bars: Vec<(Price, Volume)> = highs
+        .iter()
+        .map(|quote| (quote.parse().unwrap(), quote.parse().unwrap()))
+        .collect();
+
+    let mut group = c.benchmark_group("merge_60");
+    group.bench_function("f64", |b| {
+        b.iter(|| {
+            black_box(&float_bars).iter().fold(
+                (f64::MIN, f64::MAX, 0.0),
+                |(high, low, volume), &(price, qty)| {
+                    (high.max(price), low.min(price), volume + qty)
+                },
+            )
+        })
+    });
+    group.bench_function("decimal", |b| {
+        b.iter(|| {
+            black_box(&decimal_bars).iter().fold(
+                (Price::ZERO, Price(rust_decimal::Decimal::MAX), Volume::ZERO),
+                |(high, low, volume), &(price, qty)| {
+                    (high.max(price), low.min(price), volume + qty)
+                },
+            )
+        })
+    });
+    group.finish();
+}
+
+fn percent_change(c: &mut Criterion) {
+    let (high, low) = (0.00001247_f64, 0.00001234_f64);
+    let (high_dec, low_dec): (Price, Price) =
+        ("0.00001247".parse().unwrap(), "0.00001234".parse().unwrap());
+
+    let mut group = c.benchmark_group("percent_change");
+    group.bench_function("f64", |b| {
+        b.iter(|| {
+            let extent = (black_box(high) - black_box(low)) / black_box(low) * 100.0;
+            (extent * 10.0).trunc() / 10.0
+        })
+    });
+    group.bench_function("decimal", |b| {
+        b.iter(|| black_box(high_dec).percent_change(black_box(low_dec), 1))
+    });
+    group.finish();
+}
+
+criterion_group!(benches, parse, merge, percent_change);
+criterion_main!(benches);
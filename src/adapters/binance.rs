@@ -1,21 +1,37 @@
 use std::{
     collections::VecDeque,
+    str::FromStr,
     sync::Arc,
     time::{Duration, Instant},
 };
 
 use reqwest::Client;
-use serde::Deserialize;
+use serde::{Deserialize, de::DeserializeOwned};
 use tokio::{sync::Mutex, time::sleep};
 
 use crate::{
     error::{GlobalError, Result},
-    types::{Kline, Pair, Timeframe, Timestamp},
+    types::{FundingRate, Kline, Market, OpenInterest, Pair, Timeframe, Timestamp, UtcOffset},
 };
 
 const BINANCE_API_BASE: &str = "https://api.binance.com";
+const BINANCE_FUTURES_API_BASE: &str = "https://fapi.binance.com";
 const BINANCE_RATE_LIMIT_PER_MIN: usize = 1_200;
 const BINANCE_MAX_LIMIT: u16 = 1_000;
+const BINANCE_MAX_OI_LIMIT: u16 = 500;
+
+/// Periods accepted by the futures open interest history endpoint.
+pub const OPEN_INTEREST_TIMEFRAMES: [Timeframe; 9] = [
+    Timeframe::M5,
+    Timeframe::M15,
+    Timeframe::M30,
+    Timeframe::H1,
+    Timeframe::H2,
+    Timeframe::H4,
+    Timeframe::H6,
+    Timeframe::H12,
+    Timeframe::D1,
+];
 
 /// Simple REST adapter for Binance public market data.
 /// Uses a small builder for configurability and keeps a local rate limiter.
@@ -23,6 +39,7 @@ const BINANCE_MAX_LIMIT: u16 = 1_000;
 pub struct BinanceRest {
     client: Client,
     base_url: String,
+    futures_base_url: String,
     rate_limiter: RateLimiter,
 }
 
@@ -32,28 +49,104 @@ impl BinanceRest {
     }
 
     /// Fetch historical klines starting from the provided timestamp.
+    ///
+    /// Spot candles follow `offset` through the `timeZone` parameter; USD-M
+    /// futures don't take it, so timeframes it moves are refused there.
     pub async fn kline_history(
         &self,
+        market: Market,
         pair: &Pair,
         timeframe: Timeframe,
+        offset: UtcOffset,
         start: Timestamp,
         limit: u16,
     ) -> Result<Vec<Kline>> {
         let limit = limit.clamp(1, BINANCE_MAX_LIMIT);
-        self.rate_limiter.acquire().await;
+        let interval = BinanceInterval::try_from(timeframe)?;
+        let url = match market {
+            Market::Spot => format!("{}/api/v3/klines", self.base_url),
+            Market::UsdmFutures => format!("{}/fapi/v1/klines", self.futures_base_url),
+        };
+
+        let start = start.0.to_string();
+        let limit = limit.to_string();
+        let time_zone = offset.to_string();
+        let mut query = vec![
+            ("symbol", pair.0.as_str()),
+            ("interval", interval.as_str()),
+            ("startTime", start.as_str()),
+            ("limit", limit.as_str()),
+        ];
+        if offset.shifts(timeframe) {
+            if market.is_futures() {
+                return Err(GlobalError::Other(format!(
+                    "{timeframe} klines at UTC{offset} not available for futures"
+                )));
+            }
+            query.push(("timeZone", time_zone.as_str()));
+        }
+
+        let body = self.get_text(url, &query).await?;
+
+        parse_klines(&body)
+    }
+
+    /// Fetch the current funding rate of every USD-M perpetual in a single call.
+    pub async fn premium_index(&self) -> Result<Vec<(Pair, FundingRate)>> {
+        let url = format!("{}/fapi/v1/premiumIndex", self.futures_base_url);
+        let entries: Vec<BinancePremiumIndex> = self.get_json(url, &[]).await?;
+
+        entries
+            .into_iter()
+            .map(|raw| raw.try_into_funding())
+            .collect()
+    }
+
+    /// Fetch open interest history for a USD-M perpetual, oldest sample first.
+    pub async fn open_interest_history(
+        &self,
+        pair: &Pair,
+        period: Timeframe,
+        limit: u16,
+    ) -> Result<Vec<OpenInterest>> {
+        let limit = limit.clamp(1, BINANCE_MAX_OI_LIMIT);
+        let interval = BinanceInterval::try_from(period)?;
+        let url = format!("{}/futures/data/openInterestHist", self.futures_base_url);
+
+        let samples: Vec<BinanceOpenInterest> = self
+            .get_json(
+                url,
+                &[
+                    ("symbol", pair.0.as_str()),
+                    ("period", interval.as_str()),
+                    ("limit", &limit.to_string()),
+                ],
+            )
+            .await?;
+
+        samples
+            .into_iter()
+            .map(|raw| raw.try_into_open_interest())
+            .collect()
+    }
+
+    async fn get_json<T: DeserializeOwned>(
+        &self,
+        url: String,
+        query: &[(&str, &str)],
+    ) -> Result<T> {
+        let body = self.get_text(url, query).await?;
+        serde_json::from_str(&body)
+            .map_err(|e| GlobalError::Other(format!("binance response decode failed: {e}")))
+    }
 
-        let interval: BinanceInterval = timeframe.into();
-        let url = format!("{}/api/v3/klines", self.base_url);
+    async fn get_text(&self, url: String, query: &[(&str, &str)]) -> Result<String> {
+        self.rate_limiter.acquire().await;
 
         let response = self
             .client
             .get(url)
-            .query(&[
-                ("symbol", pair.0.as_str()),
-                ("interval", interval.as_str()),
-                ("startTime", &start.0.to_string()),
-                ("limit", &limit.to_string()),
-            ])
+            .query(query)
             .send()
             .await
             .map_err(|e| GlobalError::Other(format!("binance request failed: {e}")))?;
@@ -62,21 +155,17 @@ impl BinanceRest {
             .error_for_status()
             .map_err(|e| GlobalError::Other(format!("binance http error: {e}")))?;
 
-        let body = response
+        response
             .text()
             .await
-            .map_err(|e| GlobalError::Other(format!("binance response read failed: {e}")))?;
-
-        let klines: Vec<BinanceKline> = serde_json::from_str(&body)
-            .map_err(|e| GlobalError::Other(format!("binance response decode failed: {e}")))?;
-
-        klines.into_iter().map(|raw| raw.try_into_kline()).collect()
+            .map_err(|e| GlobalError::Other(format!("binance response read failed: {e}")))
     }
 }
 
 #[derive(Debug)]
 pub struct BinanceRestBuilder {
     base_url: String,
+    futures_base_url: String,
     rate_limit_per_minute: usize,
 }
 
@@ -84,6 +173,7 @@ impl Default for BinanceRestBuilder {
     fn default() -> Self {
         Self {
             base_url: BINANCE_API_BASE.to_string(),
+            futures_base_url: BINANCE_FUTURES_API_BASE.to_string(),
             rate_limit_per_minute: BINANCE_RATE_LIMIT_PER_MIN,
         }
     }
@@ -95,6 +185,11 @@ impl BinanceRestBuilder {
         self
     }
 
+    pub fn futures_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.futures_base_url = base_url.into();
+        self
+    }
+
     pub fn rate_limit_per_minute(mut self, max: usize) -> Self {
         self.rate_limit_per_minute = max.max(1);
         self
@@ -104,6 +199,7 @@ impl BinanceRestBuilder {
         BinanceRest {
             client: Client::new(),
             base_url: self.base_url,
+            futures_base_url: self.futures_base_url,
             rate_limiter: RateLimiter::per_minute(self.rate_limit_per_minute),
         }
     }
@@ -119,9 +215,14 @@ impl BinanceInterval {
     }
 }
 
-impl From<Timeframe> for BinanceInterval {
-    fn from(tf: Timeframe) -> Self {
-        Self(tf.to_string().to_owned())
+impl TryFrom<Timeframe> for BinanceInterval {
+    type Error = GlobalError;
+
+    /// Custom timeframes have no Binance interval; they are resampled instead.
+    fn try_from(tf: Timeframe) -> Result<Self> {
+        tf.binance_interval()
+            .map(|label| Self(label.to_owned()))
+            .ok_or_else(|| GlobalError::Other(format!("{tf} is not a Binance interval")))
     }
 }
 
@@ -187,7 +288,7 @@ struct BinanceKline(
     String, // volume
     i64,    // close time
     String, // quote asset volume
-    i64,    // number of trades
+    u64,    // number of trades
     String, // taker buy base asset volume
     String, // taker buy quote asset volume
     String, // ignore
@@ -196,17 +297,94 @@ struct BinanceKline(
 impl BinanceKline {
     fn try_into_kline(self) -> Result<Kline> {
         Ok(Kline {
-            open: parse_f64(&self.1, "open")?,
-            high: parse_f64(&self.2, "high")?,
-            low: parse_f64(&self.3, "low")?,
-            close: parse_f64(&self.4, "close")?,
-            volume: parse_f64(&self.5, "volume")?,
+            open: parse_decimal(&self.1, "open")?,
+            high: parse_decimal(&self.2, "high")?,
+            low: parse_decimal(&self.3, "low")?,
+            close: parse_decimal(&self.4, "close")?,
+            volume: parse_decimal(&self.5, "volume")?,
+            quote_volume: parse_decimal(&self.7, "quote asset volume")?,
+            trades: self.8,
+            taker_buy_volume: parse_decimal(&self.9, "taker buy base asset volume")?,
+            taker_buy_quote_volume: parse_decimal(&self.10, "taker buy quote asset volume")?,
             open_time: self.0,
             closed: true,
         })
     }
 }
 
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BinancePremiumIndex {
+    symbol: String,
+    last_funding_rate: String,
+}
+
+impl BinancePremiumIndex {
+    fn try_into_funding(self) -> Result<(Pair, FundingRate)> {
+        // Delivery contracts report an empty funding rate; treat them as flat.
+        let rate = if self.last_funding_rate.is_empty() {
+            0.0
+        } else {
+            parse_f64(&self.last_funding_rate, "lastFundingRate")?
+        };
+
+        Ok((Pair(self.symbol), FundingRate { rate }))
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BinanceOpenInterest {
+    sum_open_interest: String,
+    timestamp: BinanceTimestamp,
+}
+
+impl BinanceOpenInterest {
+    fn try_into_open_interest(self) -> Result<OpenInterest> {
+        Ok(OpenInterest {
+            open_interest: parse_f64(&self.sum_open_interest, "sumOpenInterest")?,
+            timestamp: self.timestamp.as_millis()?,
+        })
+    }
+}
+
+/// Futures data endpoints document timestamps as strings but return numbers at times.
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum BinanceTimestamp {
+    Number(i64),
+    Text(String),
+}
+
+impl BinanceTimestamp {
+    fn as_millis(&self) -> Result<i64> {
+        match self {
+            BinanceTimestamp::Number(ms) => Ok(*ms),
+            BinanceTimestamp::Text(raw) => raw
+                .parse::<i64>()
+                .map_err(|e| GlobalError::Other(format!("failed to parse timestamp: {e}"))),
+        }
+    }
+}
+
+/// Decode a raw `/klines` response body.
+pub(crate) fn parse_klines(body: &str) -> Result<Vec<Kline>> {
+    let klines: Vec<BinanceKline> = serde_json::from_str(body)
+        .map_err(|e| GlobalError::Other(format!("binance response decode failed: {e}")))?;
+
+    klines.into_iter().map(|raw| raw.try_into_kline()).collect()
+}
+
+/// Parse a price or volume string exactly as quoted.
+pub(crate) fn parse_decimal<T>(value: &str, field: &str) -> Result<T>
+where
+    T: FromStr<Err = rust_decimal::Error>,
+{
+    value
+        .parse::<T>()
+        .map_err(|e| GlobalError::Other(format!("failed to parse {field}: {e}")))
+}
+
 fn parse_f64(value: &str, field: &str) -> Result<f64> {
     value
         .parse::<f64>()
@@ -230,8 +408,11 @@ mod tests {
 
     #[test]
     fn timeframe_translation_matches_binance() {
-        let tf = BinanceInterval::from(Timeframe::M15);
+        let tf = BinanceInterval::try_from(Timeframe::M15).unwrap();
         assert_eq!(tf.as_str(), "15m");
+
+        let custom = Timeframe::from_label("90m").unwrap();
+        assert!(BinanceInterval::try_from(custom).is_err());
     }
 
     #[test]
@@ -244,16 +425,51 @@ mod tests {
             "1.5".to_string(),
             "10.0".to_string(),
             1_700_000_060_000,
-            "0".to_string(),
-            0,
-            "0".to_string(),
-            "0".to_string(),
+            "15.0".to_string(),
+            42,
+            "4.0".to_string(),
+            "6.0".to_string(),
             "0".to_string(),
         );
 
         let kline = raw.try_into_kline().expect("should parse");
         assert_eq!(kline.open_time, 1_700_000_000_000);
-        assert_eq!(kline.close, 1.5);
+        assert_eq!(kline.close.to_string(), "1.5");
+        assert_eq!(kline.quote_volume.to_string(), "15.0");
+        assert_eq!(kline.trades, 42);
+        assert_eq!(kline.taker_buy_volume.to_string(), "4.0");
+        assert_eq!(kline.taker_buy_quote_volume.to_string(), "6.0");
+        assert_eq!(kline.taker_buy_ratio(), Some(0.4));
         assert!(kline.closed);
     }
+
+    #[test]
+    fn parses_futures_payloads() {
+        let premium: Vec<BinancePremiumIndex> = serde_json::from_str(
+            r#"[{"symbol":"BTCUSDT","markPrice":"11793.63","lastFundingRate":"0.00038246","nextFundingTime":1597392000000,"time":1597370495002}]"#,
+        )
+        .expect("premium index should decode");
+        let (pair, funding) = premium
+            .into_iter()
+            .next()
+            .unwrap()
+            .try_into_funding()
+            .expect("should parse");
+        assert_eq!(pair.0, "BTCUSDT");
+        assert_eq!(funding.rate, 0.00038246);
+
+        let samples: Vec<BinanceOpenInterest> = serde_json::from_str(
+            r#"[{"symbol":"BTCUSDT","sumOpenInterest":"20403.637","sumOpenInterestValue":"150570784.07","timestamp":"1583127900000"},
+                {"symbol":"BTCUSDT","sumOpenInterest":"20401.36","sumOpenInterestValue":"149940752.14","timestamp":1583128200000}]"#,
+        )
+        .expect("open interest should decode");
+        let parsed = samples
+            .into_iter()
+            .map(|raw| raw.try_into_open_interest())
+            .collect::<Result<Vec<_>>>()
+            .expect("should parse");
+        assert_eq!(parsed[0].timestamp, 1_583_127_900_000);
+        assert_eq!(parsed[1].timestamp, 1_583_128_200_000);
+        assert_eq!(parsed[1].open_interest, 20401.36);
+    }
 }
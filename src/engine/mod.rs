@@ -1,16 +1,23 @@
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeSet, HashMap, HashSet};
 use std::time::Duration;
 
 use log::{debug, info};
 use tokio::sync::mpsc::Receiver;
 use tokio::time::Interval;
 
-use crate::message_bus::{HistoryMessage, HistoryTx, IndicatorValue, UiMessage, UiTx};
+use crate::message_bus::{
+    HistoryMessage, HistoryTx, IndicatorValue, UiMessage, UiTx, WarmupProgress,
+};
 use crate::{
     error::{GlobalError, Result},
-    indicators::{IndicatorManager, IndicatorName, IndicatorResult},
-    message_bus::{EngineMessage, EngineRx, KlineEvent, KlineHist, RebootEvent, WarmUpEvent},
-    types::{AppConfig, KlineSource, Pair, Timeframe, config::IndexLookup},
+    indicators::{Feed, IndicatorId, IndicatorManager, funding_percent, open_interest_change},
+    message_bus::{
+        DerivativesEvent, EngineMessage, EngineRx, KlineEvent, KlineHist, RebootEvent, WarmUpEvent,
+    },
+    types::{
+        AppConfig, KlineSource, Pair, Timeframe, UtcOffset,
+        config::{FUNDING_TIMEFRAME, IndexLookup},
+    },
 };
 
 /// Core engine that consumes websocket events and maintains indicator state.
@@ -23,6 +30,8 @@ pub struct Engine {
     warmup_done: bool,
     indicators: IndicatorManager,
     pending_results: Vec<(usize, IndicatorValue)>,
+    // Cells whose latest reading marks an event, so each event is logged once.
+    alerting: HashSet<(Pair, Timeframe, IndicatorId)>,
     flush_interval: Interval,
 }
 
@@ -35,8 +44,9 @@ impl Engine {
             history_tx,
             warmup_pending: None,
             warmup_done: false,
-            indicators: IndicatorManager::new(),
+            indicators: IndicatorManager::new(UtcOffset::UTC),
             pending_results: Vec::new(),
+            alerting: HashSet::new(),
             flush_interval: tokio::time::interval(Duration::from_secs(2)),
         }
     }
@@ -57,33 +67,29 @@ impl Engine {
     }
 
     fn push_result(
-        &mut self,
+        pending: &mut Vec<(usize, IndicatorValue)>,
         index_lookup: &IndexLookup,
         pair: &Pair,
-        indicator: IndicatorName,
         timeframe: Timeframe,
-        value: f32,
+        value: IndicatorValue,
     ) {
-        let indicator_key = match indicator {
-            IndicatorName::Volatility => crate::types::config::IndicatorKey::Volatility,
-            IndicatorName::Rsi => crate::types::config::IndicatorKey::Rsi,
-        };
-
-        if let Some(idx) = index_lookup.index(&pair.0, indicator_key, timeframe) {
-            let val = match indicator {
-                IndicatorName::Volatility => IndicatorValue::Volatility(value),
-                IndicatorName::Rsi => IndicatorValue::Rsi(value),
-            };
-            self.pending_results.push((idx, val));
+        if let Some(idx) = index_lookup.index(&pair.0, value.indicator, timeframe) {
+            pending.push((idx, value));
         }
     }
     async fn send_warmup(&self, pair: Pair) -> Result<()> {
-        let warmup = WarmUpEvent::new(pair);
+        let warmup = WarmUpEvent::new(pair.clone());
         self.history_tx
             .send(HistoryMessage::WarmUp(warmup))
             .await
             .map_err(|e| GlobalError::Other(format!("history warmup send failed: {e}")))?;
-        Ok(())
+        self.send_progress(WarmupProgress::Requested(pair)).await
+    }
+    async fn send_progress(&self, progress: WarmupProgress) -> Result<()> {
+        self.ui_tx
+            .send(UiMessage::WarmupProgress(progress))
+            .await
+            .map_err(|e| GlobalError::Other(format!("ui send failed: {e}")))
     }
     pub async fn run(mut self) -> Result<()> {
         loop {
@@ -124,7 +130,20 @@ impl Engine {
 
                             self.handle_kline(event);
                         }
-                        EngineMessage::KHistBundle(event) => self.handle_khist_bundle(event),
+                        EngineMessage::KHistBundle(event) => {
+                            if !self.is_current_bundle(&event) {
+                                // Fetched for an older config; warm the pair up again
+                                // if this one still has it.
+                                if let Some(pair) = self.configured_pair(&event) {
+                                    self.send_warmup(pair).await?;
+                                }
+                                continue;
+                            }
+                            if let Some(pair) = self.handle_khist_bundle(event) {
+                                self.send_progress(WarmupProgress::Ready(pair)).await?;
+                            }
+                        }
+                        EngineMessage::Derivatives(event) => self.handle_derivatives(event),
                         EngineMessage::Config(config) => {
                             self.config = Some(config.clone());
                             let reboot_event = RebootEvent {
@@ -139,60 +158,119 @@ impl Engine {
     }
 
     fn handle_reboot(&mut self, event: RebootEvent) {
-        self.indicators = IndicatorManager::new();
-        self.warmup_pending = if let Some(config) = self.config.as_mut() {
-            Some(config.pairs().iter().cloned().map(Pair).collect())
-        } else {
-            None
-        };
+        let utc_offset = self
+            .config
+            .as_ref()
+            .map_or(UtcOffset::UTC, AppConfig::utc_offset);
+        self.indicators = IndicatorManager::new(utc_offset);
+        self.alerting.clear();
+        self.warmup_pending = self
+            .config
+            .as_ref()
+            .map(|config| config.pairs().iter().cloned().map(Pair).collect());
         self.warmup_done = false;
         info!("engine reset after reboot: {}", event.reason);
     }
 
     fn handle_kline(&mut self, event: KlineEvent) {
         // info!("Kline event = {:?}", event);
-        let (index_lookup, rsi_enabled, rsi_timeframes, vol_enabled, vol_timeframes) = {
-            let Some(config) = self.config.as_ref() else {
-                return;
-            };
-            (
-                config.index_lookup().clone(),
-                config.indicators().rsi().enabled(),
-                config.indicators().rsi().timeframes().to_vec(),
-                config.indicators().volatility().enabled(),
-                config.indicators().volatility().timeframes().to_vec(),
-            )
+        let Some(config) = self.config.as_ref() else {
+            return;
         };
         let pair = event.pair;
 
-        if rsi_enabled {
-            for tf in &rsi_timeframes {
-                if let Some(val) = self
-                    .indicators
-                    .update(&pair, &tf, IndicatorName::Rsi, &event.bar)
-                    .into_rsi_value()
-                {
-                    self.push_result(&index_lookup, &pair, IndicatorName::Rsi, *tf, val);
-                }
-            }
+        // Aggregate each timeframe once; every indicator on it reads the same bars.
+        let timeframes: BTreeSet<Timeframe> = config
+            .indicators()
+            .iter()
+            .filter(|setup| matches!(setup.spec().feed, Feed::Bars(_)))
+            .flat_map(|setup| setup.timeframes().iter().copied())
+            .collect();
+        for tf in &timeframes {
+            self.indicators.push_bar(&pair, tf, &event.bar);
         }
 
-        if vol_enabled {
-            for tf in &vol_timeframes {
-                if let Some(val) = self
-                    .indicators
-                    .update(&pair, &tf, IndicatorName::Volatility, &event.bar)
-                    .into_volatility_value()
-                {
-                    self.push_result(&index_lookup, &pair, IndicatorName::Volatility, *tf, val);
+        for setup in config.indicators() {
+            for &tf in setup.timeframes() {
+                if let Some(reading) = self.indicators.update(&pair, tf, setup) {
+                    let key = (pair.clone(), tf, setup.id());
+                    match setup.spec().event(reading) {
+                        Some(event) => {
+                            if self.alerting.insert(key) {
+                                info!(target: "alerts", "{} {} {}: {event}", pair.0, tf, setup.spec().label);
+                            }
+                        }
+                        None => {
+                            self.alerting.remove(&key);
+                        }
+                    }
+                    Self::push_result(
+                        &mut self.pending_results,
+                        config.index_lookup(),
+                        &pair,
+                        tf,
+                        IndicatorValue::new(setup.id(), reading),
+                    );
                 }
             }
         }
     }
-    fn handle_khist_bundle(&mut self, event: Vec<KlineHist>) {
-        // todo!("Implement kline history handlers");
+    /// Whether a warmup bundle was fetched for the current config.
+    fn is_current_bundle(&self, event: &[KlineHist]) -> bool {
+        let generation = self.config.as_ref().map(AppConfig::generation);
+        event
+            .iter()
+            .all(|khist| Some(khist.generation) == generation)
+    }
+
+    /// The pair a warmup bundle belongs to, if the current config has it.
+    fn configured_pair(&self, event: &[KlineHist]) -> Option<Pair> {
+        let config = self.config.as_ref()?;
+        let pair = &event.first()?.pair;
+        config.pairs().contains(&pair.0).then(|| pair.clone())
+    }
+
+    /// Seed indicators from a warmup bundle; returns the pair it belongs to.
+    fn handle_khist_bundle(&mut self, event: Vec<KlineHist>) -> Option<Pair> {
+        let pair = event.first().map(|khist| khist.pair.clone());
         for khist in event {
             self.indicators.update_khist(khist);
         }
+        pair
+    }
+
+    fn handle_derivatives(&mut self, events: Vec<DerivativesEvent>) {
+        let Some(config) = self.config.as_ref() else {
+            return;
+        };
+        let index_lookup = config.index_lookup();
+        let funding_enabled = config.indicator(IndicatorId::FUNDING).is_some();
+        let oi_timeframes = config
+            .indicator(IndicatorId::OPEN_INTEREST)
+            .map_or(&[][..], |setup| setup.timeframes());
+
+        for event in events {
+            if funding_enabled && let Some(funding) = event.funding.as_ref() {
+                Self::push_result(
+                    &mut self.pending_results,
+                    index_lookup,
+                    &event.pair,
+                    FUNDING_TIMEFRAME,
+                    IndicatorValue::new(IndicatorId::FUNDING, funding_percent(funding)),
+                );
+            }
+
+            for &tf in oi_timeframes {
+                if let Some(change) = open_interest_change(&event.open_interest, tf) {
+                    Self::push_result(
+                        &mut self.pending_results,
+                        index_lookup,
+                        &event.pair,
+                        tf,
+                        IndicatorValue::new(IndicatorId::OPEN_INTEREST, change),
+                    );
+                }
+            }
+        }
     }
 }
@@ -0,0 +1,289 @@
+use std::{
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+use log::info;
+use serde_json::{Value, json};
+
+use crate::{
+    error::{GlobalError, Result},
+    history::fetch_range,
+    klinestore::KlineStore,
+    time::{now_millis, parse_utc_millis},
+    types::{Kline, Market, Pair, Timeframe, UtcOffset},
+};
+
+/// Name of the export subcommand on the command line.
+pub const EXPORT_KLINES: &str = "export-klines";
+
+const USAGE: &str = "usage: the_grid export-klines --pair BTCUSDT --tf 1h --from 2024-01-01 \
+[--to 2024-02-01] [--market spot|usdm] [--utc-offset +09:00] [--format csv|jsonl|columnar] \
+[--out FILE]";
+
+/// Output layout of an export.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// One header line, then one row per bar.
+    Csv,
+    /// One JSON object per bar and line.
+    Jsonl,
+    /// A typed schema plus one array per column; every column maps onto a
+    /// Parquet type (INT64, BOOLEAN, and DECIMAL written as exact strings).
+    Columnar,
+}
+
+impl ExportFormat {
+    fn from_label(label: &str) -> Option<Self> {
+        match label {
+            "csv" => Some(ExportFormat::Csv),
+            "jsonl" => Some(ExportFormat::Jsonl),
+            "columnar" => Some(ExportFormat::Columnar),
+            _ => None,
+        }
+    }
+
+    /// Guess from the output file name; CSV when nothing matches.
+    fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("jsonl") => ExportFormat::Jsonl,
+            Some("json") => ExportFormat::Columnar,
+            _ => ExportFormat::Csv,
+        }
+    }
+}
+
+/// Arguments of `the_grid export-klines`.
+#[derive(Debug, Clone)]
+pub struct ExportArgs {
+    market: Market,
+    pair: Pair,
+    tf: Timeframe,
+    utc_offset: UtcOffset,
+    from: i64,
+    to: i64,
+    format: ExportFormat,
+    out: Option<PathBuf>,
+}
+
+impl ExportArgs {
+    /// Parse the arguments following the subcommand name.
+    pub fn parse(args: &[String]) -> Result<Self> {
+        let mut market = Market::Spot;
+        let mut pair = None;
+        let mut tf = None;
+        let mut utc_offset = UtcOffset::UTC;
+        let mut from = None;
+        let mut to = None;
+        let mut format = None;
+        let mut out = None;
+
+        let mut args = args.iter();
+        while let Some(flag) = args.next() {
+            let value = args
+                .next()
+                .ok_or_else(|| usage_error(format!("missing value for {flag}")))?;
+            match flag.as_str() {
+                "--pair" => pair = Some(Pair(value.to_ascii_uppercase())),
+                "--tf" => {
+                    tf = Some(
+                        Timeframe::from_label(value)
+                            .ok_or_else(|| usage_error(format!("unknown timeframe {value}")))?,
+                    )
+                }
+                "--utc-offset" => {
+                    utc_offset = UtcOffset::from_label(value)
+                        .ok_or_else(|| usage_error(format!("invalid UTC offset {value}")))?
+                }
+                "--from" => from = Some(parse_time(value)?),
+                "--to" => to = Some(parse_time(value)?),
+                "--market" => {
+                    market = match value.as_str() {
+                        "spot" => Market::Spot,
+                        "usdm" => Market::UsdmFutures,
+                        _ => return Err(usage_error(format!("unknown market {value}"))),
+                    }
+                }
+                "--format" => {
+                    format = Some(
+                        ExportFormat::from_label(value)
+                            .ok_or_else(|| usage_error(format!("unknown format {value}")))?,
+                    )
+                }
+                "--out" => out = Some(PathBuf::from(value)),
+                _ => return Err(usage_error(format!("unknown flag {flag}"))),
+            }
+        }
+
+        let pair = pair.ok_or_else(|| usage_error("--pair is required".into()))?;
+        let tf = tf.ok_or_else(|| usage_error("--tf is required".into()))?;
+        let from = from.ok_or_else(|| usage_error("--from is required".into()))?;
+        let to = to.unwrap_or_else(now_millis);
+        if to <= from {
+            return Err(usage_error("--to must be after --from".into()));
+        }
+        let format = format.unwrap_or_else(|| {
+            out.as_deref()
+                .map_or(ExportFormat::Csv, ExportFormat::from_path)
+        });
+
+        Ok(Self {
+            market,
+            pair,
+            tf,
+            utc_offset,
+            from,
+            to,
+            format,
+            out,
+        })
+    }
+}
+
+/// Fetch the requested bars through `store` and write them out.
+///
+/// Bars come from the same store and paging the warmup uses, so they match what
+/// the grid was seeded with. A bar still forming at `to` is marked not closed.
+pub async fn export_klines(args: &ExportArgs, store: &dyn KlineStore) -> Result<()> {
+    let (tf, offset) = (args.tf, args.utc_offset);
+    let mut bars = fetch_range(
+        store,
+        args.market,
+        &args.pair,
+        tf,
+        offset,
+        args.from,
+        args.to,
+    )
+    .await?;
+    let now = now_millis();
+    for bar in &mut bars {
+        bar.closed = tf.next_open_in(bar.open_time, offset) <= now;
+    }
+    info!(
+        "exporting {} {} bars of {}",
+        bars.len(),
+        args.tf,
+        args.pair.0
+    );
+
+    let write_err = |e: io::Error| GlobalError::Other(format!("export write failed: {e}"));
+    let mut out: Box<dyn Write> = match &args.out {
+        Some(path) => Box::new(BufWriter::new(File::create(path).map_err(write_err)?)),
+        None => Box::new(BufWriter::new(io::stdout().lock())),
+    };
+    match args.format {
+        ExportFormat::Csv => write_csv(&mut out, &bars),
+        ExportFormat::Jsonl => write_jsonl(&mut out, &bars),
+        ExportFormat::Columnar => write_columnar(&mut out, &bars),
+    }
+    .and_then(|_| out.flush())
+    .map_err(write_err)
+}
+
+const COLUMNS: [(&str, &str); 11] = [
+    ("open_time", "INT64"),
+    ("open", "DECIMAL"),
+    ("high", "DECIMAL"),
+    ("low", "DECIMAL"),
+    ("close", "DECIMAL"),
+    ("volume", "DECIMAL"),
+    ("quote_volume", "DECIMAL"),
+    ("trades", "INT64"),
+    ("taker_buy_volume", "DECIMAL"),
+    ("taker_buy_quote_volume", "DECIMAL"),
+    ("closed", "BOOLEAN"),
+];
+
+// Prices and volumes are written exactly as the exchange quoted them.
+fn write_csv(out: &mut dyn Write, bars: &[Kline]) -> io::Result<()> {
+    let header: Vec<&str> = COLUMNS.iter().map(|(name, _)| *name).collect();
+    writeln!(out, "{}", header.join(","))?;
+    for bar in bars {
+        writeln!(
+            out,
+            "{},{},{},{},{},{},{},{},{},{},{}",
+            bar.open_time,
+            bar.open,
+            bar.high,
+            bar.low,
+            bar.close,
+            bar.volume,
+            bar.quote_volume,
+            bar.trades,
+            bar.taker_buy_volume,
+            bar.taker_buy_quote_volume,
+            bar.closed
+        )?;
+    }
+    Ok(())
+}
+
+fn write_jsonl(out: &mut dyn Write, bars: &[Kline]) -> io::Result<()> {
+    for bar in bars {
+        serde_json::to_writer(&mut *out, bar)?;
+        writeln!(out)?;
+    }
+    Ok(())
+}
+
+fn write_columnar(out: &mut dyn Write, bars: &[Kline]) -> io::Result<()> {
+    let column = |f: fn(&Kline) -> Value| Value::Array(bars.iter().map(f).collect());
+    let schema: Vec<Value> = COLUMNS
+        .iter()
+        .map(|(name, kind)| json!({ "name": name, "type": kind }))
+        .collect();
+    let table = json!({
+        "schema": schema,
+        "num_rows": bars.len(),
+        "columns": {
+            "open_time": column(|bar| json!(bar.open_time)),
+            "open": column(|bar| json!(bar.open)),
+            "high": column(|bar| json!(bar.high)),
+            "low": column(|bar| json!(bar.low)),
+            "close": column(|bar| json!(bar.close)),
+            "volume": column(|bar| json!(bar.volume)),
+            "quote_volume": column(|bar| json!(bar.quote_volume)),
+            "trades": column(|bar| json!(bar.trades)),
+            "taker_buy_volume": column(|bar| json!(bar.taker_buy_volume)),
+            "taker_buy_quote_volume": column(|bar| json!(bar.taker_buy_quote_volume)),
+            "closed": column(|bar| json!(bar.closed)),
+        },
+    });
+    serde_json::to_writer(&mut *out, &table)?;
+    writeln!(out)
+}
+
+fn parse_time(value: &str) -> Result<i64> {
+    parse_utc_millis(value).ok_or_else(|| usage_error(format!("invalid time {value}")))
+}
+
+fn usage_error(reason: String) -> GlobalError {
+    GlobalError::Other(format!("{reason}\n{USAGE}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ExportArgs, ExportFormat};
+    use crate::types::Timeframe;
+
+    fn args(raw: &str) -> Vec<String> {
+        raw.split_whitespace().map(str::to_string).collect()
+    }
+
+    #[test]
+    fn parses_export_args() {
+        let parsed = ExportArgs::parse(&args(
+            "--pair btcusdt --tf 1h --from 2024-01-01 --to 2024-01-02 --out bars.jsonl",
+        ))
+        .unwrap();
+        assert_eq!(parsed.pair.0, "BTCUSDT");
+        assert_eq!(parsed.tf, Timeframe::H1);
+        assert_eq!(parsed.to - parsed.from, 24 * 3_600_000);
+        assert_eq!(parsed.format, ExportFormat::Jsonl);
+
+        assert!(ExportArgs::parse(&args("--pair BTCUSDT --tf 2x --from 2024-01-01")).is_err());
+        assert!(ExportArgs::parse(&args("--tf 1h --from 2024-01-01")).is_err());
+    }
+}
@@ -1,133 +1,384 @@
-use std::collections::HashMap;
+mod resample;
+
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    time::Duration,
+};
 
 use log::{info, warn};
-use tokio::sync::mpsc::Receiver;
+use tokio::{
+    sync::mpsc::Receiver,
+    task::{Id, JoinHandle, JoinSet},
+    time::{MissedTickBehavior, interval},
+};
 
 use crate::{
     error::{GlobalError, Result},
-    indicators::IndicatorName,
-    klinestore,
-    message_bus::{EngineMessage, EngineTx, HistoryMessage, HistoryRx, KlineHist, WarmUpEvent},
+    indicators::{Anchor, BarAggregator, Feed, IndicatorId, Lookback, MinuteSpan},
+    klinestore::{KlineStore, SharedKlineStore},
+    message_bus::{
+        DerivativesEvent, EngineMessage, EngineTx, HistoryMessage, HistoryRx, KlineHist,
+        PriorityEvent, UiMessage, UiTx, WarmUpEvent, WarmupProgress,
+    },
     time::now_millis,
-    types::{AppConfig, Bar1m, Kline, Pair, Timeframe, Timestamp, config},
+    types::{AppConfig, Bar1m, Kline, Market, Pair, Timeframe, Timestamp, UtcOffset},
 };
+use resample::resample;
+
+const DERIVATIVES_POLL_INTERVAL: Duration = Duration::from_secs(60);
+/// Longest 1m lookback (ten REST pages) worth resampling; longer ones use native
+/// klines. With RSI's default seed (141 bars) that resamples up to 1h; 4h and 1d
+/// page through their own klines, which cost one request per 1,000 bars.
+const MAX_RESAMPLE_MINUTES: i64 = 10_000;
+/// Furthest back anchored 1m histories are fetched (31 days, 45 REST pages).
+const MAX_ANCHOR_MINUTES: usize = 31 * 24 * 60;
+const OPEN_INTEREST_PERIOD: Timeframe = Timeframe::M5;
 
 /// Service that listens for history requests and sends warmup data to the engine.
+///
+/// Warmups are queued and run by a bounded pool of jobs; pinned pairs go first,
+/// then pairs visible on the dashboard, then everything else in arrival order.
 pub struct HistoryService {
     config: Option<AppConfig>,
     rx: Receiver<HistoryMessage>,
     engine_tx: EngineTx,
+    ui_tx: UiTx,
+    store: SharedKlineStore,
+    derivatives_job: Option<JoinHandle<()>>,
+    warmup_queue: VecDeque<WarmUpEvent>,
+    warmup_jobs: JoinSet<()>,
+    warmup_running: HashMap<Id, WarmUpEvent>,
+    visible: HashSet<Pair>,
+    pinned: HashSet<Pair>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum WarmupPriority {
+    Normal,
+    Visible,
+    Pinned,
 }
 
 impl HistoryService {
-    pub fn new(rx: HistoryRx, engine_tx: EngineTx) -> Self {
+    pub fn new(rx: HistoryRx, engine_tx: EngineTx, ui_tx: UiTx, store: SharedKlineStore) -> Self {
         Self {
             config: None,
             rx: rx.into_inner(),
             engine_tx,
+            ui_tx,
+            store,
+            derivatives_job: None,
+            warmup_queue: VecDeque::new(),
+            warmup_jobs: JoinSet::new(),
+            warmup_running: HashMap::new(),
+            visible: HashSet::new(),
+            pinned: HashSet::new(),
         }
     }
 
     pub async fn run(mut self) -> Result<()> {
-        while let Some(message) = self.rx.recv().await {
-            match message {
-                HistoryMessage::WarmUp(event) => {
-                    let Some(config) = self.config.as_ref() else {
-                        return Ok(());
+        let mut derivatives_poll = interval(DERIVATIVES_POLL_INTERVAL);
+        derivatives_poll.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+        loop {
+            let message = tokio::select! {
+                _ = derivatives_poll.tick() => {
+                    self.poll_derivatives();
+                    continue;
+                }
+                Some(joined) = self.warmup_jobs.join_next_with_id(), if !self.warmup_jobs.is_empty() => {
+                    let id = match joined {
+                        Ok((id, ())) => id,
+                        Err(err) => err.id(),
                     };
-                    let config = config.clone();
-                    let engine_tx = self.engine_tx.clone();
-                    tokio::spawn(async move {
-                        if let Err(err) = WarmupJob::new(config, engine_tx).process(event).await {
-                            warn!("warmup processing failed: {err}");
-                        }
-                    });
+                    self.warmup_running.remove(&id);
+                    self.dispatch_warmups();
+                    continue;
                 }
+                message = self.rx.recv() => message,
+            };
+            let Some(message) = message else {
+                break;
+            };
+
+            match message {
+                HistoryMessage::WarmUp(event) => self.queue_warmup(event),
+                HistoryMessage::Priority(event) => self.set_priority(event),
                 HistoryMessage::Config(config) => {
                     info!("history config updated");
+                    // Running jobs fetch for the previous config; they start over
+                    // under the new one. Queued and running pairs still in the new
+                    // config stay, since the engine may already have asked for them.
+                    self.warmup_jobs.abort_all();
+                    for (_, event) in self.warmup_running.drain() {
+                        if !self
+                            .warmup_queue
+                            .iter()
+                            .any(|queued| queued.pair == event.pair)
+                        {
+                            self.warmup_queue.push_front(event);
+                        }
+                    }
+                    self.warmup_queue
+                        .retain(|event| config.pairs().contains(&event.pair.0));
+                    self.pinned = config.pinned_pairs().iter().cloned().map(Pair).collect();
                     self.config = Some(config.clone());
+                    if let Some(job) = self.derivatives_job.take() {
+                        job.abort();
+                    }
+                    derivatives_poll.reset_immediately();
                 }
             }
+            self.dispatch_warmups();
         }
 
         info!("history receiver closed; shutting down");
         Ok(())
     }
+
+    /// Queue `event` unless its pair is already queued or warming up.
+    fn queue_warmup(&mut self, event: WarmUpEvent) {
+        if !self
+            .warmup_queue
+            .iter()
+            .chain(self.warmup_running.values())
+            .any(|known| known.pair == event.pair)
+        {
+            self.warmup_queue.push_back(event);
+        }
+    }
+
+    fn set_priority(&mut self, event: PriorityEvent) {
+        self.visible = event.visible.into_iter().collect();
+        self.pinned = event.pinned.into_iter().collect();
+    }
+
+    fn priority(&self, pair: &Pair) -> WarmupPriority {
+        if self.pinned.contains(pair) {
+            WarmupPriority::Pinned
+        } else if self.visible.contains(pair) {
+            WarmupPriority::Visible
+        } else {
+            WarmupPriority::Normal
+        }
+    }
+
+    /// Start queued warmups, highest priority first, until the pool is full.
+    fn dispatch_warmups(&mut self) {
+        let Some(config) = self.config.as_ref() else {
+            return;
+        };
+        let concurrency = config.warmup_concurrency();
+
+        while self.warmup_jobs.len() < concurrency {
+            // The first entry wins ties, so equal priorities keep arrival order.
+            let next = self
+                .warmup_queue
+                .iter()
+                .enumerate()
+                .rev()
+                .max_by_key(|(_, event)| self.priority(&event.pair))
+                .map(|(idx, _)| idx);
+            let Some(event) = next.and_then(|idx| self.warmup_queue.remove(idx)) else {
+                break;
+            };
+
+            let job = WarmupJob::new(
+                config.clone(),
+                self.engine_tx.clone(),
+                self.ui_tx.clone(),
+                self.store.clone(),
+            );
+            let running = event.clone();
+            let handle = self.warmup_jobs.spawn(async move {
+                let pair = event.pair.clone();
+                if let Err(err) = job.process(event).await {
+                    warn!("warmup processing failed for {}: {err}", pair.0);
+                    let reason = err.to_string();
+                    job.send_progress(WarmupProgress::Failed { pair, reason })
+                        .await;
+                }
+            });
+            self.warmup_running.insert(handle.id(), running);
+        }
+    }
+
+    fn poll_derivatives(&mut self) {
+        let Some(config) = self.config.as_ref() else {
+            return;
+        };
+        let derivatives = [IndicatorId::FUNDING, IndicatorId::OPEN_INTEREST];
+        if !config.market().is_futures()
+            || !derivatives.iter().any(|&id| config.indicator(id).is_some())
+        {
+            return;
+        }
+        // Skip this tick if the previous poll is still paging through pairs.
+        if self
+            .derivatives_job
+            .as_ref()
+            .is_some_and(|job| !job.is_finished())
+        {
+            return;
+        }
+
+        let job = DerivativesJob::new(config.clone(), self.engine_tx.clone(), self.store.clone());
+        self.derivatives_job = Some(tokio::spawn(async move {
+            if let Err(err) = job.process().await {
+                warn!("derivatives poll failed: {err}");
+            }
+        }));
+    }
 }
 
 struct WarmupJob {
     config: AppConfig,
     engine_tx: EngineTx,
+    ui_tx: UiTx,
+    store: SharedKlineStore,
 }
 
 impl WarmupJob {
-    fn new(config: AppConfig, engine_tx: EngineTx) -> Self {
-        Self { config, engine_tx }
+    fn new(config: AppConfig, engine_tx: EngineTx, ui_tx: UiTx, store: SharedKlineStore) -> Self {
+        Self {
+            config,
+            engine_tx,
+            ui_tx,
+            store,
+        }
+    }
+
+    /// Progress is informational; a closed UI channel must not fail the warmup.
+    async fn send_progress(&self, progress: WarmupProgress) {
+        if let Err(err) = self.ui_tx.send(UiMessage::WarmupProgress(progress)).await {
+            warn!("warmup progress send failed: {err}");
+        }
     }
 
     async fn process(&self, event: WarmUpEvent) -> Result<()> {
         let pair = event.pair;
         let start_ts = event.start_ts;
 
-        let rsi_cfg = self.config.indicators().rsi().clone();
-        let vol_cfg = self.config.indicators().volatility().clone();
-
-        let base_tfs: Vec<Timeframe> = collect_timeframes(&rsi_cfg, &vol_cfg);
-        let base_tf = highest_timeframe(&base_tfs).unwrap_or(Timeframe::M1);
-        let now_ms = now_millis();
-        let current_1m_start = Timeframe::M1.nearest_ms(now_ms);
-        let base_start = current_1m_start
-            - (base_tf.window_minutes() + 100) as i64 * Timeframe::M1.window_millis();
+        let mut lookbacks: Vec<(IndicatorId, Timeframe, Lookback)> = Vec::new();
+        for setup in self.config.indicators() {
+            let Feed::Bars(feed) = &setup.spec().feed else {
+                continue;
+            };
+            for &tf in setup.timeframes() {
+                lookbacks.push((setup.id(), tf, (feed.lookback)(setup.params(), tf)));
+            }
+        }
 
-        let base_hist_1m = fetch_history(&pair, Timeframe::M1, base_start).await?;
+        let offset = self.config.utc_offset();
+        let current_1m_start = Timeframe::M1.nearest_ms(now_millis());
+        let base_minutes = lookbacks
+            .iter()
+            .map(|(_, _, lookback)| lookback.minutes)
+            .max()
+            .unwrap_or(1);
+        let mut base_start = minutes_start(current_1m_start, base_minutes);
+        for (_, _, lookback) in &lookbacks {
+            if let MinuteSpan::Since(anchor) = lookback.span {
+                base_start = base_start.min(anchor_start(anchor, current_1m_start, offset));
+            }
+        }
 
-        let mut bundle: Vec<KlineHist> = Vec::new();
+        // Bar histories short enough are resampled from the 1m history.
+        let resampled_tfs: Vec<Timeframe> = lookbacks
+            .iter()
+            .filter(|(_, tf, lookback)| resamples_from_1m(*tf, lookback))
+            .map(|(_, tf, _)| *tf)
+            .collect();
+        for (_, tf, lookback) in &lookbacks {
+            if resampled_tfs.contains(tf) {
+                base_start = base_start.min(bars_start(*tf, offset, start_ts, lookback.bars));
+            }
+        }
 
-        if vol_cfg.enabled() {
-            for tf in vol_cfg.timeframes().iter().copied() {
-                let now_ms = now_millis();
-                let nearest =
-                    now_ms - (tf.window_minutes() as i64 + 1) * Timeframe::M1.window_millis();
-                let truncated = truncate_from(&base_hist_1m, nearest);
-                bundle.push(KlineHist {
-                    pair: pair.clone(),
-                    indicator: IndicatorName::Volatility,
-                    indicator_tf: tf,
-                    hist_1m: truncated,
-                    hist_tf: Vec::new(),
-                });
+        // Timeframes longer than the 1m window start their forming bar from
+        // closed bars of a shorter timeframe; 1m history covers the rest.
+        let market = self.config.market();
+        let mut heads: HashMap<Timeframe, (i64, Option<Kline>)> = HashMap::new();
+        for (_, tf, _) in &lookbacks {
+            if BarAggregator::window_minutes(*tf) == tf.window_minutes() || heads.contains_key(tf) {
+                continue;
             }
+            let store = self.store.as_ref();
+            let (end, head) = fetch_head(store, market, &pair, *tf, offset, start_ts).await?;
+            base_start = base_start.min(end);
+            heads.insert(*tf, (end, head));
         }
 
-        if rsi_cfg.enabled() {
-            let mut rsi_histories = HashMap::new();
+        let base_hist_1m = fetch_history(
+            self.store.as_ref(),
+            market,
+            &pair,
+            Timeframe::M1,
+            offset,
+            base_start,
+        )
+        .await?;
 
-            for tf in rsi_cfg.timeframes().iter().copied() {
-                if tf == Timeframe::M1 {
-                    let nearest = tf.nearest_ms(start_ts);
-                    rsi_histories.insert(tf, truncate_from(&base_hist_1m, nearest));
-                } else {
-                    let start = tf.nearest_ms(start_ts.saturating_sub(tf.window_millis() * 500));
-                    let history = fetch_history(&pair, tf, start).await?;
-                    rsi_histories.insert(tf, history);
+        let mut bundle: Vec<KlineHist> = Vec::new();
+
+        for (indicator, tf, lookback) in lookbacks {
+            let head = heads.get(&tf).copied();
+            let hist_1m = match (head, lookback.span) {
+                (Some((end, _)), _) => truncate_from(&base_hist_1m, end),
+                (None, MinuteSpan::Forming) => {
+                    truncate_from(&base_hist_1m, tf.nearest_ms_in(start_ts, offset))
                 }
+                (None, MinuteSpan::Rolling) => truncate_from(
+                    &base_hist_1m,
+                    minutes_start(current_1m_start, lookback.minutes),
+                ),
+                (None, MinuteSpan::Since(anchor)) => truncate_from(
+                    &base_hist_1m,
+                    anchor_start(anchor, current_1m_start, offset),
+                ),
+            };
+
+            let mut hist_tf = Vec::new();
+            if lookback.bars > 0 {
+                let start = bars_start(tf, offset, start_ts, lookback.bars);
+                let resampled = resampled_tfs
+                    .contains(&tf)
+                    .then(|| {
+                        let bars = truncate_from(&base_hist_1m, start);
+                        resample(&bars, Timeframe::M1, tf, offset)
+                    })
+                    .filter(|bars| {
+                        // Bars before the one containing start_ts must all be there.
+                        let current = tf.nearest_ms_in(start_ts, offset);
+                        let expected = (current - start) / tf.window_millis();
+                        bars.len() as i64 >= expected
+                    });
+                hist_tf = match resampled {
+                    Some(bars) => bars,
+                    None => {
+                        let store = self.store.as_ref();
+                        fetch_history(store, market, &pair, tf, offset, start).await?
+                    }
+                };
             }
 
-            for tf in rsi_cfg.timeframes() {
-                let nearest = tf.nearest_ms(start_ts);
-                let base = truncate_from(&base_hist_1m, nearest);
-                let hist_tf = rsi_histories.get(tf).cloned().unwrap();
-                bundle.push(KlineHist {
-                    pair: pair.clone(),
-                    indicator: IndicatorName::Rsi,
-                    indicator_tf: *tf,
-                    hist_1m: base,
-                    hist_tf,
-                });
-            }
+            bundle.push(KlineHist {
+                generation: self.config.generation(),
+                pair: pair.clone(),
+                indicator,
+                indicator_tf: tf,
+                hist_1m,
+                hist_tf,
+                head: head.and_then(|(_, head)| head),
+            });
         }
 
-        if !bundle.is_empty() {
+        self.send_progress(WarmupProgress::Fetched(pair.clone()))
+            .await;
+        if bundle.is_empty() {
+            // Nothing for the engine to seed; the pair is ready as is.
+            self.send_progress(WarmupProgress::Ready(pair)).await;
+        } else {
             self.send_khist_bundle(bundle).await?;
         }
 
@@ -143,25 +394,115 @@ impl WarmupJob {
     }
 }
 
-fn collect_timeframes(rsi: &config::RsiConfig, vol: &config::VolatilityConfig) -> Vec<Timeframe> {
-    let mut frames = Vec::new();
+/// Polls funding and open interest for every configured perpetual.
+struct DerivativesJob {
+    config: AppConfig,
+    engine_tx: EngineTx,
+    store: SharedKlineStore,
+}
 
-    if rsi.enabled() {
-        frames.extend_from_slice(rsi.timeframes());
+impl DerivativesJob {
+    fn new(config: AppConfig, engine_tx: EngineTx, store: SharedKlineStore) -> Self {
+        Self {
+            config,
+            engine_tx,
+            store,
+        }
     }
 
-    if vol.enabled() {
-        frames.extend_from_slice(vol.timeframes());
+    async fn process(&self) -> Result<()> {
+        let funding_enabled = self.config.indicator(IndicatorId::FUNDING).is_some();
+        let oi_timeframes = self
+            .config
+            .indicator(IndicatorId::OPEN_INTEREST)
+            .map_or(&[][..], |setup| setup.timeframes());
+
+        let mut funding = if funding_enabled {
+            self.store
+                .funding_rates()
+                .await?
+                .into_iter()
+                .map(|(pair, rate)| (pair.0, rate))
+                .collect()
+        } else {
+            HashMap::new()
+        };
+
+        // One 5m series per pair covers the change over every enabled timeframe.
+        let oi_limit = oi_timeframes
+            .iter()
+            .map(|tf| tf.window_millis() / OPEN_INTEREST_PERIOD.window_millis() + 1)
+            .max()
+            .unwrap_or(0) as u16;
+
+        let mut events = Vec::with_capacity(self.config.pairs().len());
+        for pair in self.config.pairs() {
+            let pair = Pair(pair.clone());
+            let open_interest = if oi_limit > 0 {
+                match self
+                    .store
+                    .open_interest(&pair, OPEN_INTEREST_PERIOD, oi_limit)
+                    .await
+                {
+                    Ok(samples) => samples,
+                    Err(err) => {
+                        warn!("open interest fetch failed for {}: {err}", pair.0);
+                        Vec::new()
+                    }
+                }
+            } else {
+                Vec::new()
+            };
+
+            events.push(DerivativesEvent {
+                funding: funding.remove(&pair.0),
+                pair,
+                open_interest,
+            });
+        }
+
+        self.engine_tx
+            .send(EngineMessage::Derivatives(events))
+            .await
+            .map_err(|e| GlobalError::Other(format!("engine send failed: {e}")))
     }
+}
 
-    frames
+/// Open time of the first of `minutes` 1m bars ending at `current_1m_start`.
+fn minutes_start(current_1m_start: i64, minutes: usize) -> i64 {
+    current_1m_start - minutes.saturating_sub(1) as i64 * Timeframe::M1.window_millis()
 }
 
-fn highest_timeframe(timeframes: &[Timeframe]) -> Option<Timeframe> {
-    timeframes
-        .iter()
-        .copied()
-        .max_by_key(Timeframe::window_millis)
+/// Open of the latest `anchor`, at most [`MAX_ANCHOR_MINUTES`] back; a fixed
+/// anchor still ahead starts at the current minute. VWAP leaves its cell empty
+/// when a fixed anchor lies past that limit.
+fn anchor_start(anchor: Anchor, current_1m_start: i64, offset: UtcOffset) -> i64 {
+    let oldest = minutes_start(current_1m_start, MAX_ANCHOR_MINUTES);
+    anchor
+        .open_at(current_1m_start, offset)
+        .unwrap_or(current_1m_start)
+        .max(oldest)
+}
+
+/// Whether `lookback`'s `tf` bars are resampled from the 1m history rather
+/// than fetched as `tf` klines.
+fn resamples_from_1m(tf: Timeframe, lookback: &Lookback) -> bool {
+    lookback.bars > 0 && (tf.window_minutes() * lookback.bars) as i64 <= MAX_RESAMPLE_MINUTES
+}
+
+/// Open time of the first of `bars` closed tf bars before the one containing `start_ts`.
+fn bars_start(tf: Timeframe, offset: UtcOffset, start_ts: i64, bars: usize) -> i64 {
+    tf.nearest_ms_in(start_ts, offset) - tf.window_millis() * bars as i64
+}
+
+/// Exchange timeframe the start of a forming `tf` bar is folded from: it tiles
+/// `tf`, isn't moved by `offset`, and its own forming bar fits in the 1m window.
+fn head_base(tf: Timeframe, offset: UtcOffset) -> Timeframe {
+    let mut base = tf.tiling_base(offset);
+    while BarAggregator::window_minutes(base) < base.window_minutes() {
+        base = base.tiling_base(offset);
+    }
+    base
 }
 
 fn truncate_from(bars: &[Bar1m], start_ms: i64) -> Vec<Bar1m> {
@@ -171,33 +512,170 @@ fn truncate_from(bars: &[Bar1m], start_ms: i64) -> Vec<Bar1m> {
         .collect()
 }
 
-async fn fetch_history(pair: &Pair, tf: Timeframe, start_ms: i64) -> Result<Vec<Kline>> {
+async fn fetch_history(
+    store: &dyn KlineStore,
+    market: Market,
+    pair: &Pair,
+    tf: Timeframe,
+    offset: UtcOffset,
+    start_ms: i64,
+) -> Result<Vec<Kline>> {
+    fetch_range(store, market, pair, tf, offset, start_ms, now_millis()).await
+}
+
+/// The `tf` bar containing `start_ts`, folded from its open up to the open of
+/// the [`head_base`] bar containing `start_ts`, and that open.
+async fn fetch_head(
+    store: &dyn KlineStore,
+    market: Market,
+    pair: &Pair,
+    tf: Timeframe,
+    offset: UtcOffset,
+    start_ts: i64,
+) -> Result<(i64, Option<Kline>)> {
+    let base = head_base(tf, offset);
+    let (start, end) = (
+        tf.nearest_ms_in(start_ts, offset),
+        base.nearest_ms_in(start_ts, offset),
+    );
+    if start >= end {
+        return Ok((end, None));
+    }
+    let bars = fetch_range(store, market, pair, base, offset, start, end).await?;
+    Ok((end, bars.into_iter().reduce(|head, bar| head.merge(&bar))))
+}
+
+/// Page through `store` for the `tf` bars opening in `start_ms..end_ms`, with
+/// days starting at midnight in `offset`.
+///
+/// Custom timeframes, and futures timeframes the offset moves (the futures API
+/// has no `timeZone`), are resampled from their base exchange timeframe; only
+/// complete bars are returned for them.
+pub(crate) async fn fetch_range(
+    store: &dyn KlineStore,
+    market: Market,
+    pair: &Pair,
+    tf: Timeframe,
+    offset: UtcOffset,
+    start_ms: i64,
+    end_ms: i64,
+) -> Result<Vec<Kline>> {
+    if tf.is_custom() || (market.is_futures() && offset.shifts(tf)) {
+        let base = tf.tiling_base(offset);
+        let first = tf.nearest_ms_in(start_ms, offset);
+        let bars = Box::pin(fetch_range(
+            store, market, pair, base, offset, first, end_ms,
+        ))
+        .await?;
+        let mut history = resample(&bars, base, tf, offset);
+        history.retain(|bar| bar.open_time >= start_ms);
+        return Ok(history);
+    }
+
     const LIMIT: u16 = 1_000;
 
     let mut start = start_ms;
     let mut history = Vec::new();
-    let window = tf.window_millis();
 
     loop {
-        let mut batch = klinestore::history(pair, tf, Timestamp(start), LIMIT).await?;
+        let mut batch = store
+            .history(market, pair, tf, offset, Timestamp(start), LIMIT)
+            .await?;
         if batch.is_empty() {
             break;
         }
 
         let next_start = batch
             .last()
-            .map(|bar| bar.open_time.saturating_add(window))
+            .map(|bar| tf.next_open_in(bar.open_time, offset))
             .unwrap_or(start);
 
         history.append(&mut batch);
 
-        let now = now_millis();
-        if next_start >= now || next_start == start {
+        if next_start >= end_ms || next_start == start {
             break;
         }
 
         start = next_start;
     }
 
+    history.retain(|bar| bar.open_time < end_ms);
     Ok(history)
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::BTreeMap, future::pending, sync::Arc};
+
+    use super::{HistoryService, head_base, resamples_from_1m};
+    use crate::{
+        indicators::{Feed, IndicatorId, REGISTRY},
+        klinestore::MemoryStore,
+        message_bus::{EngineBus, HistoryBus, UiBus, WarmUpEvent},
+        types::{Pair, Timeframe, UtcOffset},
+    };
+
+    #[test]
+    fn default_rsi_resamples_up_to_an_hour() {
+        let spec = REGISTRY
+            .iter()
+            .find(|spec| spec.id == IndicatorId::RSI)
+            .unwrap();
+        let Feed::Bars(feed) = &spec.feed else {
+            panic!("RSI reads bars");
+        };
+        let params = spec.params(&BTreeMap::new());
+        let resampled: Vec<Timeframe> = Timeframe::ALL
+            .into_iter()
+            .filter(|&tf| resamples_from_1m(tf, &(feed.lookback)(&params, tf)))
+            .collect();
+        assert_eq!(
+            resampled,
+            [
+                Timeframe::M1,
+                Timeframe::M3,
+                Timeframe::M5,
+                Timeframe::M15,
+                Timeframe::M30,
+                Timeframe::H1,
+            ]
+        );
+    }
+
+    #[test]
+    fn heads_fold_bars_the_1m_window_covers() {
+        let utc = UtcOffset::UTC;
+        let tokyo = UtcOffset::from_label("+09:00").unwrap();
+        assert_eq!(head_base(Timeframe::Mo1, utc), Timeframe::D1);
+        assert_eq!(head_base(Timeframe::W1, tokyo), Timeframe::H1);
+        // 6d is tiled by 3d bars, which outlast the 1m window.
+        let six_days = Timeframe::from_label("6d").unwrap();
+        assert_eq!(head_base(six_days, utc), Timeframe::D1);
+    }
+
+    #[tokio::test]
+    async fn skips_pairs_already_warming_up() {
+        let (_, history_rx) = HistoryBus::builder().build().into_engine();
+        let (engine_tx, _engine_rx) = EngineBus::builder().build().into_engine();
+        let (ui_tx, _ui_rx) = UiBus::builder().build().into_engine();
+        let mut service =
+            HistoryService::new(history_rx, engine_tx, ui_tx, Arc::new(MemoryStore::new()));
+
+        let btc = Pair("BTCUSDT".into());
+        let eth = Pair("ETHUSDT".into());
+        let handle = service.warmup_jobs.spawn(pending());
+        service
+            .warmup_running
+            .insert(handle.id(), WarmUpEvent::new(btc.clone()));
+
+        service.queue_warmup(WarmUpEvent::new(btc));
+        service.queue_warmup(WarmUpEvent::new(eth.clone()));
+        service.queue_warmup(WarmUpEvent::new(eth.clone()));
+        let queued: Vec<&Pair> = service
+            .warmup_queue
+            .iter()
+            .map(|event| &event.pair)
+            .collect();
+        assert_eq!(queued, [&eth]);
+    }
+}
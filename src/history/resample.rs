@@ -0,0 +1,129 @@
+use crate::types::{Kline, Timeframe, UtcOffset};
+
+/// Build `tf` bars out of ascending `base` bars (1m unless `tf` is tiled by a
+/// longer exchange timeframe), with days starting at midnight in `offset`.
+///
+/// Only buckets with every base bar present are emitted, so each bar matches the
+/// exchange's native candle for that interval. Incomplete buckets (gaps, or the
+/// bucket still forming at the end of the input) are dropped.
+pub fn resample(bars: &[Kline], base: Timeframe, tf: Timeframe, offset: UtcOffset) -> Vec<Kline> {
+    let per_bucket = (tf.window_minutes() / base.window_minutes()).max(1);
+    let mut out = Vec::with_capacity(bars.len() / per_bucket + 1);
+
+    let mut current: Option<Kline> = None;
+    let mut contiguous = false;
+    let mut next_open = i64::MIN;
+
+    for bar in bars {
+        // Duplicates and out-of-order bars would double count a base bar.
+        if bar.open_time < next_open {
+            continue;
+        }
+
+        let bucket = tf.nearest_ms_in(bar.open_time, offset);
+        match current {
+            Some(agg) if agg.open_time == bucket => {
+                contiguous &= bar.open_time == next_open;
+                current = Some(agg.merge(bar));
+            }
+            _ => {
+                if let Some(agg) = current.take()
+                    && contiguous
+                    && next_open == tf.next_open_in(agg.open_time, offset)
+                {
+                    out.push(agg);
+                }
+                contiguous = bar.open_time == bucket;
+                current = Some(Kline {
+                    open_time: bucket,
+                    ..*bar
+                });
+            }
+        }
+        next_open = base.next_open_in(bar.open_time, offset);
+    }
+
+    if let Some(agg) = current
+        && contiguous
+        && next_open == tf.next_open_in(agg.open_time, offset)
+    {
+        out.push(agg);
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::resample;
+    use crate::adapters::binance::parse_klines;
+    use crate::types::{Kline, Timeframe, UtcOffset};
+
+    // BTCUSDT 2024-01-01 00:00..00:10 UTC, 1m and the matching native 5m candles.
+    const KLINES_1M: &str = r#"[
+        [1704067200000,"42283.58000000","42286.66000000","42272.02000000","42280.10000000","32.30217000",1704067259999,"1365795.18359280",997,"10.98274000","464370.45544160","0"],
+        [1704067260000,"42280.10000000","42295.91000000","42272.62000000","42293.99000000","29.44390000",1704067319999,"1245095.52427550",1037,"18.25522000","771959.30962490","0"],
+        [1704067320000,"42293.99000000","42294.75000000","42279.81000000","42281.57000000","23.18841000",1704067379999,"980586.38062980",2512,"7.88406000","333399.39478680","0"],
+        [1704067380000,"42281.57000000","42283.42000000","42260.00000000","42271.28000000","22.80562000",1704067439999,"964140.08350850",1042,"15.05171000","636332.48893675","0"],
+        [1704067440000,"42271.28000000","42275.85000000","42238.51000000","42251.42000000","31.31645000",1704067499999,"1323475.45420750",1053,"20.66886000","873493.92656100","0"],
+        [1704067500000,"42251.42000000","42277.50000000","42250.41000000","42269.38000000","14.27284000",1704067559999,"603175.92753600",990,"9.27735000","392064.52194000","0"],
+        [1704067560000,"42269.38000000","42275.31000000","42241.70000000","42250.28000000","11.05049000",1704067619999,"466991.82881670",1282,"7.29332000","308214.46333560","0"],
+        [1704067620000,"42250.28000000","42261.75000000","42231.59000000","42245.55000000","12.58021000",1704067679999,"531487.64276215",1222,"8.42874000","356096.69107710","0"],
+        [1704067680000,"42245.55000000","42275.42000000","42241.71000000","42262.34000000","20.61948000",1704067739999,"871254.37384860",1199,"13.40266000","566315.25849370","0"],
+        [1704067740000,"42262.34000000","42291.95000000","42250.79000000","42290.67000000","7.49985000",1704067799999,"317067.44602425",1643,"4.57491000","193411.20548955","0"]
+    ]"#;
+
+    const KLINES_5M: &str = r#"[
+        [1704067200000,"42283.58000000","42295.91000000","42238.51000000","42251.42000000","139.05655000",1704067499999,"5879092.62621410",6641,"72.84259000","3079555.57535105","0"],
+        [1704067500000,"42251.42000000","42291.95000000","42231.59000000","42290.67000000","66.02287000",1704067799999,"2789977.21898770",6336,"42.97698000","1816102.14033595","0"]
+    ]"#;
+
+    // Decimal sums are exact, so volumes match to the last quoted digit.
+    fn assert_same_candle(resampled: &Kline, native: &Kline) {
+        assert_eq!(resampled.open_time, native.open_time);
+        assert_eq!(resampled.open, native.open);
+        assert_eq!(resampled.high, native.high);
+        assert_eq!(resampled.low, native.low);
+        assert_eq!(resampled.close, native.close);
+        assert_eq!(resampled.trades, native.trades);
+        assert_eq!(resampled.volume, native.volume);
+        assert_eq!(resampled.quote_volume, native.quote_volume);
+        assert_eq!(resampled.taker_buy_volume, native.taker_buy_volume);
+        assert_eq!(
+            resampled.taker_buy_quote_volume,
+            native.taker_buy_quote_volume
+        );
+    }
+
+    #[test]
+    fn matches_native_candles() {
+        let bars_1m = parse_klines(KLINES_1M).unwrap();
+        let native = parse_klines(KLINES_5M).unwrap();
+
+        let resampled = resample(&bars_1m, Timeframe::M1, Timeframe::M5, UtcOffset::UTC);
+
+        assert_eq!(resampled.len(), native.len());
+        for (bar, native) in resampled.iter().zip(&native) {
+            assert_same_candle(bar, native);
+        }
+    }
+
+    #[test]
+    fn drops_incomplete_buckets() {
+        let mut bars_1m = parse_klines(KLINES_1M).unwrap();
+        bars_1m.remove(7);
+
+        let resampled = resample(&bars_1m, Timeframe::M1, Timeframe::M5, UtcOffset::UTC);
+        assert_eq!(resampled.len(), 1);
+
+        // Starting mid-bucket drops the first candle as well.
+        let resampled = resample(&bars_1m[2..], Timeframe::M1, Timeframe::M5, UtcOffset::UTC);
+        assert!(resampled.is_empty());
+
+        let native = parse_klines(KLINES_5M).unwrap();
+        let full = parse_klines(KLINES_1M).unwrap();
+        let resampled = resample(&full[1..], Timeframe::M1, Timeframe::M5, UtcOffset::UTC);
+        assert_eq!(resampled.len(), 1);
+        assert_same_candle(&resampled[0], &native[1]);
+    }
+}
@@ -0,0 +1,204 @@
+use crate::types::{Kline, Timeframe, Wilder, true_range};
+
+use super::{
+    aggregator::BarAggregator,
+    indicator::{BarIndicator, Direction, Lookback, MinuteSpan, Reading, Staged, shown},
+    registry::{
+        BarFeed, Feed, IndicatorId, IndicatorSpec, ParamKind, ParamSpec, Params, ThresholdSpec,
+        TimeframeSet,
+    },
+};
+
+const LENGTH: &str = "length";
+/// Lengths of closed bars fetched beyond the first, for the SMA seeds of the
+/// DI averages and of ADX itself to fade (about e^-9, like ATR's).
+const SEED_LENGTHS: usize = 10;
+
+/// Wilder's ADX, signed by the dominant directional indicator: `+32` is a
+/// trend of strength 32 with +DI above -DI. The threshold marks trending.
+pub const SPEC: IndicatorSpec = IndicatorSpec {
+    id: IndicatorId::ADX,
+    label: "ADX",
+    title: "ADX / DMI",
+    feed: Feed::Bars(BarFeed {
+        lookback: |params, tf| Adx::lookback(params, tf),
+        build: |params, _, _| Box::new(Staged::new(Adx::new(params))),
+    }),
+    timeframes: TimeframeSet::All,
+    default_enabled: false,
+    default_timeframes: &[Timeframe::H1, Timeframe::H4],
+    params: &[ParamSpec {
+        key: LENGTH,
+        label: "Length",
+        kind: ParamKind::Count,
+        default: 14.0,
+        min: 2.0,
+        max: 100.0,
+        step: 1.0,
+    }],
+    threshold: ThresholdSpec::Magnitude {
+        default: 25.0,
+        step: 1.0,
+        coarse_step: 5.0,
+        decimals: 0,
+    },
+    format: |value| format!("{:.0}", value.abs()),
+    width: 5,
+    event: None,
+};
+
+/// True range and directional movements of `bar` after `prev`.
+fn movement(bar: &Kline, prev: &Kline) -> (f64, f64, f64) {
+    let up_move = (bar.high - prev.high).to_f64();
+    let down_move = (prev.low - bar.low).to_f64();
+    let plus = if up_move > down_move && up_move > 0.0 {
+        up_move
+    } else {
+        0.0
+    };
+    let minus = if down_move > up_move && down_move > 0.0 {
+        down_move
+    } else {
+        0.0
+    };
+    (true_range(bar, prev.close), plus, minus)
+}
+
+/// +DI and -DI from the smoothed true range and movements.
+fn directional(true_range: f64, plus: f64, minus: f64) -> Option<(f64, f64)> {
+    (true_range > 0.0).then(|| (plus / true_range * 100.0, minus / true_range * 100.0))
+}
+
+fn dx(plus_di: f64, minus_di: f64) -> f64 {
+    let sum = plus_di + minus_di;
+    if sum > 0.0 {
+        (plus_di - minus_di).abs() / sum * 100.0
+    } else {
+        0.0
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Adx {
+    prev: Option<Kline>, // last closed bar
+    true_range: Wilder,
+    plus: Wilder,
+    minus: Wilder,
+    adx: Wilder,
+}
+
+impl Adx {
+    fn new(params: &Params) -> Self {
+        let length = params.count(LENGTH).max(2);
+        Self {
+            prev: None,
+            true_range: Wilder::new(length),
+            plus: Wilder::new(length),
+            minus: Wilder::new(length),
+            adx: Wilder::new(length),
+        }
+    }
+
+    /// A length to seed the DI averages, then `SEED_LENGTHS` more for ADX's
+    /// own SMA seed to fade.
+    fn lookback(params: &Params, tf: Timeframe) -> Lookback {
+        Lookback {
+            minutes: BarAggregator::window_minutes(tf),
+            bars: params.count(LENGTH).max(2) * (SEED_LENGTHS + 1) + 1,
+            span: MinuteSpan::Forming,
+        }
+    }
+
+    fn push(&mut self, bar: &Kline) {
+        if let Some(prev) = self.prev {
+            let (true_range, plus, minus) = movement(bar, &prev);
+            self.true_range.push(true_range);
+            self.plus.push(plus);
+            self.minus.push(minus);
+            let averages = self
+                .true_range
+                .average()
+                .zip(self.plus.average())
+                .zip(self.minus.average());
+            if let Some(((true_range, plus), minus)) = averages
+                && let Some((plus_di, minus_di)) = directional(true_range, plus, minus)
+            {
+                self.adx.push(dx(plus_di, minus_di));
+            }
+        }
+        self.prev = Some(*bar);
+    }
+
+    /// Signed ADX and +DI/-DI with `bar` as the forming bar.
+    fn with_forming(&self, bar: &Kline) -> Option<(f64, f64, f64)> {
+        let (true_range, plus, minus) = movement(bar, self.prev.as_ref()?);
+        let (plus_di, minus_di) = directional(
+            self.true_range.with_next(true_range)?,
+            self.plus.with_next(plus)?,
+            self.minus.with_next(minus)?,
+        )?;
+        let adx = self.adx.with_next(dx(plus_di, minus_di))?;
+        Some((adx, plus_di, minus_di))
+    }
+}
+
+impl BarIndicator for Adx {
+    fn clear(&mut self) {
+        self.prev = None;
+        for wilder in [
+            &mut self.true_range,
+            &mut self.plus,
+            &mut self.minus,
+            &mut self.adx,
+        ] {
+            wilder.clear();
+        }
+    }
+
+    fn close_bar(&mut self, bar: &Kline, _bars: &BarAggregator) {
+        self.push(bar);
+    }
+
+    fn reading(&self, bars: &BarAggregator) -> Option<Reading> {
+        let (adx, plus_di, minus_di) = self.with_forming(&bars.forming()?)?;
+        let direction = Direction::of(plus_di, minus_di);
+        let adx = shown(adx, 0);
+        let value = match direction {
+            Direction::Up => adx,
+            Direction::Down => -adx,
+        };
+        Some(Reading::new(value).with_direction(Some(direction)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{directional, dx, movement};
+    use crate::types::{Kline, Price, Volume};
+
+    fn bar(low: f64, high: f64) -> Kline {
+        Kline {
+            open: Price::from_f64(low),
+            high: Price::from_f64(high),
+            low: Price::from_f64(low),
+            close: Price::from_f64(high),
+            volume: Volume::from_f64(1.0),
+            quote_volume: Volume::from_f64(high),
+            trades: 1,
+            taker_buy_volume: Volume::from_f64(0.5),
+            taker_buy_quote_volume: Volume::from_f64(high / 2.0),
+            open_time: 0,
+            closed: true,
+        }
+    }
+
+    #[test]
+    fn rising_bars_only_move_up() {
+        // Gapped up from a close of 11: the range is 2 but the true range 3.
+        let (true_range, plus, minus) = movement(&bar(12.0, 14.0), &bar(10.0, 11.0));
+        assert_eq!((true_range, plus, minus), (3.0, 3.0, 0.0));
+        let (plus_di, minus_di) = directional(true_range, plus, minus).unwrap();
+        assert_eq!((plus_di, minus_di), (100.0, 0.0));
+        assert_eq!(dx(plus_di, minus_di), 100.0);
+    }
+}
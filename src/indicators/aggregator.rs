@@ -0,0 +1,292 @@
+use std::collections::BTreeMap;
+
+use crate::types::{Bar1m, Kline, RingBuffer, Timeframe, UtcOffset};
+
+/// Most 1m bars kept per aggregator: a month of them would be 44,640 per
+/// (pair, timeframe).
+const MAX_WINDOW_MINUTES: usize = 24 * 60;
+
+/// Turns the 1m stream of one pair into bars of a higher timeframe.
+///
+/// One aggregator is kept per (pair, timeframe) and shared by every indicator on
+/// that timeframe. It holds the last `tf` minutes of 1m bars, at most a day (a
+/// rolling window), the aggregate of the closed bars in the current tf bucket,
+/// and reports when a push rolled the bucket over. Buckets follow the preset's
+/// day-start offset.
+#[derive(Debug, Clone)]
+pub struct BarAggregator {
+    tf: Timeframe,
+    offset: UtcOffset,
+    window: RingBuffer<Bar1m>,
+    closed_agg: Option<Kline>, // closed 1m bars of the current tf bucket
+    just_closed: Option<Kline>,
+    new_minute: bool,
+    seeds: u64,
+}
+
+impl BarAggregator {
+    pub fn new(tf: Timeframe, offset: UtcOffset) -> Self {
+        Self {
+            tf,
+            offset,
+            window: RingBuffer::new(Self::window_minutes(tf)),
+            closed_agg: None,
+            just_closed: None,
+            new_minute: false,
+            seeds: 0,
+        }
+    }
+
+    /// 1m bars kept for `tf`: its whole window, up to a day. Longer timeframes
+    /// get the start of their forming bar from [`BarAggregator::seed`].
+    pub fn window_minutes(tf: Timeframe) -> usize {
+        tf.window_minutes().min(MAX_WINDOW_MINUTES)
+    }
+
+    pub fn timeframe(&self) -> Timeframe {
+        self.tf
+    }
+
+    /// Day start the buckets follow.
+    pub fn offset(&self) -> UtcOffset {
+        self.offset
+    }
+
+    /// Feed a streamed 1m bar. Updates of the current minute replace it; bars
+    /// older than the latest one are ignored.
+    pub fn push(&mut self, bar: Bar1m) {
+        self.just_closed = None;
+        self.new_minute = false;
+
+        let Some(last) = self.window.back().copied() else {
+            self.window.push(bar);
+            self.new_minute = true;
+            return;
+        };
+
+        if bar.open_time < last.open_time {
+            return;
+        }
+        if bar.open_time == last.open_time {
+            self.window.replace_last(bar);
+            return;
+        }
+
+        let merged = match self.closed_agg {
+            Some(agg) => agg.merge(&last),
+            None => last,
+        };
+        if self.bucket_open(bar.open_time) == self.bucket_open(last.open_time) {
+            self.closed_agg = Some(merged);
+        } else {
+            self.closed_agg = None;
+            self.just_closed = Some(Kline {
+                closed: true,
+                ..merged
+            });
+        }
+
+        self.window.push(bar);
+        let cutoff = bar.open_time - self.window_millis();
+        self.window.expire_through(cutoff);
+        self.new_minute = true;
+    }
+
+    /// Merge 1m history into the window. Streamed bars win over history for the
+    /// same minute, so bars received while warming up are kept.
+    ///
+    /// `head` is the tf bucket from its open up to the first bar of `history`,
+    /// for timeframes longer than the window; it is dropped once that bucket
+    /// has closed.
+    pub fn seed(&mut self, history: &[Bar1m], head: Option<&Kline>) {
+        let mut merged: BTreeMap<i64, Bar1m> =
+            history.iter().map(|bar| (bar.open_time, *bar)).collect();
+        for bar in self.window.iter() {
+            merged.insert(bar.open_time, *bar);
+        }
+        let Some(&latest) = merged.keys().next_back() else {
+            return;
+        };
+
+        let bucket = self.bucket_open(latest);
+        let head = head.filter(|head| self.bucket_open(head.open_time) == bucket);
+        let first = match (head, history.first()) {
+            (Some(_), Some(first)) => first.open_time,
+            _ => bucket,
+        };
+        self.closed_agg = merged
+            .range(first..latest)
+            .fold(head.copied(), |agg: Option<Kline>, (_, bar)| {
+                Some(agg.map_or(*bar, |agg| agg.merge(bar)))
+            });
+
+        let cutoff = latest - self.window_millis();
+        self.window.clear();
+        for bar in merged.range(cutoff + 1..).map(|(_, bar)| *bar) {
+            self.window.push(bar);
+        }
+        self.just_closed = None;
+        self.new_minute = false;
+        self.seeds += 1;
+    }
+
+    /// Latest 1m bar, closed or still forming.
+    pub fn latest(&self) -> Option<&Bar1m> {
+        self.window.back()
+    }
+
+    /// Rolling window of the last `tf` minutes, at most a day, latest bar
+    /// included.
+    pub fn window(&self) -> &RingBuffer<Bar1m> {
+        &self.window
+    }
+
+    /// Current tf bar, aligned to the timeframe and including the forming 1m bar.
+    pub fn forming(&self) -> Option<Kline> {
+        let latest = *self.window.back()?;
+        let bar = match self.closed_agg {
+            Some(agg) => agg.merge(&latest),
+            None => latest,
+        };
+        let bucket_end = self.bucket_end(self.bucket_open(latest.open_time));
+        Some(Kline {
+            closed: latest.closed && latest.open_time + Timeframe::M1.window_millis() == bucket_end,
+            ..bar
+        })
+    }
+
+    /// The tf bar completed by the latest push, if it rolled the bucket over.
+    pub fn just_closed(&self) -> Option<&Kline> {
+        self.just_closed.as_ref()
+    }
+
+    /// How many times history was merged in; state derived incrementally
+    /// from the window must be rebuilt when this changes.
+    pub fn seeds(&self) -> u64 {
+        self.seeds
+    }
+
+    /// Whether the latest push started a new minute.
+    pub fn new_minute(&self) -> bool {
+        self.new_minute
+    }
+
+    fn window_millis(&self) -> i64 {
+        Self::window_minutes(self.tf) as i64 * Timeframe::M1.window_millis()
+    }
+
+    /// Open time of the tf bucket containing `open_time`.
+    pub fn bucket_open(&self, open_time: i64) -> i64 {
+        self.tf.nearest_ms_in(open_time, self.offset)
+    }
+
+    /// Open time of the tf bucket after the one opening at `bucket_open`.
+    pub fn bucket_end(&self, bucket_open: i64) -> i64 {
+        self.tf.next_open_in(bucket_open, self.offset)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::BarAggregator;
+    use crate::types::{Kline, Price, Timeframe, UtcOffset, Volume};
+
+    const MINUTE: i64 = 60_000;
+
+    fn bar(minute: i64, close: f64, closed: bool) -> Kline {
+        Kline {
+            open: Price::from_f64(close - 1.0),
+            high: Price::from_f64(close + 1.0),
+            low: Price::from_f64(close - 2.0),
+            close: Price::from_f64(close),
+            volume: Volume::from_f64(1.0),
+            quote_volume: Volume::from_f64(close),
+            trades: 1,
+            taker_buy_volume: Volume::from_f64(0.5),
+            taker_buy_quote_volume: Volume::from_f64(close / 2.0),
+            open_time: minute * MINUTE,
+            closed,
+        }
+    }
+
+    #[test]
+    fn rolls_over_into_closed_bar() {
+        let mut agg = BarAggregator::new(Timeframe::M5, UtcOffset::UTC);
+        for minute in 0..5 {
+            agg.push(bar(minute, 10.0 + minute as f64, false));
+            agg.push(bar(minute, 10.0 + minute as f64, true));
+            assert!(agg.just_closed().is_none());
+        }
+
+        let forming = agg.forming().unwrap();
+        assert!(forming.closed);
+        assert_eq!(forming.volume, Volume::from_f64(5.0));
+
+        agg.push(bar(5, 20.0, false));
+        let closed = agg.just_closed().copied().unwrap();
+        assert_eq!(closed.open_time, 0);
+        assert_eq!(closed.open, Price::from_f64(9.0));
+        assert_eq!(closed.close, Price::from_f64(14.0));
+        assert_eq!(closed.high, Price::from_f64(15.0));
+        assert_eq!(closed.low, Price::from_f64(8.0));
+        assert_eq!(closed.trades, 5);
+
+        let forming = agg.forming().unwrap();
+        assert_eq!(forming.open_time, 5 * MINUTE);
+        assert_eq!(forming.close, Price::from_f64(20.0));
+        assert!(!forming.closed);
+        assert_eq!(agg.window().len(), 5);
+    }
+
+    #[test]
+    fn seed_keeps_streamed_bars() {
+        let mut agg = BarAggregator::new(Timeframe::M5, UtcOffset::UTC);
+        agg.push(bar(7, 50.0, false));
+
+        let history: Vec<Kline> = (0..8).map(|m| bar(m, 10.0, true)).collect();
+        agg.seed(&history, None);
+
+        assert_eq!(agg.latest().unwrap().close, Price::from_f64(50.0));
+        assert_eq!(agg.window().front().unwrap().open_time, 3 * MINUTE);
+
+        let forming = agg.forming().unwrap();
+        assert_eq!(forming.open_time, 5 * MINUTE);
+        assert_eq!(forming.volume, Volume::from_f64(3.0));
+        assert_eq!(forming.close, Price::from_f64(50.0));
+    }
+
+    #[test]
+    fn long_bars_start_from_the_seeded_head() {
+        let mut agg = BarAggregator::new(Timeframe::D3, UtcOffset::UTC);
+        // The bucket's first two days, folded from longer bars.
+        let head = Kline {
+            volume: Volume::from_f64(100.0),
+            ..bar(0, 30.0, true)
+        };
+        let history: Vec<Kline> = (2880..2886).map(|m| bar(m, 10.0, true)).collect();
+        agg.seed(&history, Some(&head));
+
+        let forming = agg.forming().unwrap();
+        assert_eq!(forming.open_time, 0);
+        assert_eq!(forming.open, Price::from_f64(29.0));
+        assert_eq!(forming.volume, Volume::from_f64(106.0));
+
+        for minute in 2886..=4320 {
+            agg.push(bar(minute, 10.0, true));
+        }
+        let closed = agg.just_closed().copied().unwrap();
+        assert_eq!(closed.open_time, 0);
+        assert_eq!(closed.volume, Volume::from_f64(1540.0));
+        // Only a day of 1m bars is kept.
+        assert_eq!(agg.window().len(), 1440);
+
+        // A head from a bucket that has since closed is dropped.
+        let mut agg = BarAggregator::new(Timeframe::D3, UtcOffset::UTC);
+        let stale = Kline {
+            open_time: -4320 * MINUTE,
+            ..head
+        };
+        agg.seed(&history, Some(&stale));
+        assert_eq!(agg.forming().unwrap().volume, Volume::from_f64(6.0));
+    }
+}
@@ -0,0 +1,193 @@
+use crate::types::{Kline, Price, Timeframe, Wilder, true_range};
+
+use super::{
+    aggregator::BarAggregator,
+    indicator::{BarIndicator, Lookback, MinuteSpan, Reading, Staged, shown},
+    registry::{
+        BarFeed, Feed, IndicatorId, IndicatorSpec, ParamKind, ParamSpec, Params, ThresholdSpec,
+        TimeframeSet,
+    },
+};
+
+const LENGTH: &str = "length";
+
+const PARAMS: &[ParamSpec] = &[ParamSpec {
+    key: LENGTH,
+    label: "Length",
+    kind: ParamKind::Count,
+    default: 14.0,
+    min: 1.0,
+    max: 250.0,
+    step: 1.0,
+}];
+
+/// Wilder's average true range, in quote units. Not highlighted, since one
+/// threshold can't fit every pair's price.
+pub const ATR: IndicatorSpec = IndicatorSpec {
+    id: IndicatorId::ATR,
+    label: "ATR",
+    title: "ATR",
+    feed: Feed::Bars(BarFeed {
+        lookback: |params, tf| Atr::lookback(params, tf),
+        build: |params, _, _| Box::new(Staged::new(Atr::new(Output::Absolute, params))),
+    }),
+    timeframes: TimeframeSet::All,
+    default_enabled: false,
+    default_timeframes: &[Timeframe::H1, Timeframe::D1],
+    params: PARAMS,
+    threshold: ThresholdSpec::Plain,
+    format: |value| {
+        let decimals = significant_decimals(value);
+        format!("{value:.decimals$}")
+    },
+    width: 8,
+    event: None,
+};
+
+/// ATR in percent of the price, comparable across pairs. Same highlight
+/// steps as the volatility columns, so ranges read alike.
+pub const NATR: IndicatorSpec = IndicatorSpec {
+    id: IndicatorId::NATR,
+    label: "NATR",
+    title: "NATR",
+    feed: Feed::Bars(BarFeed {
+        lookback: |params, tf| Atr::lookback(params, tf),
+        build: |params, _, _| Box::new(Staged::new(Atr::new(Output::Percent, params))),
+    }),
+    timeframes: TimeframeSet::All,
+    default_enabled: false,
+    default_timeframes: &[Timeframe::H1, Timeframe::D1],
+    params: PARAMS,
+    threshold: ThresholdSpec::Magnitude {
+        default: 0.0,
+        step: 0.5,
+        coarse_step: 5.0,
+        decimals: 1,
+    },
+    format: |value| format!("{value:.2}%"),
+    width: 6,
+    event: None,
+};
+
+/// Decimals that leave four significant digits; quotes span tens of
+/// thousands down to fractions of a cent.
+fn significant_decimals(value: f32) -> usize {
+    if value == 0.0 || !value.is_finite() {
+        return 0;
+    }
+    (3 - value.abs().log10().floor() as i32).clamp(0, 8) as usize
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Output {
+    Absolute,
+    Percent,
+}
+
+#[derive(Debug, Clone)]
+pub struct Atr {
+    output: Output,
+    prev_close: Option<Price>, // close of the last closed bar
+    atr: Wilder,               // true ranges up to the last closed bar
+}
+
+impl Atr {
+    fn new(output: Output, params: &Params) -> Self {
+        let length = params.count(LENGTH);
+        Self {
+            output,
+            prev_close: None,
+            atr: Wilder::new(length),
+        }
+    }
+
+    /// Ten lengths of closed bars, like the RSI seed default: the SMA seed
+    /// then weighs about e^-9 of the Wilder average.
+    pub fn lookback(params: &Params, tf: Timeframe) -> Lookback {
+        Lookback {
+            minutes: BarAggregator::window_minutes(tf),
+            bars: params.count(LENGTH) * 10 + 1,
+            span: MinuteSpan::Forming,
+        }
+    }
+
+    fn push(&mut self, bar: &Kline) {
+        if let Some(prev_close) = self.prev_close {
+            self.atr.push(true_range(bar, prev_close));
+        }
+        self.prev_close = Some(bar.close);
+    }
+}
+
+impl BarIndicator for Atr {
+    fn clear(&mut self) {
+        self.prev_close = None;
+        self.atr.clear();
+    }
+
+    fn close_bar(&mut self, bar: &Kline, _bars: &BarAggregator) {
+        self.push(bar);
+    }
+
+    fn reading(&self, bars: &BarAggregator) -> Option<Reading> {
+        let forming = bars.forming()?;
+        let atr = self.atr.with_next(true_range(&forming, self.prev_close?))?;
+        let price = forming.close.to_f64();
+        let value = match self.output {
+            Output::Absolute => Some(shown(atr, significant_decimals(atr as f32) as i32)),
+            Output::Percent => (price > 0.0).then(|| shown(atr / price * 100.0, 2)),
+        };
+        value.map(Reading::from)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::{ATR, Atr, Output, significant_decimals};
+    use crate::types::{Kline, Price, Volume};
+
+    fn bar(high: f64, low: f64, close: f64) -> Kline {
+        Kline {
+            open: Price::from_f64(close),
+            high: Price::from_f64(high),
+            low: Price::from_f64(low),
+            close: Price::from_f64(close),
+            volume: Volume::from_f64(1.0),
+            quote_volume: Volume::from_f64(close),
+            trades: 1,
+            taker_buy_volume: Volume::from_f64(0.5),
+            taker_buy_quote_volume: Volume::from_f64(close / 2.0),
+            open_time: 0,
+            closed: true,
+        }
+    }
+
+    #[test]
+    fn seeds_then_smooths_true_ranges() {
+        let params = ATR.params(&BTreeMap::from([("length".to_string(), 2.0)]));
+        let mut atr = Atr::new(Output::Absolute, &params);
+
+        atr.push(&bar(10.0, 10.0, 10.0));
+        // Gapped up from 10: the range is 1 but the true range 2.
+        atr.push(&bar(12.0, 11.0, 11.5));
+        assert_eq!(atr.atr.average(), None);
+        atr.push(&bar(13.0, 12.0, 13.0));
+        // Seeded with the mean of 2 and 1.5.
+        assert_eq!(atr.atr.average(), Some(1.75));
+        atr.push(&bar(13.0, 12.5, 12.5));
+        assert_eq!(atr.atr.average(), Some(1.125));
+        // The forming bar is folded in without closing it.
+        assert_eq!(atr.atr.with_next(4.5), Some(2.8125));
+        assert_eq!(atr.atr.average(), Some(1.125));
+    }
+
+    #[test]
+    fn keeps_four_significant_digits() {
+        assert_eq!(significant_decimals(1234.5), 0);
+        assert_eq!(significant_decimals(12.345), 2);
+        assert_eq!(significant_decimals(0.0012345), 6);
+        assert_eq!(significant_decimals(0.0), 0);
+    }
+}
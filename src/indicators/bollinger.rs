@@ -0,0 +1,284 @@
+use crate::types::{Kline, RollingMin, RollingVariance, Timeframe};
+
+use super::{
+    aggregator::BarAggregator,
+    indicator::{BarIndicator, Lookback, MinuteSpan, Reading, Signal, Staged, shown},
+    registry::{
+        BarFeed, Feed, IndicatorId, IndicatorSpec, ParamKind, ParamSpec, Params, ThresholdSpec,
+        TimeframeSet,
+    },
+};
+
+const LENGTH: &str = "length";
+const DEVIATIONS: &str = "deviations";
+const SQUEEZE: &str = "squeeze";
+
+const PARAMS: &[ParamSpec] = &[
+    ParamSpec {
+        key: LENGTH,
+        label: "Length",
+        kind: ParamKind::Count,
+        default: 20.0,
+        min: 2.0,
+        max: 500.0,
+        step: 1.0,
+    },
+    ParamSpec {
+        key: DEVIATIONS,
+        label: "Std dev",
+        kind: ParamKind::Decimal(1),
+        default: 2.0,
+        min: 0.5,
+        max: 5.0,
+        step: 0.1,
+    },
+    // Bars whose lowest bandwidth a squeeze has to match.
+    ParamSpec {
+        key: SQUEEZE,
+        label: "Squeeze bars",
+        kind: ParamKind::Count,
+        default: 120.0,
+        min: 10.0,
+        max: 500.0,
+        step: 10.0,
+    },
+];
+
+/// Where the price sits in the bands: 0 on the lower band, 1 on the upper.
+pub const PERCENT_B: IndicatorSpec = IndicatorSpec {
+    id: IndicatorId::BB_PERCENT_B,
+    label: "%B",
+    title: "Bollinger %B",
+    feed: Feed::Bars(BarFeed {
+        lookback: |params, tf| Bollinger::lookback(params, tf),
+        build: |params, _, _| Box::new(Staged::new(Bollinger::new(Output::PercentB, params))),
+    }),
+    timeframes: TimeframeSet::All,
+    default_enabled: false,
+    default_timeframes: &[Timeframe::H1, Timeframe::H4],
+    params: PARAMS,
+    threshold: ThresholdSpec::Band {
+        lower: 0.0,
+        upper: 1.0,
+    },
+    format: |value| format!("{value:.2}"),
+    width: 6,
+    event: Some(squeeze_event),
+};
+
+/// Distance between the bands in percent of the middle band.
+pub const BANDWIDTH: IndicatorSpec = IndicatorSpec {
+    id: IndicatorId::BB_BANDWIDTH,
+    label: "BB WIDTH",
+    title: "Bollinger bandwidth",
+    feed: Feed::Bars(BarFeed {
+        lookback: |params, tf| Bollinger::lookback(params, tf),
+        build: |params, _, _| Box::new(Staged::new(Bollinger::new(Output::Bandwidth, params))),
+    }),
+    timeframes: TimeframeSet::All,
+    default_enabled: false,
+    default_timeframes: &[Timeframe::H1, Timeframe::H4],
+    params: PARAMS,
+    threshold: ThresholdSpec::Magnitude {
+        default: 0.0,
+        step: 0.5,
+        coarse_step: 5.0,
+        decimals: 1,
+    },
+    format: |value| format!("{value:.2}%"),
+    width: 6,
+    event: Some(squeeze_event),
+};
+
+fn squeeze_event(reading: Reading) -> Option<&'static str> {
+    (reading.signal == Some(Signal::Squeeze)).then_some("squeeze")
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Output {
+    PercentB,
+    Bandwidth,
+}
+
+/// Bollinger Bands over the last `length` closes, the forming bar included.
+///
+/// Closed closes are kept in a rolling variance and the forming close is
+/// folded in per tick without being pushed, like `Volatility` does with the
+/// open 1m bar. A squeeze is flagged while the bandwidth is at or below the
+/// lowest of the previous `squeeze` closed bars.
+#[derive(Debug, Clone)]
+pub struct Bollinger {
+    output: Output,
+    deviations: f64,
+    squeeze: usize,
+    closes: RollingVariance,
+    widths: RollingMin<f64>, // bandwidths of closed bars, keyed by bar count
+    widths_seen: usize,
+    closed_bars: i64,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Bands {
+    lower: f64,
+    middle: f64,
+    upper: f64,
+}
+
+impl Bands {
+    fn bandwidth(&self) -> Option<f64> {
+        (self.middle > 0.0).then(|| (self.upper - self.lower) / self.middle * 100.0)
+    }
+}
+
+impl Bollinger {
+    fn new(output: Output, params: &Params) -> Self {
+        let length = params.count(LENGTH).max(2);
+        Self {
+            output,
+            deviations: params.get(DEVIATIONS),
+            squeeze: params.count(SQUEEZE),
+            // The forming close takes the last of the `length` places.
+            closes: RollingVariance::new(length - 1),
+            widths: RollingMin::new(),
+            widths_seen: 0,
+            closed_bars: 0,
+        }
+    }
+
+    /// Closed bars for the bands plus the squeeze window of bandwidths.
+    pub fn lookback(params: &Params, tf: Timeframe) -> Lookback {
+        Lookback {
+            minutes: BarAggregator::window_minutes(tf),
+            bars: params.count(LENGTH).max(2) + params.count(SQUEEZE),
+            span: MinuteSpan::Forming,
+        }
+    }
+
+    fn bands(&self, close: f64) -> Option<Bands> {
+        let (middle, variance) = self.closes.with_next(close)?;
+        let offset = self.deviations * variance.sqrt();
+        Some(Bands {
+            lower: middle - offset,
+            middle,
+            upper: middle + offset,
+        })
+    }
+
+    fn push(&mut self, close: f64) {
+        if let Some(width) = self.bands(close).and_then(|bands| bands.bandwidth()) {
+            self.widths.push(self.closed_bars, width);
+            self.widths_seen += 1;
+        }
+        self.closed_bars += 1;
+        self.widths.expire(self.closed_bars - self.squeeze as i64);
+        self.closes.push(close);
+    }
+
+    /// %B or bandwidth with `price` as the forming close, flagged while the
+    /// bandwidth is at its low of the squeeze window.
+    fn with_forming(&self, price: f64) -> Option<Reading> {
+        self.bands(price).map(|bands| {
+            let width = bands.bandwidth();
+            // A squeeze needs a full window of closed bandwidths to compare to.
+            let squeeze = self.widths_seen >= self.squeeze
+                && width
+                    .zip(self.widths.get())
+                    .is_some_and(|(width, (_, low))| width <= *low);
+            let value = match self.output {
+                Output::PercentB if bands.upper > bands.lower => {
+                    (price - bands.lower) / (bands.upper - bands.lower)
+                }
+                Output::PercentB => 0.5,
+                Output::Bandwidth => width.unwrap_or_default(),
+            };
+            Reading::new(shown(value, 2)).with_signal(squeeze.then_some(Signal::Squeeze))
+        })
+    }
+}
+
+impl BarIndicator for Bollinger {
+    fn clear(&mut self) {
+        self.closes.clear();
+        self.widths.clear();
+        self.widths_seen = 0;
+        self.closed_bars = 0;
+    }
+
+    fn close_bar(&mut self, bar: &Kline, _bars: &BarAggregator) {
+        self.push(bar.close.to_f64());
+    }
+
+    fn reading(&self, bars: &BarAggregator) -> Option<Reading> {
+        self.with_forming(bars.latest()?.close.to_f64())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::{BANDWIDTH, Bollinger, Output, PERCENT_B, Signal};
+
+    /// Bands over two closes, the forming one included, two deviations wide,
+    /// with a 10-bar squeeze window.
+    fn bollinger(output: Output) -> Bollinger {
+        let saved: BTreeMap<String, f64> =
+            [("length", 2.0), ("deviations", 2.0), ("squeeze", 10.0)]
+                .into_iter()
+                .map(|(key, value)| (key.to_string(), value))
+                .collect();
+        Bollinger::new(output, &PERCENT_B.params(&saved))
+    }
+
+    fn value(bollinger: &Bollinger, price: f64) -> Option<f32> {
+        bollinger.with_forming(price).map(|reading| reading.value)
+    }
+
+    fn squeezes(bollinger: &Bollinger, price: f64) -> bool {
+        bollinger.with_forming(price).unwrap().signal == Some(Signal::Squeeze)
+    }
+
+    #[test]
+    fn places_the_forming_close_in_the_bands() {
+        let mut percent_b = bollinger(Output::PercentB);
+        let mut bandwidth = bollinger(Output::Bandwidth);
+        assert_eq!(value(&percent_b, 100.0), None);
+        percent_b.push(100.0);
+        bandwidth.push(100.0);
+
+        // 100 and 102 deviate by 1 around 101: bands at 99 and 103.
+        assert_eq!(value(&percent_b, 102.0), Some(0.75));
+        assert_eq!(value(&bandwidth, 102.0), Some(3.96)); // 4 / 101
+        // Flat bands have no width to place the price in.
+        assert_eq!(value(&percent_b, 100.0), Some(0.5));
+        assert_eq!(value(&bandwidth, 100.0), Some(0.0));
+        assert_eq!(BANDWIDTH.format(3.96), "3.96%");
+    }
+
+    #[test]
+    fn squeezes_at_the_low_of_the_window() {
+        let mut bollinger = bollinger(Output::Bandwidth);
+
+        // A narrow bandwidth on the second close, then nine wide ones.
+        let closes = [
+            100.0, 101.0, 111.0, 101.0, 111.0, 101.0, 111.0, 101.0, 111.0, 101.0,
+        ];
+        for close in closes {
+            bollinger.push(close);
+        }
+        // Nine bandwidths aren't a full window, even for flat bands.
+        assert!(!squeezes(&bollinger, 101.0));
+
+        bollinger.push(111.0);
+        assert!(squeezes(&bollinger, 111.0));
+        // Narrower than the wide ones but not the narrow one still in the window.
+        assert!(!squeezes(&bollinger, 116.0));
+
+        // The narrow bandwidth leaves the window ten bars on.
+        bollinger.push(101.0);
+        assert!(squeezes(&bollinger, 106.0));
+        // Matching the low exactly counts.
+        assert!(squeezes(&bollinger, 111.0));
+        assert!(!squeezes(&bollinger, 112.0));
+    }
+}
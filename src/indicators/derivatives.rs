@@ -0,0 +1,72 @@
+use crate::{
+    adapters::binance::OPEN_INTEREST_TIMEFRAMES,
+    types::{FundingRate, OpenInterest, Timeframe, config::FUNDING_TIMEFRAME},
+};
+
+use super::registry::{Feed, IndicatorId, IndicatorSpec, ThresholdSpec, TimeframeSet};
+
+/// Current funding rate; highlighted when the book is crowded either way.
+pub const FUNDING: IndicatorSpec = IndicatorSpec {
+    id: IndicatorId::FUNDING,
+    label: "FUND",
+    title: "Funding",
+    feed: Feed::Derivatives,
+    timeframes: TimeframeSet::Single(FUNDING_TIMEFRAME),
+    default_enabled: true,
+    default_timeframes: &[FUNDING_TIMEFRAME],
+    params: &[],
+    threshold: ThresholdSpec::Magnitude {
+        default: 0.05,
+        step: 0.005,
+        coarse_step: 0.05,
+        decimals: 3,
+    },
+    format: |value| format!("{value:+.3}"),
+    width: 6,
+    event: None,
+};
+
+/// Open interest change over each timeframe.
+pub const OPEN_INTEREST: IndicatorSpec = IndicatorSpec {
+    id: IndicatorId::OPEN_INTEREST,
+    label: "OPEN INTEREST",
+    title: "Open interest",
+    feed: Feed::Derivatives,
+    timeframes: TimeframeSet::Only(&OPEN_INTEREST_TIMEFRAMES),
+    default_enabled: true,
+    default_timeframes: &[Timeframe::H1, Timeframe::H4, Timeframe::D1],
+    params: &[],
+    threshold: ThresholdSpec::Magnitude {
+        default: 0.0,
+        step: 0.5,
+        coarse_step: 5.0,
+        decimals: 1,
+    },
+    format: |value| format!("{value:+.1}%"),
+    width: 6,
+    event: None,
+};
+
+/// Funding rate as a percentage, truncated to three decimals (0.001%).
+pub fn funding_percent(funding: &FundingRate) -> f32 {
+    let percent = funding.rate * 100.0;
+    ((percent * 1_000.0).trunc() / 1_000.0) as f32
+}
+
+/// Percent change of open interest over the last `tf` window.
+/// Expects samples ordered oldest first; returns `None` if the series is too short.
+pub fn open_interest_change(samples: &[OpenInterest], tf: Timeframe) -> Option<f32> {
+    let last = samples.last()?;
+    let base_ts = last.timestamp - tf.window_millis();
+    let base = samples
+        .iter()
+        .rev()
+        .find(|sample| sample.timestamp <= base_ts)?;
+
+    if base.open_interest <= 0.0 {
+        return None;
+    }
+
+    let change = (last.open_interest - base.open_interest) / base.open_interest * 100.0;
+    Some(((change * 10.0).trunc() / 10.0) as f32)
+}
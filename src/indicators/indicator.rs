@@ -1,9 +1,252 @@
-use crate::message_bus::KlineHist;
+use crate::{
+    message_bus::KlineHist,
+    time::now_millis,
+    types::{Kline, Timeframe, UtcOffset},
+};
 
+use super::{aggregator::BarAggregator, series::SharedSeries};
+
+/// Indicators of one (pair, timeframe) get the same `bars` and `series`; a
+/// series is kept up to date by whichever indicator reads it first.
 pub trait Indicator {
-    type Input;
-    type Output;
+    /// Reading to show, when it changed since the last one sent.
+    fn update(&mut self, bars: &BarAggregator, series: &mut SharedSeries) -> Option<Reading>;
+    fn update_khist(&mut self, input: KlineHist, bars: &BarAggregator, series: &mut SharedSeries);
+}
+
+/// Warmup stage of an indicator and the reading it last computed.
+///
+/// Nothing is sent until the warmup history arrives; after it the first
+/// reading goes out, then every change.
+#[derive(Debug, Clone, Default)]
+pub struct Publisher {
+    stage: Stage,
+    reading: Option<Reading>,
+    sent_once: bool,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+enum Stage {
+    #[default]
+    New,
+    WarmUp,
+    Ready,
+}
+
+impl Publisher {
+    /// Whether live bars are folded in yet; a new indicator starts waiting
+    /// for its warmup here.
+    pub fn is_ready(&mut self) -> bool {
+        match self.stage {
+            Stage::New => {
+                self.stage = Stage::WarmUp;
+                false
+            }
+            Stage::WarmUp => false,
+            Stage::Ready => true,
+        }
+    }
+
+    /// Keep the reading computed from the warmup history; it is sent with
+    /// the next update.
+    pub fn warmed_up(&mut self, reading: Option<Reading>) {
+        self.reading = reading;
+        self.stage = Stage::Ready;
+    }
+
+    /// `reading` when it is the first since warmup or differs from the last.
+    pub fn publish(&mut self, reading: Option<Reading>) -> Option<Reading> {
+        let changed = reading != self.reading;
+        self.reading = reading;
+        if !self.sent_once {
+            self.sent_once = true;
+            return self.reading;
+        }
+        if changed { self.reading } else { None }
+    }
+}
+
+/// An indicator folding in closed bars of its timeframe, with the forming bar
+/// read per tick. [`Staged`] runs its warmup and sends.
+pub trait BarIndicator {
+    /// Bars of a warmup history it is rebuilt from, oldest first.
+    fn history(input: &KlineHist) -> &[Kline] {
+        &input.hist_tf
+    }
+
+    /// Rebuild the closed-bar state from the closed bars of a warmup.
+    fn seed(&mut self, closed: &[Kline], bars: &BarAggregator) {
+        self.clear();
+        for bar in closed {
+            self.close_bar(bar, bars);
+        }
+    }
+
+    fn clear(&mut self);
+
+    /// Fold in a completed bar.
+    fn close_bar(&mut self, bar: &Kline, bars: &BarAggregator);
+
+    /// Reading with the forming bar folded in; `None` until seeded.
+    fn reading(&self, bars: &BarAggregator) -> Option<Reading>;
+}
+
+/// A [`BarIndicator`] with its warmup stage and sends.
+#[derive(Debug, Clone)]
+pub struct Staged<T> {
+    indicator: T,
+    publisher: Publisher,
+}
+
+impl<T> Staged<T> {
+    pub fn new(indicator: T) -> Self {
+        Self {
+            indicator,
+            publisher: Publisher::default(),
+        }
+    }
+}
+
+impl<T: BarIndicator> Indicator for Staged<T> {
+    fn update_khist(&mut self, input: KlineHist, bars: &BarAggregator, _series: &mut SharedSeries) {
+        self.indicator
+            .seed(closed_history(T::history(&input), bars), bars);
+        self.publisher.warmed_up(self.indicator.reading(bars));
+    }
+
+    fn update(&mut self, bars: &BarAggregator, _series: &mut SharedSeries) -> Option<Reading> {
+        if !self.publisher.is_ready() {
+            return None;
+        }
+        if let Some(closed) = bars.just_closed() {
+            self.indicator.close_bar(closed, bars);
+        }
+        self.publisher.publish(self.indicator.reading(bars))
+    }
+}
+
+/// Bars of a warmup `history` before the bar of `bars`' timeframe still
+/// forming, which live updates fold in instead.
+pub(super) fn closed_history<'a>(history: &'a [Kline], bars: &BarAggregator) -> &'a [Kline] {
+    let prev_open = bars.bucket_open(bars.bucket_open(now_millis()) - 1);
+    &history[..history.partition_point(|bar| bar.open_time <= prev_open)]
+}
+
+/// `value` rounded to the `decimals` its cell shows; finer moves aren't worth
+/// a send.
+pub(super) fn shown(value: f64, decimals: i32) -> f32 {
+    let scale = 10_f64.powi(decimals);
+    (value * scale).round() as f32 / scale as f32
+}
+
+/// History an indicator needs before its first value, counted back from the
+/// warmup start.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Lookback {
+    /// 1m bars, the current minute included.
+    pub minutes: usize,
+    /// Closed bars of the indicator timeframe before the current one.
+    pub bars: usize,
+    pub span: MinuteSpan,
+}
+
+/// Where the 1m history handed to an indicator starts.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum MinuteSpan {
+    /// `minutes` back from the current minute.
+    #[default]
+    Rolling,
+    /// The open of the timeframe bar still forming.
+    Forming,
+    /// The latest anchor, e.g. the start of the day for a session VWAP.
+    Since(Anchor),
+}
+
+/// Time a cumulative indicator counts from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Anchor {
+    /// Start of the day, at the preset's day start.
+    Day,
+    /// Start of the week, Monday at the preset's day start.
+    Week,
+    /// Fixed UTC time, in ms.
+    At(i64),
+}
+
+impl Anchor {
+    /// Latest anchor at or before `time_ms`; `None` before a fixed one.
+    pub fn open_at(self, time_ms: i64, offset: UtcOffset) -> Option<i64> {
+        match self {
+            Anchor::Day => Some(Timeframe::D1.nearest_ms_in(time_ms, offset)),
+            Anchor::Week => Some(Timeframe::W1.nearest_ms_in(time_ms, offset)),
+            Anchor::At(anchor) => (time_ms >= anchor).then_some(anchor),
+        }
+    }
+}
+
+/// What a dashboard cell shows for one indicator.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Reading {
+    pub value: f32,
+    /// Shown as an arrow after the value, e.g. rising momentum.
+    pub direction: Option<Direction>,
+    /// Event on the forming bar; the cell is highlighted while it lasts.
+    pub signal: Option<Signal>,
+}
+
+impl Reading {
+    pub fn new(value: f32) -> Self {
+        Self {
+            value,
+            ..Self::default()
+        }
+    }
+
+    pub fn with_direction(mut self, direction: Option<Direction>) -> Self {
+        self.direction = direction;
+        self
+    }
+
+    pub fn with_signal(mut self, signal: Option<Signal>) -> Self {
+        self.signal = signal;
+        self
+    }
+}
+
+impl From<f32> for Reading {
+    fn from(value: f32) -> Self {
+        Reading::new(value)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Signal {
+    /// A line crossing its signal line, in the direction it crossed.
+    Cross(Direction),
+    /// Bands at their narrowest in a while.
+    Squeeze,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    Up,
+    Down,
+}
+
+impl Direction {
+    /// `Up` when `value` is above `base`.
+    pub fn of(value: f64, base: f64) -> Direction {
+        if value > base {
+            Direction::Up
+        } else {
+            Direction::Down
+        }
+    }
 
-    fn update(&mut self, input: Self::Input) -> Self::Output;
-    fn update_khist(&mut self, input: KlineHist);
+    pub fn arrow(self) -> &'static str {
+        match self {
+            Direction::Up => "↑",
+            Direction::Down => "↓",
+        }
+    }
 }
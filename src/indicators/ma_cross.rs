@@ -0,0 +1,254 @@
+use crate::types::{Kline, Timeframe};
+
+use super::{
+    aggregator::BarAggregator,
+    indicator::{BarIndicator, Lookback, MinuteSpan, Reading, Staged},
+    moving_average::{Average, KIND_LABELS, Kind},
+    registry::{
+        BarFeed, Feed, IndicatorId, IndicatorSpec, ParamKind, ParamSpec, Params, ThresholdSpec,
+        TimeframeSet,
+    },
+};
+
+const FAST_TYPE: &str = "fast_type";
+const FAST_LENGTH: &str = "fast_length";
+const SLOW_TYPE: &str = "slow_type";
+const SLOW_LENGTH: &str = "slow_length";
+
+/// Closed bars searched for the last cross beyond what the averages need.
+const CROSS_HISTORY: usize = 100;
+
+/// Regime of a fast average against a slow one.
+///
+/// Values are the signed bars since the last cross plus one: `+1` on the bar
+/// a bullish cross happens, `-3` two bars after a bearish one. Regimes older
+/// than [`CROSS_HISTORY`] bars, or begun before the history, show as `≥100`.
+pub const SPEC: IndicatorSpec = IndicatorSpec {
+    id: IndicatorId::MA_CROSS,
+    label: "MA CROSS",
+    title: "MA cross",
+    feed: Feed::Bars(BarFeed {
+        lookback: |params, tf| MaCross::lookback(params, tf),
+        build: |params, _, _| Box::new(Staged::new(MaCross::new(params))),
+    }),
+    timeframes: TimeframeSet::All,
+    default_enabled: false,
+    default_timeframes: &[Timeframe::H1, Timeframe::H4],
+    params: &[
+        ma_type(FAST_TYPE, "Fast"),
+        ma_length(FAST_LENGTH, "Fast length", 20.0),
+        ma_type(SLOW_TYPE, "Slow"),
+        ma_length(SLOW_LENGTH, "Slow length", 50.0),
+    ],
+    threshold: ThresholdSpec::Recency {
+        default: 1.0,
+        step: 1.0,
+        coarse_step: 10.0,
+    },
+    format: |value| {
+        let arrow = if value >= 0.0 { "▲" } else { "▼" };
+        match value.abs() as usize {
+            0 | 1 => format!("{arrow} now"),
+            bars if bars > CROSS_HISTORY => format!("{arrow} ≥{CROSS_HISTORY}"),
+            bars => format!("{arrow} {}", bars - 1),
+        }
+    },
+    width: 6,
+    event: Some(|reading| {
+        if reading.value == 1.0 {
+            Some("bullish cross")
+        } else if reading.value == -1.0 {
+            Some("bearish cross")
+        } else {
+            None
+        }
+    }),
+};
+
+const fn ma_type(key: &'static str, label: &'static str) -> ParamSpec {
+    ParamSpec {
+        key,
+        label,
+        kind: ParamKind::Choice(&KIND_LABELS),
+        default: 1.0,
+        min: 0.0,
+        max: 2.0,
+        step: 1.0,
+    }
+}
+
+const fn ma_length(key: &'static str, label: &'static str, default: f64) -> ParamSpec {
+    ParamSpec {
+        key,
+        label,
+        kind: ParamKind::Count,
+        default,
+        min: 2.0,
+        max: 500.0,
+        step: 1.0,
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct MaCross {
+    fast: Line,
+    slow: Line,
+    // Regime after the last closed bar, closed bars since it began, and
+    // whether it began within the history rather than before it.
+    closed: Option<bool>,
+    closed_since: usize,
+    crossed: bool,
+}
+
+#[derive(Debug, Clone)]
+struct Line {
+    length: usize,
+    average: Average,
+}
+
+impl Line {
+    fn new(params: &Params, kind: &str, length: &str) -> Self {
+        let length = params.count(length).max(2);
+        Self {
+            length,
+            average: Average::new(Kind::from_choice(params.get(kind)), length),
+        }
+    }
+
+    fn with_forming(&self, close: f64) -> Option<f64> {
+        self.average.with_forming(close, self.length)
+    }
+}
+
+impl MaCross {
+    pub fn new(params: &Params) -> Self {
+        Self {
+            fast: Line::new(params, FAST_TYPE, FAST_LENGTH),
+            slow: Line::new(params, SLOW_TYPE, SLOW_LENGTH),
+            closed: None,
+            closed_since: 0,
+            crossed: false,
+        }
+    }
+
+    /// History for both averages plus [`CROSS_HISTORY`] bars to find the last
+    /// cross in; older crosses show as at least that many bars ago.
+    pub fn lookback(params: &Params, tf: Timeframe) -> Lookback {
+        let bars = |kind: &str, length: &str| {
+            Kind::from_choice(params.get(kind)).bars(params.count(length).max(2))
+        };
+        Lookback {
+            minutes: BarAggregator::window_minutes(tf),
+            bars: bars(FAST_TYPE, FAST_LENGTH).max(bars(SLOW_TYPE, SLOW_LENGTH)) + CROSS_HISTORY,
+            span: MinuteSpan::Forming,
+        }
+    }
+
+    /// Whether the fast average is above the slow one with `close` as the
+    /// newest value; `None` until both are settled.
+    fn regime(&self, close: f64) -> Option<bool> {
+        Some(self.fast.with_forming(close)? > self.slow.with_forming(close)?)
+    }
+
+    fn push(&mut self, close: f64) {
+        if let Some(bullish) = self.regime(close) {
+            if self.closed == Some(bullish) {
+                self.closed_since += 1;
+            } else {
+                self.crossed = self.closed.is_some();
+                self.closed = Some(bullish);
+                self.closed_since = 0;
+            }
+        }
+        self.fast.average.push(close, self.fast.length);
+        self.slow.average.push(close, self.slow.length);
+    }
+
+    /// Signed bars since the cross plus one with `bullish` as the forming
+    /// bar's regime, capped past [`CROSS_HISTORY`] bars.
+    fn value(&self, bullish: bool) -> Option<f32> {
+        let closed = self.closed?;
+        // A regime differing from the closed one is crossing on this bar.
+        let bars_ago = if bullish != closed {
+            0
+        } else if self.crossed {
+            (self.closed_since + 1).min(CROSS_HISTORY)
+        } else {
+            CROSS_HISTORY
+        };
+        let value = (bars_ago + 1) as f32;
+        Some(if bullish { value } else { -value })
+    }
+}
+
+impl BarIndicator for MaCross {
+    fn clear(&mut self) {
+        self.fast.average.clear();
+        self.slow.average.clear();
+        self.closed = None;
+        self.closed_since = 0;
+        self.crossed = false;
+    }
+
+    fn close_bar(&mut self, bar: &Kline, _bars: &BarAggregator) {
+        self.push(bar.close.to_f64());
+    }
+
+    fn reading(&self, bars: &BarAggregator) -> Option<Reading> {
+        let bullish = self.regime(bars.latest()?.close.to_f64())?;
+        self.value(bullish).map(Reading::from)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::{MaCross, Reading, SPEC};
+
+    /// SMA2 against SMA3.
+    fn cross() -> MaCross {
+        let saved: BTreeMap<String, f64> = [
+            ("fast_type", 0.0),
+            ("fast_length", 2.0),
+            ("slow_type", 0.0),
+            ("slow_length", 3.0),
+        ]
+        .into_iter()
+        .map(|(key, value)| (key.to_string(), value))
+        .collect();
+        MaCross::new(&SPEC.params(&saved))
+    }
+
+    #[test]
+    fn counts_closed_bars_since_the_last_cross() {
+        let mut cross = cross();
+
+        // SMA2 falls below SMA3 on the 8, crosses back above on the 9.
+        for close in [10.0, 9.0, 8.0, 7.0, 8.0, 9.0, 10.0] {
+            cross.push(close);
+        }
+        assert_eq!(cross.closed, Some(true));
+        assert_eq!(cross.closed_since, 1);
+        assert_eq!(cross.value(true), Some(3.0));
+        assert_eq!(cross.value(false), Some(-1.0));
+
+        assert_eq!(cross.regime(9.0), Some(true));
+        assert_eq!(cross.regime(7.0), Some(false));
+        assert_eq!(SPEC.format(3.0), "▲ 2");
+        assert_eq!(SPEC.event(Reading::new(-1.0)), Some("bearish cross"));
+    }
+
+    #[test]
+    fn caps_regimes_begun_before_the_history() {
+        let mut cross = cross();
+
+        // Falling from the first settled bar: no cross was seen.
+        for close in [10.0, 9.0, 8.0, 7.0] {
+            cross.push(close);
+        }
+        assert_eq!(cross.value(false), Some(-101.0));
+        assert_eq!(SPEC.format(-101.0), "▼ ≥100");
+        assert_eq!(cross.value(true), Some(1.0));
+    }
+}
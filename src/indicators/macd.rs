@@ -0,0 +1,258 @@
+use crate::types::{Kline, Timeframe};
+
+use super::{
+    aggregator::BarAggregator,
+    indicator::{BarIndicator, Direction, Lookback, MinuteSpan, Reading, Signal, Staged, shown},
+    moving_average::{Average, Kind},
+    registry::{
+        BarFeed, Feed, IndicatorId, IndicatorSpec, ParamKind, ParamSpec, Params, ThresholdSpec,
+        TimeframeSet,
+    },
+};
+
+const FAST: &str = "fast";
+const SLOW: &str = "slow";
+const SIGNAL: &str = "signal";
+
+/// MACD histogram in percent of the price, with an arrow for its momentum
+/// against the last closed bar. Signal-line crosses highlight the cell.
+pub const SPEC: IndicatorSpec = IndicatorSpec {
+    id: IndicatorId::MACD,
+    label: "MACD",
+    title: "MACD",
+    feed: Feed::Bars(BarFeed {
+        lookback: |params, tf| Macd::lookback(params, tf),
+        build: |params, _, _| Box::new(Staged::new(Macd::new(params))),
+    }),
+    timeframes: TimeframeSet::All,
+    default_enabled: false,
+    default_timeframes: &[Timeframe::H1, Timeframe::H4],
+    params: &[
+        length(FAST, "Fast", 12.0),
+        length(SLOW, "Slow", 26.0),
+        length(SIGNAL, "Signal", 9.0),
+    ],
+    threshold: ThresholdSpec::Magnitude {
+        default: 0.0,
+        step: 0.005,
+        coarse_step: 0.05,
+        decimals: 3,
+    },
+    format: |value| format!("{value:+.3}%"),
+    width: 9,
+    event: Some(|reading| match reading.signal? {
+        Signal::Cross(Direction::Up) => Some("bullish signal cross"),
+        Signal::Cross(Direction::Down) => Some("bearish signal cross"),
+        Signal::Squeeze => None,
+    }),
+};
+
+const fn length(key: &'static str, label: &'static str, default: f64) -> ParamSpec {
+    ParamSpec {
+        key,
+        label,
+        kind: ParamKind::Count,
+        default,
+        min: 2.0,
+        max: 200.0,
+        step: 1.0,
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Macd {
+    fast: Line,
+    slow: Line,
+    signal: Line,
+    closed_histogram: Option<f64>, // histogram at the last closed bar
+}
+
+/// An EMA and its length; the state covers closed bars only.
+#[derive(Debug, Clone)]
+struct Line {
+    length: usize,
+    ema: Average,
+}
+
+impl Line {
+    fn new(params: &Params, key: &str) -> Self {
+        let length = params.count(key).max(2);
+        Self {
+            length,
+            ema: Average::new(Kind::Exponential, length),
+        }
+    }
+
+    fn with_forming(&self, value: f64) -> Option<f64> {
+        self.ema.with_forming(value, self.length)
+    }
+
+    fn push(&mut self, value: f64) {
+        self.ema.push(value, self.length);
+    }
+}
+
+impl Macd {
+    pub fn new(params: &Params) -> Self {
+        Self {
+            fast: Line::new(params, FAST),
+            slow: Line::new(params, SLOW),
+            signal: Line::new(params, SIGNAL),
+            closed_histogram: None,
+        }
+    }
+
+    /// The signal EMA starts once the slower EMA is seeded; each EMA gets
+    /// four lengths for its SMA seed to fade, as the EMA column does.
+    pub fn lookback(params: &Params, tf: Timeframe) -> Lookback {
+        let bars = |key: &str| Kind::Exponential.bars(params.count(key).max(2));
+        Lookback {
+            minutes: BarAggregator::window_minutes(tf),
+            bars: bars(FAST).max(bars(SLOW)) + bars(SIGNAL),
+            span: MinuteSpan::Forming,
+        }
+    }
+
+    fn macd(&self, close: f64) -> Option<f64> {
+        Some(self.fast.with_forming(close)? - self.slow.with_forming(close)?)
+    }
+
+    /// MACD minus its signal line with `close` as the newest value; `None`
+    /// until seeded.
+    fn histogram(&self, close: f64) -> Option<f64> {
+        let macd = self.macd(close)?;
+        Some(macd - self.signal.with_forming(macd)?)
+    }
+
+    fn push(&mut self, close: f64) {
+        let macd = self.macd(close);
+        self.closed_histogram = self.histogram(close);
+        self.fast.push(close);
+        self.slow.push(close);
+        if let Some(macd) = macd {
+            self.signal.push(macd);
+        }
+    }
+
+    /// Histogram in percent of `price`, its momentum against the last closed
+    /// bar and a signal-line cross, with `price` as the forming close.
+    fn with_forming(&self, price: f64) -> Option<Reading> {
+        self.histogram(price)
+            .filter(|_| price > 0.0)
+            .map(|histogram| {
+                let closed = self.closed_histogram;
+                let value = shown(histogram / price * 100.0, 3);
+                // The histogram changes sign when MACD crosses its signal line;
+                // leaving a zero histogram is no cross.
+                let cross = closed
+                    .filter(|closed| closed * histogram < 0.0)
+                    .map(|_| Signal::Cross(Direction::of(histogram, 0.0)));
+                Reading::new(value)
+                    .with_direction(closed.map(|closed| Direction::of(histogram, closed)))
+                    .with_signal(cross)
+            })
+    }
+}
+
+impl BarIndicator for Macd {
+    fn clear(&mut self) {
+        for line in [&mut self.fast, &mut self.slow, &mut self.signal] {
+            line.ema.clear();
+        }
+        self.closed_histogram = None;
+    }
+
+    fn close_bar(&mut self, bar: &Kline, _bars: &BarAggregator) {
+        self.push(bar.close.to_f64());
+    }
+
+    fn reading(&self, bars: &BarAggregator) -> Option<Reading> {
+        self.with_forming(bars.latest()?.close.to_f64())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::{Direction, Macd, Reading, SPEC, Signal};
+
+    fn macd(fast: f64, slow: f64, signal: f64) -> Macd {
+        let saved: BTreeMap<String, f64> = [("fast", fast), ("slow", slow), ("signal", signal)]
+            .into_iter()
+            .map(|(key, value)| (key.to_string(), value))
+            .collect();
+        Macd::new(&SPEC.params(&saved))
+    }
+
+    #[test]
+    fn signal_starts_after_the_slow_ema_seeds() {
+        let mut macd = macd(2.0, 3.0, 2.0);
+
+        // The slow EMA seeds on the third close, so the fourth gives the
+        // first MACD value and the fifth seeds the signal line.
+        for close in [10.0, 11.0] {
+            macd.push(close);
+            assert_eq!(macd.macd(13.0), None);
+        }
+        macd.push(12.0);
+        assert!(macd.macd(13.0).is_some());
+        for close in [13.0, 14.0] {
+            assert_eq!(macd.histogram(close), None);
+            macd.push(close);
+        }
+        assert!(macd.histogram(15.0).is_some());
+        assert_eq!(macd.closed_histogram, None);
+    }
+
+    #[test]
+    fn histogram_of_a_step_in_a_flat_series() {
+        let mut macd = macd(12.0, 26.0, 9.0);
+
+        // Every EMA, the signal line included, settles at 100 and MACD at 0.
+        for _ in 0..35 {
+            macd.push(100.0);
+        }
+        // A step to 110 moves the EMAs by 2/13 and 2/27 of it, and the signal
+        // line by 2/10 of MACD.
+        let expected = 0.8 * 10.0 * (2.0 / 13.0 - 2.0 / 27.0);
+        assert!((macd.histogram(110.0).unwrap() - expected).abs() < 1e-9);
+        // In percent of the price, to thousandths.
+        assert_eq!(
+            macd.with_forming(110.0).map(|reading| reading.value),
+            Some(0.58)
+        );
+    }
+
+    #[test]
+    fn flags_momentum_and_signal_crosses() {
+        let mut macd = macd(12.0, 26.0, 9.0);
+        for _ in 0..36 {
+            macd.push(100.0);
+        }
+        // MACD sits on its signal line, so neither side counts as a cross.
+        assert_eq!(macd.closed_histogram, Some(0.0));
+        for price in [90.0, 110.0] {
+            assert_eq!(macd.with_forming(price).unwrap().signal, None);
+        }
+
+        macd.push(110.0);
+        assert!(macd.closed_histogram.unwrap() > 0.0);
+
+        let Reading {
+            direction, signal, ..
+        } = macd.with_forming(120.0).unwrap();
+        assert_eq!((direction, signal), (Some(Direction::Up), None));
+        let Reading {
+            direction, signal, ..
+        } = macd.with_forming(100.0).unwrap();
+        assert_eq!((direction, signal), (Some(Direction::Down), None));
+        // Far enough down the histogram turns negative: MACD crosses below its
+        // signal line.
+        let reading = macd.with_forming(90.0).unwrap();
+        assert!(reading.value < 0.0);
+        assert_eq!(reading.direction, Some(Direction::Down));
+        assert_eq!(reading.signal, Some(Signal::Cross(Direction::Down)));
+        assert_eq!(SPEC.event(reading), Some("bearish signal cross"));
+    }
+}
@@ -1,106 +1,96 @@
+mod adx;
+mod aggregator;
+mod atr;
+mod bollinger;
+mod derivatives;
 mod indicator;
+mod ma_cross;
+mod macd;
+mod moving_average;
+mod registry;
 mod rsi;
+mod series;
+mod stochastic;
 mod volatility;
+mod volume;
+mod vwap;
 
 use std::collections::HashMap;
 
-use crate::message_bus::{KlineEvent, KlineHist};
-use crate::types::{Kline, Pair, Timeframe};
-use rsi::{Rsi, RsiInput};
-use volatility::{Volatility, VolatilityInput};
+use crate::message_bus::KlineHist;
+use crate::types::{Kline, Pair, Timeframe, UtcOffset, config::IndicatorSetup};
 
-use indicator::Indicator;
-
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
-pub enum IndicatorName {
-    Rsi,
-    Volatility,
-}
-
-#[derive(Debug, Clone)]
-pub enum IndicatorResult {
-    Rsi(<Rsi as Indicator>::Output),
-    Volatility(<Volatility as Indicator>::Output),
-}
-
-impl IndicatorResult {
-    pub fn into_rsi_value(self) -> Option<f32> {
-        match self {
-            IndicatorResult::Rsi(v) => v,
-            _ => None,
-        }
-    }
-
-    pub fn into_volatility_value(self) -> Option<f32> {
-        match self {
-            IndicatorResult::Volatility(v) => v,
-            _ => None,
-        }
-    }
-}
+pub use aggregator::BarAggregator;
+pub use derivatives::{funding_percent, open_interest_change};
+pub use indicator::{Anchor, Direction, Indicator, Lookback, MinuteSpan, Reading, Signal};
+pub use registry::{
+    BarFeed, Feed, IndicatorId, IndicatorSpec, ParamKind, ParamSpec, Params, REGISTRY, Threshold,
+    ThresholdSpec, TimeframeSet,
+};
+pub use series::SharedSeries;
 
 pub struct IndicatorManager {
-    rsi: HashMap<(Pair, Timeframe, IndicatorName), Rsi>,
-    vol: HashMap<(Pair, Timeframe, IndicatorName), Volatility>,
+    bars: HashMap<(Pair, Timeframe), BarAggregator>,
+    series: HashMap<(Pair, Timeframe), SharedSeries>,
+    indicators: HashMap<(Pair, Timeframe, IndicatorId), Box<dyn Indicator + Send + Sync>>,
+    utc_offset: UtcOffset,
 }
 
 impl IndicatorManager {
-    pub fn new() -> Self {
+    pub fn new(utc_offset: UtcOffset) -> Self {
         Self {
-            rsi: HashMap::new(),
-            vol: HashMap::new(),
+            utc_offset,
+            bars: HashMap::new(),
+            series: HashMap::new(),
+            indicators: HashMap::new(),
         }
     }
 
-    fn key(
-        pair: &Pair,
-        timeframe: &Timeframe,
-        name: IndicatorName,
-    ) -> (Pair, Timeframe, IndicatorName) {
-        (pair.clone(), *timeframe, name)
+    /// Feed a 1m bar into the shared aggregator of (pair, timeframe).
+    /// Call once per bar and timeframe, before updating that timeframe's indicators.
+    pub fn push_bar(&mut self, pair: &Pair, timeframe: &Timeframe, bar_1m: &Kline) {
+        self.bars
+            .entry((pair.clone(), *timeframe))
+            .or_insert_with(|| BarAggregator::new(*timeframe, self.utc_offset))
+            .push(*bar_1m);
     }
 
     pub fn update_khist(&mut self, khist: KlineHist) {
         let pair = khist.pair.clone();
         let timeframe = khist.indicator_tf;
-        let indicator = khist.indicator;
-        let key = Self::key(&pair, &timeframe, indicator);
-        match khist.indicator {
-            IndicatorName::Rsi => {
-                let mut entry = self.rsi.get_mut(&key).unwrap();
-                entry.update_khist(khist);
-            }
-            IndicatorName::Volatility => {
-                let mut entry = self.vol.get_mut(&key).unwrap();
-                entry.update_khist(khist);
-            }
+        let key = (pair.clone(), timeframe, khist.indicator);
+        let bars = self
+            .bars
+            .entry((pair.clone(), timeframe))
+            .or_insert_with(|| BarAggregator::new(timeframe, self.utc_offset));
+        bars.seed(&khist.hist_1m, khist.head.as_ref());
+        let series = self.series.entry((pair, timeframe)).or_default();
+
+        if let Some(entry) = self.indicators.get_mut(&key) {
+            entry.update_khist(khist, bars, series);
         }
     }
 
+    /// Run `setup`'s indicator on the bars of (pair, timeframe), building it
+    /// on first use. `None` when there is nothing new to show, or the
+    /// indicator isn't computed from bars.
     pub fn update(
         &mut self,
         pair: &Pair,
-        timeframe: &Timeframe,
-        indicator: IndicatorName,
-        bar_1m: &Kline,
-    ) -> IndicatorResult {
-        match indicator {
-            IndicatorName::Rsi => {
-                let key = Self::key(pair, timeframe, indicator);
-                let entry = self
-                    .rsi
-                    .entry(key)
-                    .or_insert_with(|| Rsi::new(14, timeframe, pair));
-                IndicatorResult::Rsi(entry.update(RsiInput { bar_1m: *bar_1m }))
-            }
-            IndicatorName::Volatility => {
-                let key = Self::key(pair, timeframe, indicator);
-                let entry = self
-                    .vol
-                    .entry(key)
-                    .or_insert_with(|| Volatility::new(timeframe, pair));
-                IndicatorResult::Volatility(entry.update(VolatilityInput { bar_1m: *bar_1m }))
-            }
-        }
+        timeframe: Timeframe,
+        setup: &IndicatorSetup,
+    ) -> Option<Reading> {
+        let Feed::Bars(feed) = &setup.spec().feed else {
+            return None;
+        };
+        let bars = self
+            .bars
+            .entry((pair.clone(), timeframe))
+            .or_insert_with(|| BarAggregator::new(timeframe, self.utc_offset));
+        let series = self.series.entry((pair.clone(), timeframe)).or_default();
+        self.indicators
+            .entry((pair.clone(), timeframe, setup.id()))
+            .or_insert_with(|| (feed.build)(setup.params(), timeframe, pair))
+            .update(bars, series)
     }
 }
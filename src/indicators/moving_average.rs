@@ -0,0 +1,252 @@
+use crate::types::{Kline, RollingSum, RollingWeightedSum, Timeframe};
+
+use super::{
+    aggregator::BarAggregator,
+    indicator::{BarIndicator, Lookback, MinuteSpan, Reading, Staged, shown},
+    registry::{
+        BarFeed, Feed, IndicatorId, IndicatorSpec, ParamKind, ParamSpec, Params, ThresholdSpec,
+        TimeframeSet,
+    },
+};
+
+const LENGTH: &str = "length";
+
+/// Distance of the price from its simple moving average, in percent.
+pub const SMA: IndicatorSpec = spec(
+    IndicatorId::SMA,
+    "SMA",
+    &[length_param(50.0)],
+    Feed::Bars(BarFeed {
+        lookback: |params, tf| MovingAverage::lookback(Kind::Simple, params, tf),
+        build: |params, _, _| Box::new(Staged::new(MovingAverage::new(Kind::Simple, params))),
+    }),
+);
+
+/// Distance of the price from its exponential moving average, in percent.
+pub const EMA: IndicatorSpec = spec(
+    IndicatorId::EMA,
+    "EMA",
+    &[length_param(20.0)],
+    Feed::Bars(BarFeed {
+        lookback: |params, tf| MovingAverage::lookback(Kind::Exponential, params, tf),
+        build: |params, _, _| Box::new(Staged::new(MovingAverage::new(Kind::Exponential, params))),
+    }),
+);
+
+/// Distance of the price from its linearly weighted moving average, in percent.
+pub const WMA: IndicatorSpec = spec(
+    IndicatorId::WMA,
+    "WMA",
+    &[length_param(20.0)],
+    Feed::Bars(BarFeed {
+        lookback: |params, tf| MovingAverage::lookback(Kind::Weighted, params, tf),
+        build: |params, _, _| Box::new(Staged::new(MovingAverage::new(Kind::Weighted, params))),
+    }),
+);
+
+const fn spec(
+    id: IndicatorId,
+    label: &'static str,
+    params: &'static [ParamSpec],
+    feed: Feed,
+) -> IndicatorSpec {
+    IndicatorSpec {
+        id,
+        label,
+        title: label,
+        feed,
+        timeframes: TimeframeSet::All,
+        default_enabled: false,
+        default_timeframes: &[Timeframe::H1, Timeframe::H4],
+        params,
+        threshold: ThresholdSpec::Magnitude {
+            default: 0.0,
+            step: 0.5,
+            coarse_step: 5.0,
+            decimals: 1,
+        },
+        format: |value| format!("{value:+.2}%"),
+        width: 7,
+        event: None,
+    }
+}
+
+const fn length_param(default: f64) -> ParamSpec {
+    ParamSpec {
+        key: LENGTH,
+        label: "Length",
+        kind: ParamKind::Count,
+        default,
+        min: 2.0,
+        max: 500.0,
+        step: 1.0,
+    }
+}
+
+/// Moving average types, in the order of [`KIND_LABELS`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum Kind {
+    Simple,
+    Exponential,
+    Weighted,
+}
+
+pub(super) const KIND_LABELS: [&str; 3] = ["SMA", "EMA", "WMA"];
+
+impl Kind {
+    /// Kind chosen by a `Choice(&KIND_LABELS)` parameter.
+    pub(super) fn from_choice(value: f64) -> Kind {
+        match value as usize {
+            0 => Kind::Simple,
+            2 => Kind::Weighted,
+            _ => Kind::Exponential,
+        }
+    }
+
+    /// Closed bars needed before the average is settled; an EMA also needs its
+    /// SMA seed to fade, which four lengths bring to about e^-8 of the value.
+    pub(super) fn bars(self, length: usize) -> usize {
+        match self {
+            Kind::Exponential => length * 4,
+            Kind::Simple | Kind::Weighted => length,
+        }
+    }
+}
+
+/// Price distance from a moving average of closes.
+///
+/// The average covers the last `length` closes of the timeframe, the bar still
+/// forming included, so it moves with the price like an exchange chart.
+#[derive(Debug, Clone)]
+pub struct MovingAverage {
+    length: usize,
+    average: Average,
+}
+
+/// Running state over closed bars only; the forming close is folded in per tick.
+#[derive(Debug, Clone)]
+pub(super) enum Average {
+    Simple(RollingSum),
+    Exponential { ema: Option<f64>, seed: RollingSum },
+    Weighted(RollingWeightedSum),
+}
+
+impl MovingAverage {
+    fn new(kind: Kind, params: &Params) -> Self {
+        let length = params.count(LENGTH).max(2);
+        Self {
+            length,
+            average: Average::new(kind, length),
+        }
+    }
+
+    fn lookback(kind: Kind, params: &Params, tf: Timeframe) -> Lookback {
+        Lookback {
+            minutes: BarAggregator::window_minutes(tf),
+            bars: kind.bars(params.count(LENGTH).max(2)),
+            span: MinuteSpan::Forming,
+        }
+    }
+}
+
+impl Average {
+    pub(super) fn new(kind: Kind, length: usize) -> Self {
+        // The forming close takes the last of the `length` places.
+        let closed = length - 1;
+        match kind {
+            Kind::Simple => Average::Simple(RollingSum::new(closed)),
+            Kind::Exponential => Average::Exponential {
+                ema: None,
+                seed: RollingSum::new(length),
+            },
+            Kind::Weighted => Average::Weighted(RollingWeightedSum::new(closed)),
+        }
+    }
+
+    /// Fold in the close of a completed bar.
+    pub(super) fn push(&mut self, close: f64, length: usize) {
+        match self {
+            Average::Simple(sum) => sum.push(close),
+            Average::Weighted(sum) => sum.push(close),
+            Average::Exponential { ema, seed } => match ema {
+                Some(ema) => *ema += alpha(length) * (close - *ema),
+                None => {
+                    seed.push(close);
+                    *ema = seed.mean();
+                }
+            },
+        }
+    }
+
+    /// Average with the forming bar's close as the newest value.
+    pub(super) fn with_forming(&self, close: f64, length: usize) -> Option<f64> {
+        let len = length as f64;
+        match self {
+            Average::Simple(sum) => sum.is_full().then(|| (sum.sum() + close) / len),
+            Average::Weighted(sum) => sum
+                .is_full()
+                .then(|| (sum.weighted_sum() + len * close) / (len * (len + 1.0) / 2.0)),
+            Average::Exponential { ema, .. } => ema.map(|ema| ema + alpha(length) * (close - ema)),
+        }
+    }
+
+    pub(super) fn clear(&mut self) {
+        match self {
+            Average::Simple(sum) => sum.clear(),
+            Average::Weighted(sum) => sum.clear(),
+            Average::Exponential { ema, seed } => {
+                *ema = None;
+                seed.clear();
+            }
+        }
+    }
+}
+
+fn alpha(length: usize) -> f64 {
+    2.0 / (length as f64 + 1.0)
+}
+
+impl BarIndicator for MovingAverage {
+    fn clear(&mut self) {
+        self.average.clear();
+    }
+
+    fn close_bar(&mut self, bar: &Kline, _bars: &BarAggregator) {
+        self.average.push(bar.close.to_f64(), self.length);
+    }
+
+    fn reading(&self, bars: &BarAggregator) -> Option<Reading> {
+        let price = bars.latest()?.close.to_f64();
+        self.average
+            .with_forming(price, self.length)
+            .filter(|average| *average > 0.0)
+            .map(|average| Reading::new(shown((price / average - 1.0) * 100.0, 2)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Average, Kind};
+
+    #[test]
+    fn averages_include_the_forming_close() {
+        let closes = [10.0, 11.0, 12.0, 13.0];
+        let mut simple = Average::new(Kind::Simple, 3);
+        let mut weighted = Average::new(Kind::Weighted, 3);
+        let mut exponential = Average::new(Kind::Exponential, 3);
+        for close in closes {
+            simple.push(close, 3);
+            weighted.push(close, 3);
+            exponential.push(close, 3);
+        }
+
+        // Last three values: 12, 13 closed and 14 forming.
+        assert_eq!(simple.with_forming(14.0, 3), Some(13.0));
+        let expected = (12.0 + 2.0 * 13.0 + 3.0 * 14.0) / 6.0;
+        assert!((weighted.with_forming(14.0, 3).unwrap() - expected).abs() < 1e-9);
+        // Seeded with the SMA of 10..12, then 13 closed and 14 forming.
+        let ema = 11.0 + 0.5 * (13.0 - 11.0);
+        let ema = ema + 0.5 * (14.0 - ema);
+        assert!((exponential.with_forming(14.0, 3).unwrap() - ema).abs() < 1e-9);
+    }
+}
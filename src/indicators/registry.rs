@@ -0,0 +1,378 @@
+//! Every indicator the grid can show, described once.
+//!
+//! A spec carries what the rest of the app needs to know about an indicator:
+//! its parameters, the timeframes it runs on, the history it needs at warmup,
+//! how its values are formatted and how thresholds highlight them. Config,
+//! engine, history and UI read the spec instead of matching on indicator names,
+//! so adding an indicator means writing it and listing its spec in [`REGISTRY`].
+
+use std::collections::BTreeMap;
+
+use crate::{
+    time::{civil_from_days, now_millis},
+    types::{Pair, Timeframe},
+};
+
+use super::{
+    adx, atr, bollinger, derivatives,
+    indicator::{Indicator, Lookback, Reading},
+    ma_cross, macd, moving_average, rsi, stochastic, volatility, volume, vwap,
+};
+
+/// Every indicator, in settings and dashboard order.
+pub static REGISTRY: &[IndicatorSpec] = &[
+    volatility::SPEC,
+    rsi::SPEC,
+    stochastic::STOCH_RSI,
+    stochastic::STOCHASTIC,
+    moving_average::SMA,
+    moving_average::EMA,
+    moving_average::WMA,
+    ma_cross::SPEC,
+    macd::SPEC,
+    bollinger::PERCENT_B,
+    bollinger::BANDWIDTH,
+    atr::ATR,
+    atr::NATR,
+    adx::SPEC,
+    volume::RELATIVE_VOLUME,
+    volume::OBV,
+    volume::AD_LINE,
+    vwap::VWAP,
+    vwap::VWAP_BANDS,
+    derivatives::FUNDING,
+    derivatives::OPEN_INTEREST,
+];
+
+/// Stable key of a registered indicator; also its key in saved presets.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct IndicatorId(&'static str);
+
+impl IndicatorId {
+    pub const VOLATILITY: IndicatorId = IndicatorId("volatility");
+    pub const RSI: IndicatorId = IndicatorId("rsi");
+    pub const FUNDING: IndicatorId = IndicatorId("funding");
+    pub const OPEN_INTEREST: IndicatorId = IndicatorId("open_interest");
+    pub const SMA: IndicatorId = IndicatorId("sma");
+    pub const EMA: IndicatorId = IndicatorId("ema");
+    pub const WMA: IndicatorId = IndicatorId("wma");
+    pub const MA_CROSS: IndicatorId = IndicatorId("ma_cross");
+    pub const MACD: IndicatorId = IndicatorId("macd");
+    pub const BB_PERCENT_B: IndicatorId = IndicatorId("bb_percent_b");
+    pub const BB_BANDWIDTH: IndicatorId = IndicatorId("bb_bandwidth");
+    pub const ATR: IndicatorId = IndicatorId("atr");
+    pub const NATR: IndicatorId = IndicatorId("natr");
+    pub const STOCH_RSI: IndicatorId = IndicatorId("stoch_rsi");
+    pub const STOCHASTIC: IndicatorId = IndicatorId("stochastic");
+    pub const RELATIVE_VOLUME: IndicatorId = IndicatorId("relative_volume");
+    pub const OBV: IndicatorId = IndicatorId("obv");
+    pub const AD_LINE: IndicatorId = IndicatorId("ad_line");
+    pub const VWAP: IndicatorId = IndicatorId("vwap");
+    pub const VWAP_BANDS: IndicatorId = IndicatorId("vwap_bands");
+    pub const ADX: IndicatorId = IndicatorId("adx");
+
+    /// Registered indicator saved under `key`.
+    pub fn from_key(key: &str) -> Option<IndicatorId> {
+        REGISTRY
+            .iter()
+            .find(|spec| spec.id.0 == key)
+            .map(|spec| spec.id)
+    }
+
+    pub fn as_str(self) -> &'static str {
+        self.0
+    }
+
+    pub fn spec(self) -> &'static IndicatorSpec {
+        REGISTRY
+            .iter()
+            .find(|spec| spec.id == self)
+            .expect("indicator ids come from the registry")
+    }
+}
+
+/// Description of one indicator.
+#[derive(Debug)]
+pub struct IndicatorSpec {
+    pub id: IndicatorId,
+    /// Dashboard group header.
+    pub label: &'static str,
+    /// Settings panel title.
+    pub title: &'static str,
+    pub feed: Feed,
+    pub timeframes: TimeframeSet,
+    pub default_enabled: bool,
+    /// Timeframes a new preset turns on.
+    pub default_timeframes: &'static [Timeframe],
+    pub params: &'static [ParamSpec],
+    pub threshold: ThresholdSpec,
+    /// Dashboard cell text.
+    pub format: fn(f32) -> String,
+    /// Dashboard cell width, in characters.
+    pub width: u16,
+    /// Names the event a reading marks, e.g. a cross; the engine logs these
+    /// under the `alerts` target.
+    pub event: Option<fn(Reading) -> Option<&'static str>>,
+}
+
+impl IndicatorSpec {
+    /// Timeframes the indicator can run on, given the preset's custom ones.
+    pub fn timeframes(&self, custom: &[Timeframe]) -> Vec<Timeframe> {
+        match self.timeframes {
+            TimeframeSet::All => {
+                let mut timeframes = Timeframe::ALL.to_vec();
+                timeframes.extend_from_slice(custom);
+                timeframes
+            }
+            TimeframeSet::Only(timeframes) => timeframes.to_vec(),
+            TimeframeSet::Single(tf) => vec![tf],
+        }
+    }
+
+    /// Dashboard column header; a single column has no timeframe to show.
+    pub fn column_label(&self, tf: Timeframe) -> String {
+        match self.timeframes {
+            TimeframeSet::Single(_) => "now".to_string(),
+            _ => tf.to_string(),
+        }
+    }
+
+    /// Perpetual metrics only exist on the futures market.
+    pub fn futures_only(&self) -> bool {
+        matches!(self.feed, Feed::Derivatives)
+    }
+
+    /// Parameter values from a preset, defaulted and clamped to their range.
+    pub fn params(&self, saved: &BTreeMap<String, f64>) -> Params {
+        Params(
+            self.params
+                .iter()
+                .map(|param| (param.key, param.resolve(saved.get(param.key).copied())))
+                .collect(),
+        )
+    }
+
+    pub fn format(&self, value: f32) -> String {
+        (self.format)(value)
+    }
+
+    pub fn event(&self, reading: Reading) -> Option<&'static str> {
+        self.event.and_then(|event| event(reading))
+    }
+}
+
+/// Where an indicator's input comes from.
+#[derive(Debug)]
+pub enum Feed {
+    /// Computed by the engine from the shared bar aggregators.
+    Bars(BarFeed),
+    /// Funding and open interest, polled by the history service.
+    Derivatives,
+}
+
+#[derive(Debug)]
+pub struct BarFeed {
+    /// History needed on a timeframe before the first value.
+    pub lookback: fn(&Params, Timeframe) -> Lookback,
+    pub build: fn(&Params, Timeframe, &Pair) -> Box<dyn Indicator + Send + Sync>,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum TimeframeSet {
+    /// Exchange timeframes plus the preset's custom ones.
+    All,
+    /// A fixed list, e.g. the periods Binance keeps open interest for.
+    Only(&'static [Timeframe]),
+    /// One column with no timeframe of its own, kept in this slot.
+    Single(Timeframe),
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct ParamSpec {
+    /// Key in saved presets.
+    pub key: &'static str,
+    pub label: &'static str,
+    pub kind: ParamKind,
+    pub default: f64,
+    pub min: f64,
+    pub max: f64,
+    pub step: f64,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum ParamKind {
+    /// Whole number, e.g. a length in bars.
+    Count,
+    /// Whole multiple of another parameter, shown as `10x`.
+    Multiple,
+    /// Number with this many decimals, e.g. a band width in deviations.
+    Decimal(usize),
+    /// Index into a list of options.
+    Choice(&'static [&'static str]),
+    /// UTC time in whole minutes since the Unix epoch, 0 while unset; steps
+    /// from unset start at the current hour, and coarse steps are a day.
+    Time,
+}
+
+impl ParamSpec {
+    pub fn resolve(&self, saved: Option<f64>) -> f64 {
+        let (min, max) = match self.kind {
+            ParamKind::Choice(options) => (0.0, options.len().saturating_sub(1) as f64),
+            _ => (self.min, self.max),
+        };
+        let value = saved
+            .filter(|value| value.is_finite())
+            .unwrap_or(self.default)
+            .clamp(min, max);
+        let scale = match self.kind {
+            ParamKind::Decimal(decimals) => 10_f64.powi(decimals as i32),
+            _ => 1.0,
+        };
+        (value * scale).round() / scale
+    }
+
+    /// Value `steps` steps away; choices wrap around.
+    pub fn stepped(&self, value: f64, steps: i32, coarse: bool) -> f64 {
+        match self.kind {
+            ParamKind::Choice(options) => {
+                let len = options.len().max(1) as i64;
+                (value as i64 + i64::from(steps)).rem_euclid(len) as f64
+            }
+            ParamKind::Time if value <= 0.0 => {
+                let hour = now_millis().div_euclid(3_600_000) * 60;
+                self.resolve(Some(hour as f64))
+            }
+            ParamKind::Time if coarse => self.resolve(Some(value + f64::from(steps) * 1440.0)),
+            _ => self.resolve(Some(value + f64::from(steps) * self.step)),
+        }
+    }
+
+    pub fn display(&self, value: f64) -> String {
+        match self.kind {
+            ParamKind::Count => format!("{value:.0}"),
+            ParamKind::Multiple => format!("{value:.0}x"),
+            ParamKind::Decimal(decimals) => format!("{value:.decimals$}"),
+            ParamKind::Choice(options) => {
+                let label = options.get(value as usize).copied().unwrap_or_default();
+                format!("{label} v")
+            }
+            ParamKind::Time if value <= 0.0 => "unset".to_string(),
+            ParamKind::Time => {
+                let minutes = value as i64;
+                let (_, month, day) = civil_from_days(minutes.div_euclid(1440));
+                let minute_of_day = minutes.rem_euclid(1440);
+                format!(
+                    "{month:02}-{day:02} {:02}:{:02}",
+                    minute_of_day / 60,
+                    minute_of_day % 60
+                )
+            }
+        }
+    }
+}
+
+/// Resolved parameter values of one indicator.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Params(BTreeMap<&'static str, f64>);
+
+impl Params {
+    pub fn get(&self, key: &str) -> f64 {
+        self.0.get(key).copied().unwrap_or_default()
+    }
+
+    /// Whole-number parameter, at least 1.
+    pub fn count(&self, key: &str) -> usize {
+        self.get(key).max(1.0) as usize
+    }
+}
+
+/// How thresholds highlight an indicator's values.
+#[derive(Debug, Clone, Copy)]
+pub enum ThresholdSpec {
+    /// Set per timeframe; values at least that far from zero, either sign, are
+    /// highlighted. Zero turns the highlight off.
+    Magnitude {
+        default: f32,
+        step: f32,
+        coarse_step: f32,
+        decimals: usize,
+    },
+    /// Fixed oversold/overbought band; text is colored against its midpoint.
+    Band { lower: f32, upper: f32 },
+    /// Set per timeframe in bars; values encode signed bars since an event
+    /// plus one, and those within that many bars are highlighted.
+    Recency {
+        default: f32,
+        step: f32,
+        coarse_step: f32,
+    },
+    /// Never highlighted and not colored; for values that don't compare
+    /// across pairs.
+    Plain,
+}
+
+impl ThresholdSpec {
+    /// Column threshold from the value saved for its timeframe.
+    pub fn resolve(&self, saved: f32) -> Threshold {
+        match *self {
+            ThresholdSpec::Magnitude { .. } => Threshold::Magnitude(saved.abs()),
+            ThresholdSpec::Band { lower, upper } => Threshold::Band { lower, upper },
+            ThresholdSpec::Recency { .. } => Threshold::Recency(saved.abs()),
+            ThresholdSpec::Plain => Threshold::Plain,
+        }
+    }
+
+    /// Saved value a newly listed timeframe starts with.
+    pub fn default_value(&self) -> f32 {
+        match *self {
+            ThresholdSpec::Magnitude { default, .. } | ThresholdSpec::Recency { default, .. } => {
+                default
+            }
+            ThresholdSpec::Band { .. } | ThresholdSpec::Plain => 0.0,
+        }
+    }
+
+    /// Value the text color flips at.
+    pub fn midpoint(&self) -> f32 {
+        match *self {
+            ThresholdSpec::Magnitude { .. }
+            | ThresholdSpec::Recency { .. }
+            | ThresholdSpec::Plain => 0.0,
+            ThresholdSpec::Band { lower, upper } => (lower + upper) / 2.0,
+        }
+    }
+
+    /// Fine and coarse steps of an editable threshold.
+    pub fn steps(&self) -> Option<(f32, f32)> {
+        match *self {
+            ThresholdSpec::Magnitude {
+                step, coarse_step, ..
+            }
+            | ThresholdSpec::Recency {
+                step, coarse_step, ..
+            } => Some((step, coarse_step)),
+            ThresholdSpec::Band { .. } | ThresholdSpec::Plain => None,
+        }
+    }
+
+    /// Decimals the threshold is edited and shown with.
+    pub fn decimals(&self) -> usize {
+        match *self {
+            ThresholdSpec::Magnitude { decimals, .. } => decimals,
+            ThresholdSpec::Band { .. } | ThresholdSpec::Recency { .. } | ThresholdSpec::Plain => 0,
+        }
+    }
+
+    pub fn is_editable(&self) -> bool {
+        self.steps().is_some()
+    }
+}
+
+/// Threshold of one dashboard column.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Threshold {
+    Magnitude(f32),
+    Band { lower: f32, upper: f32 },
+    Recency(f32),
+    Plain,
+}
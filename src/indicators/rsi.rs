@@ -1,180 +1,169 @@
-use log::info;
-use std::collections::HashMap;
-
-use crate::types::Kline;
+use crate::types::{Kline, Price, RingBuffer, Wilder};
 use crate::{
     message_bus::KlineHist,
-    time::now_millis,
-    types::{Bar1m, Pair, RingBuffer, Timeframe},
+    types::{Pair, Timeframe},
+};
+
+use super::{
+    aggregator::BarAggregator,
+    indicator::{Indicator, Lookback, MinuteSpan, Publisher, Reading, closed_history},
+    registry::{
+        BarFeed, Feed, IndicatorId, IndicatorSpec, ParamKind, ParamSpec, ThresholdSpec,
+        TimeframeSet,
+    },
+    series::SharedSeries,
 };
 
-use super::indicator::Indicator;
+const LENGTH: &str = "length";
+const SEED: &str = "seed";
+
+/// Default of the seed parameter, also used by indicators built on the RSI.
+pub(super) const DEFAULT_SEED: usize = 10;
+
+/// Wilder's RSI of closes; oversold below 30, overbought above 70.
+pub const SPEC: IndicatorSpec = IndicatorSpec {
+    id: IndicatorId::RSI,
+    label: "RSI",
+    title: "RSI",
+    feed: Feed::Bars(BarFeed {
+        lookback: |params, tf| Rsi::lookback(params.count(LENGTH), params.count(SEED), tf),
+        build: |params, tf, pair| Box::new(Rsi::new(params.count(LENGTH), &tf, pair)),
+    }),
+    timeframes: TimeframeSet::All,
+    default_enabled: true,
+    default_timeframes: &[Timeframe::M5, Timeframe::M15],
+    params: &[
+        ParamSpec {
+            key: LENGTH,
+            label: "Length",
+            kind: ParamKind::Count,
+            default: 14.0,
+            min: 1.0,
+            max: 250.0,
+            step: 1.0,
+        },
+        ParamSpec {
+            key: "source",
+            label: "Source",
+            kind: ParamKind::Choice(&["Open", "High", "Low", "Close"]),
+            default: 3.0,
+            min: 0.0,
+            max: 3.0,
+            step: 1.0,
+        },
+        // Warmup history in multiples of the length. Ten lengths leave the SMA
+        // seed weighing about e^-9 of the Wilder average, whatever the length.
+        ParamSpec {
+            key: SEED,
+            label: "Seed",
+            kind: ParamKind::Multiple,
+            default: DEFAULT_SEED as f64,
+            min: 1.0,
+            max: 50.0,
+            step: 1.0,
+        },
+    ],
+    threshold: ThresholdSpec::Band {
+        lower: 30.0,
+        upper: 70.0,
+    },
+    format: |value| format!("{value:.1}"),
+    width: 6,
+    event: None,
+};
+
+/// Closed-bar RSI values kept for the indicators built on the RSI.
+const SERIES_HISTORY: usize = 1024;
 
 #[derive(Debug, Clone)]
 pub struct Rsi {
-    stage: Stage,
     pair: Pair,
     tf: Timeframe,
     period: usize,
-    buffer: RingBuffer<Bar1m>,
-    window_1m_bars: Option<RingBuffer<Bar1m>>,
-    aggr_closed_bars: Option<BarAggregation>, // aggregation of closed 1m bars of the current bar
+    publisher: Publisher,
+}
+
+/// Wilder RSI of one (pair, timeframe), shared through [`SharedSeries`].
+///
+/// Holds the averages up to the last closed bar and the RSI of recent closed
+/// bars. Every reader syncs it on update; a closed bar is folded in once.
+#[derive(Debug, Clone)]
+pub struct RsiSeries {
+    period: usize,
     previous_bar: Option<PreviousBar>,
-    value: Option<f32>,
-    to_send: bool,
-    sent_once: bool,
+    values: RingBuffer<f64>, // RSI of closed bars, oldest first
+    closed: u64,             // values pushed since the last seed
+    seeds: u64,
+    seeded_bars: usize,
 }
 
 #[derive(Debug, Clone)]
 struct PreviousBar {
-    close: f64,
+    open_time: i64,
+    close: Price,
     avg_gain: f64,
     avg_loss: f64,
 }
 
-#[derive(Debug, Clone)]
-struct BarAggregation {
-    open: f64,
-    high: f64,
-    low: f64,
-    close: f64,
-    volume: f64,
-}
-
-#[derive(Debug, Clone)]
-pub enum Stage {
-    New,
-    WarmUp,
-    Ready,
-}
-
 impl Rsi {
     pub fn new(period: usize, tf: &Timeframe, pair: &Pair) -> Self {
         Self {
-            stage: Stage::New,
             pair: pair.clone(),
             period,
             tf: *tf,
-            buffer: RingBuffer::new(10),
-            window_1m_bars: None,
-            aggr_closed_bars: None,
-            previous_bar: None,
-            value: None,
-            to_send: false,
-            sent_once: false,
+            publisher: Publisher::default(),
         }
     }
-    fn update_window(&mut self, bar: Bar1m) {
-        //The window_1m_bars is already set to RingBugger in set_window_1m_bars_from_history
-        //So assume that, and don't set again here.
-        let now_ms = now_millis();
-        let current_tf_open = self.tf.nearest_ms(now_ms);
-        let mut update_prev_from: Option<Bar1m> = None;
-
-        {
-            let Some(window) = self.window_1m_bars.as_mut() else {
-                return;
-            };
-
-            let last = window.back().copied();
-            match last {
-                None => {
-                    window.push(bar);
-                    return;
-                }
-                Some(last) if last.open_time == bar.open_time => {
-                    window.replace_last(bar);
-                    return;
-                }
-                Some(last) if last.open_time > bar.open_time => {
-                    return;
-                }
-                Some(last) => {
-                    if window
-                        .front()
-                        .map(|first| first.open_time < current_tf_open)
-                        .unwrap_or(false)
-                    {
-                        // capture last before trimming to update previous_bar later
-                        update_prev_from = Some(last);
-                        window.retain_by_open_time(|ts| ts >= current_tf_open);
-                    }
-                    window.push(bar);
-                    self.set_aggregate();
-                }
-            }
-        }
 
-        if let Some(last_before_trim) = update_prev_from {
-            self.update_previous_bar_from_last(last_before_trim);
+    /// History for a `period` RSI seeded over `seed` periods of `tf` bars.
+    ///
+    /// The SMA seed still weighs `((period - 1) / period)^n` after `n` Wilder
+    /// steps, so longer seeds converge closer to the exchange's values. The 1m
+    /// bars cover the tf bar that is still forming.
+    pub fn lookback(period: usize, seed: usize, tf: Timeframe) -> Lookback {
+        Lookback {
+            minutes: BarAggregator::window_minutes(tf),
+            bars: period.max(1) * seed.max(1) + 1,
+            span: MinuteSpan::Forming,
         }
     }
-    fn update_previous_bar_from_last(&mut self, last: Bar1m) {
-        let Some(prev) = self.previous_bar.as_ref() else {
-            return;
-        };
 
-        let diff = last.close - prev.close;
-        let gain = if diff > 0.0 { diff } else { 0.0 };
-        let loss = if diff < 0.0 { -diff } else { 0.0 };
-        let period = self.period as f64;
-        let avg_gain = (prev.avg_gain * (period - 1.0) + gain) / period;
-        let avg_loss = (prev.avg_loss * (period - 1.0) + loss) / period;
-
-        self.previous_bar = Some(PreviousBar {
-            close: last.close,
-            avg_gain,
-            avg_loss,
-        });
+    fn reading(bars: &BarAggregator, rsi: &RsiSeries) -> Option<Reading> {
+        let value = rsi.with_forming(bars.latest()?.close)?;
+        Some(Reading::new(value.trunc() as f32))
     }
-    fn set_value(&mut self) {
-        let Some(window) = self.window_1m_bars.as_ref() else {
-            return;
-        };
-        let Some(last) = window.back() else {
-            return;
-        };
-        let Some(prev) = self.previous_bar.as_ref() else {
-            return;
-        };
-
-        let diff = last.close - prev.close;
-        let gain = if diff > 0.0 { diff } else { 0.0 };
-        let loss = if diff < 0.0 { -diff } else { 0.0 };
-
-        let period = self.period as f64;
-        let avg_gain = (prev.avg_gain * (period - 1.0) + gain) / period;
-        let avg_loss = (prev.avg_loss * (period - 1.0) + loss) / period;
-
-        let new_value = if avg_loss == 0.0 {
-            Some(100 as f32)
-        } else {
-            let rs = avg_gain / avg_loss;
-            let v = 100.0 - (100.0 / (1.0 + rs));
-            Some(v.trunc() as f32)
-        };
-
-        let changed = new_value != self.value;
+}
 
-        if changed {
-            self.value = new_value;
+impl RsiSeries {
+    pub(super) fn new(period: usize) -> Self {
+        Self {
+            period: period.max(1),
+            previous_bar: None,
+            values: RingBuffer::new(SERIES_HISTORY),
+            closed: 0,
+            seeds: 0,
+            seeded_bars: 0,
         }
-        self.to_send = changed;
     }
-    fn set_previous_bar_from_history(&mut self, input: &KlineHist) {
-        let now_ms = now_millis();
-        let tf = input.indicator_tf;
-        let prev_open = tf.nearest_ms(now_ms) - tf.window_millis();
 
-        let closes: Vec<f64> = input
-            .hist_tf
-            .iter()
-            .filter(|bar| bar.open_time <= prev_open)
-            .map(|bar| bar.close)
-            .collect();
+    /// Rebuild from the closed bars of a warmup history. Readers warming up
+    /// with a shorter history of the same bars keep the longer seed.
+    pub(super) fn seed(&mut self, closed: &[Kline]) {
+        let newer = closed.last().is_some_and(|bar| {
+            self.previous_bar
+                .as_ref()
+                .is_none_or(|prev| bar.open_time > prev.open_time)
+        });
+        if !newer && closed.len() < self.seeded_bars {
+            return;
+        }
 
-        if closes.len() < self.period + 1 {
-            self.previous_bar = None;
+        self.previous_bar = None;
+        self.values.clear();
+        self.closed = 0;
+        self.seeds += 1;
+        self.seeded_bars = closed.len();
+        if closed.len() < self.period + 1 {
             return;
         }
 
@@ -182,7 +171,7 @@ impl Rsi {
         let mut losses = 0.0;
 
         for i in 1..=self.period {
-            let diff = closes[i] - closes[i - 1];
+            let diff = (closed[i].close - closed[i - 1].close).to_f64();
             if diff >= 0.0 {
                 gains += diff;
             } else {
@@ -190,162 +179,111 @@ impl Rsi {
             }
         }
 
-        let mut avg_gain = gains / self.period as f64;
-        let mut avg_loss = losses / self.period as f64;
-
-        for i in (self.period + 1)..closes.len() {
-            let diff = closes[i] - closes[i - 1];
-            let gain = if diff > 0.0 { diff } else { 0.0 };
-            let loss = if diff < 0.0 { -diff } else { 0.0 };
-
-            avg_gain = (avg_gain * (self.period as f64 - 1.0) + gain) / self.period as f64;
-            avg_loss = (avg_loss * (self.period as f64 - 1.0) + loss) / self.period as f64;
-        }
-
-        if let Some(close) = closes.last().copied() {
-            self.previous_bar = Some(PreviousBar {
-                close,
-                avg_gain,
-                avg_loss,
-            });
-        } else {
-            self.previous_bar = None;
+        let seed_bar = &closed[self.period];
+        self.previous_bar = Some(PreviousBar {
+            open_time: seed_bar.open_time,
+            close: seed_bar.close,
+            avg_gain: gains / self.period as f64,
+            avg_loss: losses / self.period as f64,
+        });
+        self.push_value();
+        for bar in &closed[self.period + 1..] {
+            self.close_bar(bar);
         }
     }
-    fn set_aggregate(&mut self) {
-        if self.tf == Timeframe::M1 {
-            self.aggr_closed_bars = None;
-            return;
-        }
 
-        let Some(window) = self.window_1m_bars.as_ref() else {
-            self.aggr_closed_bars = None;
+    /// Fold in the bar that just closed, unless another reader already did.
+    pub(super) fn sync(&mut self, bars: &BarAggregator) {
+        let Some(closed) = bars.just_closed().copied() else {
             return;
         };
+        if self
+            .previous_bar
+            .as_ref()
+            .is_some_and(|prev| closed.open_time > prev.open_time)
+        {
+            self.close_bar(&closed);
+        }
+    }
 
-        let mut bars = window.iter_without_last();
-
-        let Some(first_bar) = bars.next() else {
-            self.aggr_closed_bars = None;
-            return;
-        };
+    /// RSI with `close` as the forming bar's close; `None` until seeded.
+    pub(super) fn with_forming(&self, close: Price) -> Option<f64> {
+        let prev = self.previous_bar.as_ref()?;
+        let (avg_gain, avg_loss) = self.averages(prev, close);
+        Some(rsi(avg_gain, avg_loss))
+    }
 
-        let mut open = first_bar.open;
-        let mut close = first_bar.close;
-        let mut high = first_bar.high;
-        let mut low = first_bar.low;
-        let mut volume = first_bar.volume;
+    /// RSI of recent closed bars, oldest first.
+    pub(super) fn closed_values(&self) -> &RingBuffer<f64> {
+        &self.values
+    }
 
-        for bar in bars {
-            if bar.high > high {
-                high = bar.high;
-            }
-            if bar.low < low {
-                low = bar.low;
-            }
-            close = bar.close;
-            volume += bar.volume;
-        }
+    /// Closed values pushed since the last seed, older ones included.
+    pub(super) fn closed_count(&self) -> u64 {
+        self.closed
+    }
 
-        self.aggr_closed_bars = Some(BarAggregation {
-            open,
-            high,
-            low,
-            close,
-            volume,
-        });
+    /// Bumped on every rebuild; readers folding closed values start over.
+    pub(super) fn seeds(&self) -> u64 {
+        self.seeds
     }
-    fn update_buffer(&mut self, bar: Bar1m) {
-        match self.buffer.back() {
-            None => {
-                self.buffer.push(bar);
-            }
-            Some(last) if last.open_time < bar.open_time => {
-                self.buffer.push(bar);
-            }
-            Some(last) if !last.closed && last.open_time == bar.open_time => {
-                self.buffer.replace_last(bar);
-            }
-            _ => {}
-        }
+
+    /// Wilder step of both averages from the previous bar to `close`.
+    fn averages(&self, prev: &PreviousBar, close: Price) -> (f64, f64) {
+        // Exact difference of the quoted closes; the averages are floats.
+        let diff = (close - prev.close).to_f64();
+        let gain = if diff > 0.0 { diff } else { 0.0 };
+        let loss = if diff < 0.0 { -diff } else { 0.0 };
+        (
+            Wilder::step(prev.avg_gain, gain, self.period),
+            Wilder::step(prev.avg_loss, loss, self.period),
+        )
     }
-    fn set_window_1m_bars_from_history(&mut self, input: &KlineHist) {
-        let now_ms = now_millis();
-        let tf = input.indicator_tf;
-        let window_size = tf.window_minutes();
-        let window_1m_size = Timeframe::M1.window_millis();
-        let current_1m_start = Timeframe::M1.nearest_ms(now_ms);
-        let window_start_ts = tf.nearest_ms(now_ms);
-        let ws_by_open: HashMap<i64, Bar1m> = self
-            .buffer
-            .iter()
-            .map(|bar| (bar.open_time, *bar))
-            .collect();
-        let api_by_open: HashMap<i64, Bar1m> = input
-            .hist_1m
-            .iter()
-            .map(|bar| (bar.open_time, *bar))
-            .collect();
 
-        let mut window = RingBuffer::new(window_size);
-        for idx in 0..window_size {
-            let expected_open = window_start_ts + (idx as i64) * window_1m_size;
+    fn close_bar(&mut self, bar: &Kline) {
+        let Some(prev) = self.previous_bar.as_ref() else {
+            return;
+        };
 
-            if let Some(bar) = ws_by_open.get(&expected_open) {
-                window.push(*bar);
-                continue;
-            }
+        let (avg_gain, avg_loss) = self.averages(prev, bar.close);
+        self.previous_bar = Some(PreviousBar {
+            open_time: bar.open_time,
+            close: bar.close,
+            avg_gain,
+            avg_loss,
+        });
+        self.push_value();
+    }
 
-            if let Some(bar) = api_by_open.get(&expected_open) {
-                window.push(*bar);
-            }
+    fn push_value(&mut self) {
+        if let Some(prev) = self.previous_bar.as_ref() {
+            self.values.push(rsi(prev.avg_gain, prev.avg_loss));
+            self.closed += 1;
         }
-
-        self.window_1m_bars = Some(window);
     }
 }
 
-#[derive(Debug, Clone, Copy)]
-pub struct RsiInput {
-    pub bar_1m: Bar1m,
+fn rsi(avg_gain: f64, avg_loss: f64) -> f64 {
+    if avg_loss == 0.0 {
+        100.0
+    } else {
+        100.0 - (100.0 / (1.0 + avg_gain / avg_loss))
+    }
 }
 
 impl Indicator for Rsi {
-    type Input = RsiInput;
-    type Output = Option<f32>;
-
-    fn update_khist(&mut self, input: KlineHist) {
-        self.set_window_1m_bars_from_history(&input);
-        self.buffer.clear();
-        self.set_aggregate();
-        self.set_previous_bar_from_history(&input);
-        self.set_value();
-        self.stage = Stage::Ready;
+    fn update_khist(&mut self, input: KlineHist, bars: &BarAggregator, series: &mut SharedSeries) {
+        let rsi = series.rsi(self.period);
+        rsi.seed(closed_history(&input.hist_tf, bars));
+        self.publisher.warmed_up(Self::reading(bars, rsi));
     }
 
-    fn update(&mut self, input: Self::Input) -> Self::Output {
-        match self.stage {
-            Stage::New => {
-                self.stage = Stage::WarmUp;
-
-                self.update_buffer(input.bar_1m);
-            }
-            Stage::WarmUp => {
-                self.update_buffer(input.bar_1m);
-            }
-            Stage::Ready => {
-                self.update_window(input.bar_1m);
-                self.set_value();
-                if !self.sent_once {
-                    self.sent_once = true;
-                    return self.value;
-                }
-                if self.to_send {
-                    return self.value;
-                }
-            }
+    fn update(&mut self, bars: &BarAggregator, series: &mut SharedSeries) -> Option<Reading> {
+        if !self.publisher.is_ready() {
+            return None;
         }
-
-        None
+        let rsi = series.rsi(self.period);
+        rsi.sync(bars);
+        self.publisher.publish(Self::reading(bars, rsi))
     }
 }
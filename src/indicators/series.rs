@@ -0,0 +1,19 @@
+use std::collections::HashMap;
+
+use super::rsi::RsiSeries;
+
+/// Series several indicators of one (pair, timeframe) are built on, kept
+/// once so that e.g. RSI and Stoch RSI don't both compute the RSI.
+#[derive(Debug, Default)]
+pub struct SharedSeries {
+    rsi: HashMap<usize, RsiSeries>,
+}
+
+impl SharedSeries {
+    /// Wilder RSI of closes over `length` bars.
+    pub(super) fn rsi(&mut self, length: usize) -> &mut RsiSeries {
+        self.rsi
+            .entry(length)
+            .or_insert_with(|| RsiSeries::new(length))
+    }
+}
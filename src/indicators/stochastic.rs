@@ -0,0 +1,426 @@
+use crate::{
+    message_bus::KlineHist,
+    types::{Kline, RollingMax, RollingMin, RollingSum, Timeframe},
+};
+
+use super::{
+    aggregator::BarAggregator,
+    indicator::{
+        BarIndicator, Direction, Indicator, Lookback, MinuteSpan, Publisher, Reading, Signal,
+        Staged, closed_history, shown,
+    },
+    registry::{
+        BarFeed, Feed, IndicatorId, IndicatorSpec, ParamKind, ParamSpec, Params, ThresholdSpec,
+        TimeframeSet,
+    },
+    rsi::{self, Rsi, RsiSeries},
+    series::SharedSeries,
+};
+
+const RSI_LENGTH: &str = "rsi_length";
+const LENGTH: &str = "length";
+const K: &str = "k";
+const D: &str = "d";
+
+/// %K of the RSI's position in its recent range, reading the RSI series the
+/// RSI column keeps rather than a second copy.
+pub const STOCH_RSI: IndicatorSpec = IndicatorSpec {
+    id: IndicatorId::STOCH_RSI,
+    label: "STOCH RSI",
+    title: "Stoch RSI",
+    feed: Feed::Bars(BarFeed {
+        lookback: |params, tf| StochRsi::lookback(params, tf),
+        build: |params, _, _| Box::new(StochRsi::new(params)),
+    }),
+    timeframes: TimeframeSet::All,
+    default_enabled: false,
+    default_timeframes: &[Timeframe::M15, Timeframe::H1],
+    params: &[
+        count(RSI_LENGTH, "RSI length", 14.0, 250.0),
+        count(LENGTH, "Stoch length", 14.0, 250.0),
+        count(K, "%K smoothing", 3.0, 20.0),
+        count(D, "%D smoothing", 3.0, 20.0),
+    ],
+    threshold: THRESHOLD,
+    format: |value| format!("{value:.0}"),
+    width: 6,
+    event: Some(cross_event),
+};
+
+/// %K of the close's position in the recent high-low range.
+pub const STOCHASTIC: IndicatorSpec = IndicatorSpec {
+    id: IndicatorId::STOCHASTIC,
+    label: "STOCH",
+    title: "Stochastic",
+    feed: Feed::Bars(BarFeed {
+        lookback: |params, tf| Stochastic::lookback(params, tf),
+        build: |params, _, _| Box::new(Staged::new(Stochastic::new(params))),
+    }),
+    timeframes: TimeframeSet::All,
+    default_enabled: false,
+    default_timeframes: &[Timeframe::M15, Timeframe::H1],
+    params: &[
+        count(LENGTH, "Length", 14.0, 250.0),
+        count(K, "%K smoothing", 3.0, 20.0),
+        count(D, "%D smoothing", 3.0, 20.0),
+    ],
+    threshold: THRESHOLD,
+    format: |value| format!("{value:.0}"),
+    width: 6,
+    event: Some(cross_event),
+};
+
+/// Oversold below 20, overbought above 80.
+const THRESHOLD: ThresholdSpec = ThresholdSpec::Band {
+    lower: 20.0,
+    upper: 80.0,
+};
+
+const fn count(key: &'static str, label: &'static str, default: f64, max: f64) -> ParamSpec {
+    ParamSpec {
+        key,
+        label,
+        kind: ParamKind::Count,
+        default,
+        min: 1.0,
+        max,
+        step: 1.0,
+    }
+}
+
+fn cross_event(reading: Reading) -> Option<&'static str> {
+    match reading.signal? {
+        Signal::Cross(Direction::Up) => Some("bullish K/D cross"),
+        Signal::Cross(Direction::Down) => Some("bearish K/D cross"),
+        Signal::Squeeze => None,
+    }
+}
+
+/// Highest high and lowest low of the last `length` values; the closed ones
+/// are pushed, the newest is passed in per tick.
+#[derive(Debug, Clone)]
+struct Range {
+    length: usize,
+    highs: RollingMax<f64>,
+    lows: RollingMin<f64>,
+    pushed: i64,
+}
+
+impl Range {
+    fn new(length: usize) -> Self {
+        Self {
+            length: length.max(1),
+            highs: RollingMax::new(),
+            lows: RollingMin::new(),
+            pushed: 0,
+        }
+    }
+
+    /// Where `value` sits in the range with (`high`, `low`) as the newest
+    /// entry, 0 to 100; `None` until `length - 1` values were pushed.
+    fn percent(&self, value: f64, high: f64, low: f64) -> Option<f64> {
+        let closed = self.length as i64 - 1;
+        if self.pushed < closed {
+            return None;
+        }
+        let (high, low) = if closed == 0 {
+            (high, low)
+        } else {
+            let (_, max) = self.highs.get()?;
+            let (_, min) = self.lows.get()?;
+            (high.max(*max), low.min(*min))
+        };
+        // A flat range has no position in it; call it the middle.
+        Some(if high > low {
+            (value - low) / (high - low) * 100.0
+        } else {
+            50.0
+        })
+    }
+
+    fn push(&mut self, high: f64, low: f64) {
+        self.highs.push(self.pushed, high);
+        self.lows.push(self.pushed, low);
+        self.pushed += 1;
+        let first = self.pushed - (self.length as i64 - 1);
+        self.highs.expire(first);
+        self.lows.expire(first);
+    }
+
+    fn clear(&mut self) {
+        self.highs.clear();
+        self.lows.clear();
+        self.pushed = 0;
+    }
+}
+
+/// Simple average of the last `length` values, the newest passed in per tick.
+#[derive(Debug, Clone)]
+struct Smoothing {
+    length: usize,
+    closed: RollingSum,
+}
+
+impl Smoothing {
+    fn new(length: usize) -> Self {
+        let length = length.max(1);
+        Self {
+            length,
+            closed: RollingSum::new(length - 1),
+        }
+    }
+
+    fn with_next(&self, value: f64) -> Option<f64> {
+        if self.length == 1 {
+            return Some(value);
+        }
+        self.closed
+            .is_full()
+            .then(|| (self.closed.sum() + value) / self.length as f64)
+    }
+
+    fn push(&mut self, value: f64) {
+        if self.length > 1 {
+            self.closed.push(value);
+        }
+    }
+}
+
+/// %K and %D smoothed from the raw stochastic, with K/D crosses flagged
+/// against the last closed bar.
+#[derive(Debug, Clone)]
+struct KdLines {
+    k: Smoothing,
+    d: Smoothing,
+    closed_spread: Option<f64>, // %K minus %D at the last closed bar
+}
+
+impl KdLines {
+    fn new(params: &Params) -> Self {
+        Self {
+            k: Smoothing::new(params.count(K)),
+            d: Smoothing::new(params.count(D)),
+            closed_spread: None,
+        }
+    }
+
+    /// Bars the smoothing needs on top of the raw stochastic.
+    fn bars(params: &Params) -> usize {
+        params.count(K) + params.count(D)
+    }
+
+    fn lines(&self, raw: f64) -> Option<(f64, f64)> {
+        let k = self.k.with_next(raw)?;
+        Some((k, self.d.with_next(k)?))
+    }
+
+    fn close(&mut self, raw: f64) {
+        if let Some((k, d)) = self.lines(raw) {
+            self.closed_spread = Some(k - d);
+        }
+        if let Some(k) = self.k.with_next(raw) {
+            self.d.push(k);
+        }
+        self.k.push(raw);
+    }
+
+    /// %K with an arrow for its side of %D; crossing %D on the forming bar
+    /// highlights the cell.
+    fn reading(&self, raw: f64) -> Option<Reading> {
+        let (k, d) = self.lines(raw)?;
+        let cross = self
+            .closed_spread
+            .filter(|closed| (*closed > 0.0) != (k > d))
+            .map(|_| Signal::Cross(Direction::of(k, d)));
+        Some(
+            Reading::new(shown(k, 0))
+                .with_direction(Some(Direction::of(k, d)))
+                .with_signal(cross),
+        )
+    }
+
+    fn clear(&mut self) {
+        *self = Self {
+            k: Smoothing::new(self.k.length),
+            d: Smoothing::new(self.d.length),
+            closed_spread: None,
+        };
+    }
+}
+
+/// Stochastic of the shared RSI series.
+///
+/// Closed RSI values are folded in as the series grows, whichever indicator
+/// synced it; a reseed of the series starts the fold over.
+#[derive(Debug, Clone)]
+pub struct StochRsi {
+    rsi_length: usize,
+    range: Range,
+    kd: KdLines,
+    folded: u64, // closed RSI values folded in, as counted by the series
+    seeds: u64,
+    publisher: Publisher,
+}
+
+impl StochRsi {
+    fn new(params: &Params) -> Self {
+        Self {
+            rsi_length: params.count(RSI_LENGTH),
+            range: Range::new(params.count(LENGTH)),
+            kd: KdLines::new(params),
+            folded: 0,
+            seeds: 0,
+            publisher: Publisher::default(),
+        }
+    }
+
+    /// The RSI column's default seed, plus the stochastic window and smoothing.
+    fn lookback(params: &Params, tf: Timeframe) -> Lookback {
+        let rsi = Rsi::lookback(params.count(RSI_LENGTH), rsi::DEFAULT_SEED, tf);
+        Lookback {
+            bars: rsi.bars + params.count(LENGTH) + KdLines::bars(params),
+            ..rsi
+        }
+    }
+
+    fn close_bar(&mut self, rsi: f64) {
+        if let Some(raw) = self.range.percent(rsi, rsi, rsi) {
+            self.kd.close(raw);
+        }
+        self.range.push(rsi, rsi);
+    }
+
+    fn catch_up(&mut self, rsi: &RsiSeries) {
+        let values = rsi.closed_values();
+        if self.seeds != rsi.seeds() {
+            self.seeds = rsi.seeds();
+            self.range.clear();
+            self.kd.clear();
+            self.folded = rsi.closed_count() - values.len() as u64;
+        }
+
+        let new = (rsi.closed_count() - self.folded) as usize;
+        for value in values.iter().skip(values.len().saturating_sub(new)) {
+            self.close_bar(*value);
+        }
+        self.folded = rsi.closed_count();
+    }
+
+    fn reading(&self, bars: &BarAggregator, rsi: &RsiSeries) -> Option<Reading> {
+        let close = bars.latest()?.close;
+        let value = rsi.with_forming(close)?;
+        let raw = self.range.percent(value, value, value)?;
+        self.kd.reading(raw)
+    }
+}
+
+impl Indicator for StochRsi {
+    fn update_khist(&mut self, input: KlineHist, bars: &BarAggregator, series: &mut SharedSeries) {
+        let rsi = series.rsi(self.rsi_length);
+        rsi.seed(closed_history(&input.hist_tf, bars));
+        self.catch_up(rsi);
+        self.publisher.warmed_up(self.reading(bars, rsi));
+    }
+
+    fn update(&mut self, bars: &BarAggregator, series: &mut SharedSeries) -> Option<Reading> {
+        if !self.publisher.is_ready() {
+            return None;
+        }
+        let rsi = series.rsi(self.rsi_length);
+        rsi.sync(bars);
+        self.catch_up(rsi);
+        let reading = self.reading(bars, rsi);
+        self.publisher.publish(reading)
+    }
+}
+
+/// Classic (slow) stochastic over the high-low range of the last `length`
+/// bars, the forming bar included.
+#[derive(Debug, Clone)]
+pub struct Stochastic {
+    range: Range,
+    kd: KdLines,
+}
+
+impl Stochastic {
+    fn new(params: &Params) -> Self {
+        Self {
+            range: Range::new(params.count(LENGTH)),
+            kd: KdLines::new(params),
+        }
+    }
+
+    fn lookback(params: &Params, tf: Timeframe) -> Lookback {
+        Lookback {
+            minutes: BarAggregator::window_minutes(tf),
+            bars: params.count(LENGTH) + KdLines::bars(params),
+            span: MinuteSpan::Forming,
+        }
+    }
+
+    fn raw(&self, bar: &Kline) -> Option<f64> {
+        self.range
+            .percent(bar.close.to_f64(), bar.high.to_f64(), bar.low.to_f64())
+    }
+}
+
+impl BarIndicator for Stochastic {
+    fn clear(&mut self) {
+        self.range.clear();
+        self.kd.clear();
+    }
+
+    fn close_bar(&mut self, bar: &Kline, _bars: &BarAggregator) {
+        if let Some(raw) = self.raw(bar) {
+            self.kd.close(raw);
+        }
+        self.range.push(bar.high.to_f64(), bar.low.to_f64());
+    }
+
+    fn reading(&self, bars: &BarAggregator) -> Option<Reading> {
+        let raw = self.raw(&bars.forming()?)?;
+        self.kd.reading(raw)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::{Direction, KdLines, Range, Reading, STOCHASTIC, Signal};
+
+    #[test]
+    fn smooths_k_and_d_and_flags_crosses() {
+        let saved: BTreeMap<String, f64> = [("k", 2.0), ("d", 2.0)]
+            .into_iter()
+            .map(|(key, value)| (key.to_string(), value))
+            .collect();
+        let mut kd = KdLines::new(&STOCHASTIC.params(&saved));
+
+        // Closed %K: (20+40)/2 = 30, (40+60)/2 = 50; %D of those: 40.
+        for raw in [20.0, 40.0, 60.0] {
+            kd.close(raw);
+        }
+        assert_eq!(kd.closed_spread, Some(10.0));
+        // Forming %K (60+0)/2 = 30 drops under %D (50+30)/2 = 40.
+        assert_eq!(
+            kd.reading(0.0),
+            Some(
+                Reading::new(30.0)
+                    .with_direction(Some(Direction::Down))
+                    .with_signal(Some(Signal::Cross(Direction::Down)))
+            )
+        );
+        assert_eq!(
+            STOCHASTIC.event(kd.reading(0.0).unwrap()),
+            Some("bearish K/D cross")
+        );
+
+        let mut range = Range::new(3);
+        range.push(10.0, 8.0);
+        assert_eq!(range.percent(9.0, 9.5, 9.0), None);
+        range.push(12.0, 9.0);
+        // Range of 8..12 with the newest bar inside it.
+        assert_eq!(range.percent(11.0, 11.0, 10.0), Some(75.0));
+    }
+}
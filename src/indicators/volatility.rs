@@ -1,248 +1,156 @@
-use std::collections::HashMap;
+use rust_decimal::prelude::ToPrimitive;
 
-use log::info;
-
-use crate::time::now_millis;
 use crate::{
     message_bus::KlineHist,
-    types::{Bar1m, Pair, RingBuffer, Timeframe},
+    types::{Pair, Price, RollingMax, RollingMin, Timeframe},
+};
+
+use super::{
+    aggregator::BarAggregator,
+    indicator::{Indicator, Lookback, MinuteSpan, Publisher, Reading},
+    registry::{BarFeed, Feed, IndicatorId, IndicatorSpec, ThresholdSpec, TimeframeSet},
+    series::SharedSeries,
 };
 
-use super::indicator::Indicator;
+/// Signed high-to-low range of the rolling window, in percent. Timeframes
+/// longer than the aggregators' 1m window range over their forming bar.
+pub const SPEC: IndicatorSpec = IndicatorSpec {
+    id: IndicatorId::VOLATILITY,
+    label: "VOLATILITY",
+    title: "Volatility",
+    feed: Feed::Bars(BarFeed {
+        lookback: |_, tf| Volatility::lookback(tf),
+        build: |_, tf, pair| Box::new(Volatility::new(&tf, pair)),
+    }),
+    timeframes: TimeframeSet::All,
+    default_enabled: true,
+    default_timeframes: &[Timeframe::M15, Timeframe::H1, Timeframe::H4, Timeframe::D1],
+    params: &[],
+    threshold: ThresholdSpec::Magnitude {
+        default: 0.0,
+        step: 0.5,
+        coarse_step: 5.0,
+        decimals: 1,
+    },
+    format: |value| format!("{value:+.1}%"),
+    width: 6,
+    event: None,
+};
 
 #[derive(Debug, Clone)]
 pub struct Volatility {
-    stage: Stage,
     pair: Pair,
     tf: Timeframe,
-    buffer: RingBuffer<Bar1m>,
-    window_1m_bars: Option<RingBuffer<Bar1m>>,
-    aggr_closed_bars: Option<BarAggregation>,
-    value: Option<f32>,
-    sent_once: bool,
-    to_send: bool,
-}
-
-#[derive(Debug, Clone)]
-struct BarAggregation {
-    high: f64,
-    high_ts: i64,
-    open: f64,
-    low: f64,
-    low_ts: i64,
-}
-
-#[derive(Debug, Clone)]
-pub enum Stage {
-    New,
-    WarmUp,
-    Ready,
+    // Extremes of the closed 1m bars in the window, keyed by open time.
+    highs: RollingMax<Price>,
+    lows: RollingMin<Price>,
+    last_closed: Option<i64>,
+    seeds: u64, // aggregator seeds the extremes were built after
+    publisher: Publisher,
 }
 
 impl Volatility {
     pub fn new(tf: &Timeframe, pair: &Pair) -> Self {
         Self {
-            stage: Stage::New,
             pair: pair.clone(),
             tf: *tf,
-            buffer: RingBuffer::new(10),
-            window_1m_bars: None,
-            aggr_closed_bars: None,
-            value: None,
-            to_send: false,
-            sent_once: false,
+            highs: RollingMax::new(),
+            lows: RollingMin::new(),
+            last_closed: None,
+            seeds: 0,
+            publisher: Publisher::default(),
         }
     }
-    fn update_window(&mut self, bar: Bar1m) -> Option<f32> {
-        let Some(window) = self.window_1m_bars.as_mut() else {
-            return None;
-        };
 
-        match window.back() {
-            None => {
-                window.push(bar);
-            }
-            Some(last) if last.open_time < bar.open_time => {
-                window.push(bar);
-                self.set_aggregate();
-            }
-            Some(last) if !last.closed && last.open_time == bar.open_time => {
-                window.replace_last(bar);
-            }
-            _ => {}
+    /// Volatility only looks at the rolling `tf` window of 1m bars.
+    pub fn lookback(tf: Timeframe) -> Lookback {
+        Lookback {
+            minutes: BarAggregator::window_minutes(tf),
+            bars: 0,
+            span: MinuteSpan::Rolling,
         }
+    }
 
-        None
+    /// Whether the aggregator keeps the whole `tf` window in 1m bars.
+    fn rolling(&self) -> bool {
+        BarAggregator::window_minutes(self.tf) == self.tf.window_minutes()
     }
-    fn set_value(&mut self) {
-        let Some(window) = self.window_1m_bars.as_ref() else {
-            return;
-        };
-        let Some(last) = window.back() else {
-            return;
-        };
 
-        let new_value = if let Some(aggr) = self.aggr_closed_bars.as_ref() {
-            let high = aggr.high.max(last.high);
-            let low = aggr.low.min(last.low);
-            let sign = if aggr.high_ts > aggr.low_ts {
-                1.0
-            } else {
-                -1.0
-            };
-            let extent = ((high - low) / low) * 100.0 * sign; // +/- fraction
-            let value = (extent * 10.0).trunc() / 10.0; // 4-decimals
-            Some(value as f32)
+    fn reading(&self, bars: &BarAggregator) -> Option<Reading> {
+        let last = if self.rolling() {
+            *bars.latest()?
         } else {
-            let sign = if last.open < last.close { 1.0 } else { -1.0 };
-            let extent = ((last.high - last.low) / last.low) * 100.0 * sign; // +/- fraction
-            let value = (extent * 10.0).trunc() / 10.0; // 4-decimals
-            Some(value as f32)
+            bars.forming()?
         };
 
-        let changed = new_value != self.value;
-
-        if changed {
-            self.value = new_value;
-        }
-        self.to_send = changed;
-    }
-    fn set_aggregate(&mut self) {
-        self.aggr_closed_bars = if self.tf == Timeframe::M1 {
-            None
+        let (high, low, rising) = if let (Some((high_ts, high)), Some((low_ts, low))) =
+            (self.highs.get(), self.lows.get())
+        {
+            (
+                (*high).max(last.high),
+                (*low).min(last.low),
+                high_ts > low_ts,
+            )
         } else {
-            let now_ms = now_millis();
-            let current_1m_start = Timeframe::M1.nearest_ms(now_ms);
-            let Some(window) = self.window_1m_bars.as_ref() else {
-                return;
-            };
-
-            let Some(first_bar) = window.front() else {
-                return;
-            };
-
-            let open = first_bar.open;
-            let mut high: Option<(f64, i64)> = None;
-            let mut low: Option<(f64, i64)> = None;
-
-            for bar in window.iter() {
-                if bar.open_time == current_1m_start || !bar.closed {
-                    continue;
-                }
-
-                match high {
-                    Some((h, _)) if bar.high > h => high = Some((bar.high, bar.open_time)),
-                    None => high = Some((bar.high, bar.open_time)),
-                    _ => {}
-                }
-
-                match low {
-                    Some((l, _)) if bar.low < l => low = Some((bar.low, bar.open_time)),
-                    None => low = Some((bar.low, bar.open_time)),
-                    _ => {}
-                }
-            }
-
-            match (high, low) {
-                (Some((high, high_ts)), Some((low, low_ts))) => Some(BarAggregation {
-                    high,
-                    high_ts,
-                    low,
-                    low_ts,
-                    open,
-                }),
-                _ => None,
-            }
+            (last.high, last.low, last.open < last.close)
         };
+        // Truncated on the exact prices, so the shown tenth is never a tick off.
+        high.percent_change(low, 1)
+            .and_then(|extent| if rising { extent } else { -extent }.to_f32())
+            .map(Reading::from)
     }
-    fn update_buffer(&mut self, bar: Bar1m) {
-        match self.buffer.back() {
-            None => {
-                self.buffer.push(bar);
-            }
-            Some(last) if last.open_time < bar.open_time => {
-                self.buffer.push(bar);
-            }
-            Some(last) if !last.closed && last.open_time == bar.open_time => {
-                self.buffer.replace_last(bar);
-            }
-            _ => {}
+    /// Fold the 1m bars closed since the last call into the rolling extremes
+    /// and drop the ones that left the window; rebuilt after a (re)seed.
+    fn set_aggregate(&mut self, bars: &BarAggregator) {
+        if self.tf == Timeframe::M1 || !self.rolling() {
+            return;
+        }
+        if self.seeds != bars.seeds() {
+            self.seeds = bars.seeds();
+            self.highs.clear();
+            self.lows.clear();
+            self.last_closed = None;
         }
-    }
-}
-
-#[derive(Debug, Clone, Copy)]
-pub struct VolatilityInput {
-    pub bar_1m: Bar1m,
-}
-
-impl Indicator for Volatility {
-    type Input = VolatilityInput;
-    type Output = Option<f32>;
-
-    fn update_khist(&mut self, input: KlineHist) {
-        let now_ms = now_millis();
-        let tf = input.indicator_tf;
-        let window_size = tf.window_minutes();
-        let window_1m_size = Timeframe::M1.window_millis();
-        let current_1m_start = Timeframe::M1.nearest_ms(now_ms);
-        let window_start_ts = current_1m_start - (window_size as i64 - 1) * window_1m_size;
-
-        let ws_by_open: HashMap<i64, Bar1m> = self
-            .buffer
-            .iter()
-            .map(|bar| (bar.open_time, *bar))
-            .collect();
-        let api_by_open: HashMap<i64, Bar1m> = input
-            .hist_1m
-            .iter()
-            .map(|bar| (bar.open_time, *bar))
-            .collect();
-
-        let mut window = RingBuffer::new(window_size);
-        for idx in 0..window_size {
-            let expected_open = window_start_ts + (idx as i64) * window_1m_size;
-
-            if let Some(bar) = ws_by_open.get(&expected_open) {
-                window.push(*bar);
-                continue;
-            }
 
-            if let Some(bar) = api_by_open.get(&expected_open) {
-                window.push(*bar);
-            }
+        // The latest bar is still forming; set_value folds it in on every tick.
+        let window = bars.window();
+        let closed = window.len().saturating_sub(1);
+        let unseen = (0..closed)
+            .rev()
+            .filter_map(|index| window.get(index))
+            .take_while(|bar| self.last_closed.is_none_or(|ts| bar.open_time > ts))
+            .count();
+        for bar in (closed - unseen..closed).filter_map(|index| window.get(index)) {
+            self.highs.push(bar.open_time, bar.high);
+            self.lows.push(bar.open_time, bar.low);
+            self.last_closed = Some(bar.open_time);
         }
 
-        self.window_1m_bars = Some(window);
-        self.set_aggregate();
-        self.buffer.clear();
-        self.set_value();
-        self.stage = Stage::Ready;
+        if let Some(first) = window.front() {
+            self.highs.expire(first.open_time);
+            self.lows.expire(first.open_time);
+        }
     }
+}
 
-    fn update(&mut self, input: Self::Input) -> Self::Output {
-        match self.stage {
-            Stage::New => {
-                self.stage = Stage::WarmUp;
+impl Indicator for Volatility {
+    fn update_khist(
+        &mut self,
+        _input: KlineHist,
+        bars: &BarAggregator,
+        _series: &mut SharedSeries,
+    ) {
+        self.set_aggregate(bars);
+        self.publisher.warmed_up(self.reading(bars));
+    }
 
-                self.update_buffer(input.bar_1m);
-            }
-            Stage::WarmUp => {
-                self.update_buffer(input.bar_1m);
-            }
-            Stage::Ready => {
-                self.update_window(input.bar_1m);
-                self.set_value();
-                if !self.sent_once {
-                    self.sent_once = true;
-                    return self.value;
-                }
-                if self.to_send {
-                    return self.value;
-                }
-            }
+    fn update(&mut self, bars: &BarAggregator, _series: &mut SharedSeries) -> Option<Reading> {
+        if !self.publisher.is_ready() {
+            return None;
         }
-
-        None
+        if bars.new_minute() {
+            self.set_aggregate(bars);
+        }
+        self.publisher.publish(self.reading(bars))
     }
 }
@@ -0,0 +1,446 @@
+use crate::{
+    time::now_millis,
+    types::{Kline, Price, RollingSum, RollingVariance, RollingWeightedSum, Timeframe},
+};
+
+use super::{
+    aggregator::BarAggregator,
+    indicator::{BarIndicator, Lookback, MinuteSpan, Reading, Staged, shown},
+    registry::{
+        BarFeed, Feed, IndicatorId, IndicatorSpec, ParamKind, ParamSpec, Params, ThresholdSpec,
+        TimeframeSet,
+    },
+};
+
+const LENGTH: &str = "length";
+const OUTPUT: &str = "output";
+
+/// Elapsed time the forming bar's volume is scaled from at least, so the
+/// first seconds of a bar don't project a spike out of a single trade.
+const MIN_ELAPSED_MS: i64 = 60_000;
+
+/// Volume of the forming bar, projected over the whole bar, as a multiple of
+/// the average volume of the last `length` closed bars.
+pub const RELATIVE_VOLUME: IndicatorSpec = IndicatorSpec {
+    id: IndicatorId::RELATIVE_VOLUME,
+    label: "RVOL",
+    title: "Relative volume",
+    feed: Feed::Bars(BarFeed {
+        lookback: |params, tf| RelativeVolume::lookback(params, tf),
+        build: |params, _, _| Box::new(Staged::new(RelativeVolume::new(params))),
+    }),
+    timeframes: TimeframeSet::All,
+    default_enabled: false,
+    default_timeframes: &[Timeframe::M15, Timeframe::H1],
+    params: &[ParamSpec {
+        key: LENGTH,
+        label: "Average bars",
+        kind: ParamKind::Count,
+        default: 20.0,
+        min: 2.0,
+        max: 500.0,
+        step: 1.0,
+    }],
+    threshold: ThresholdSpec::Magnitude {
+        default: 2.0,
+        step: 0.5,
+        coarse_step: 5.0,
+        decimals: 1,
+    },
+    format: |value| format!("{value:.1}x"),
+    width: 6,
+    event: None,
+};
+
+/// On-balance volume: each bar adds its volume when it closes up and takes it
+/// away when it closes down.
+pub const OBV: IndicatorSpec = trend_spec(
+    IndicatorId::OBV,
+    "OBV",
+    "On-balance volume",
+    Feed::Bars(BarFeed {
+        lookback: |params, tf| VolumeTrend::lookback(params, tf),
+        build: |params, _, _| Box::new(Staged::new(VolumeTrend::new(Line::OnBalance, params))),
+    }),
+);
+
+/// Chaikin's accumulation/distribution line: each bar adds its volume
+/// weighted by where it closed in its range, from -1 at the low to 1 at the high.
+pub const AD_LINE: IndicatorSpec = trend_spec(
+    IndicatorId::AD_LINE,
+    "A/D",
+    "Accumulation/distribution",
+    Feed::Bars(BarFeed {
+        lookback: |params, tf| VolumeTrend::lookback(params, tf),
+        build: |params, _, _| {
+            Box::new(Staged::new(VolumeTrend::new(
+                Line::AccumulationDistribution,
+                params,
+            )))
+        },
+    }),
+);
+
+/// Cumulative lines start wherever the history does, so cells show how they
+/// move instead: the slope in average bar volumes per bar (-1 to 1, every
+/// bar adding its whole volume), or the z-score of the latest value.
+const fn trend_spec(
+    id: IndicatorId,
+    label: &'static str,
+    title: &'static str,
+    feed: Feed,
+) -> IndicatorSpec {
+    IndicatorSpec {
+        id,
+        label,
+        title,
+        feed,
+        timeframes: TimeframeSet::All,
+        default_enabled: false,
+        default_timeframes: &[Timeframe::H1, Timeframe::H4],
+        params: &[
+            ParamSpec {
+                key: LENGTH,
+                label: "Bars",
+                kind: ParamKind::Count,
+                default: 20.0,
+                min: 3.0,
+                max: 500.0,
+                step: 1.0,
+            },
+            ParamSpec {
+                key: OUTPUT,
+                label: "Output",
+                kind: ParamKind::Choice(&["Slope", "Z-score"]),
+                default: 0.0,
+                min: 0.0,
+                max: 1.0,
+                step: 1.0,
+            },
+        ],
+        threshold: ThresholdSpec::Magnitude {
+            default: 0.0,
+            step: 0.1,
+            coarse_step: 1.0,
+            decimals: 1,
+        },
+        format: |value| format!("{value:+.2}"),
+        width: 6,
+        event: None,
+    }
+}
+
+/// Volume scaled from `elapsed_ms` of a bar to its full `span_ms`.
+fn projected(volume: f64, elapsed_ms: i64, span_ms: i64) -> f64 {
+    let elapsed_ms = elapsed_ms.clamp(MIN_ELAPSED_MS.min(span_ms), span_ms);
+    volume * span_ms as f64 / elapsed_ms as f64
+}
+
+/// Least-squares slope of `len` values at x = 1..len, the closed ones given
+/// by their sum and `RollingWeightedSum` over `len - 1`, plus `next` at `len`.
+fn slope(weighted: f64, sum: f64, next: f64, len: usize) -> f64 {
+    let n = len as f64;
+    let xy = weighted + n * next;
+    let y = sum + next;
+    (xy - (n + 1.0) / 2.0 * y) / (n * (n * n - 1.0) / 12.0)
+}
+
+#[derive(Debug, Clone)]
+pub struct RelativeVolume {
+    volumes: RollingSum, // base volume of the last closed bars
+}
+
+impl RelativeVolume {
+    fn new(params: &Params) -> Self {
+        Self {
+            volumes: RollingSum::new(params.count(LENGTH)),
+        }
+    }
+
+    fn lookback(params: &Params, tf: Timeframe) -> Lookback {
+        Lookback {
+            minutes: BarAggregator::window_minutes(tf),
+            bars: params.count(LENGTH),
+            span: MinuteSpan::Forming,
+        }
+    }
+}
+
+impl BarIndicator for RelativeVolume {
+    fn clear(&mut self) {
+        self.volumes.clear();
+    }
+
+    fn close_bar(&mut self, bar: &Kline, _bars: &BarAggregator) {
+        self.volumes.push(bar.volume.to_f64());
+    }
+
+    fn reading(&self, bars: &BarAggregator) -> Option<Reading> {
+        let forming = bars.forming()?;
+        let open = bars.bucket_open(forming.open_time);
+        let span = bars.bucket_end(open) - open;
+        let volume = projected(forming.volume.to_f64(), now_millis() - open, span);
+        self.volumes
+            .mean()
+            .filter(|average| *average > 0.0)
+            .map(|average| Reading::new(shown(volume / average, 1)))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Line {
+    OnBalance,
+    AccumulationDistribution,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Output {
+    Slope,
+    ZScore,
+}
+
+/// Slope or z-score of a cumulative volume line over the last `length` bars,
+/// the forming bar's step folded in per tick.
+#[derive(Debug, Clone)]
+pub struct VolumeTrend {
+    line: Line,
+    output: Output,
+    length: usize,
+    level: f64,                 // line value at the last closed bar
+    prev_close: Option<Price>,  // close of the last closed bar
+    levels: RollingWeightedSum, // closed line values, for the slope
+    spread: RollingVariance,    // closed line values, for the z-score
+    volumes: RollingSum,        // closed bar volumes the slope is scaled by
+}
+
+impl VolumeTrend {
+    fn new(line: Line, params: &Params) -> Self {
+        let length = params.count(LENGTH).max(3);
+        let output = match params.get(OUTPUT) as usize {
+            1 => Output::ZScore,
+            _ => Output::Slope,
+        };
+        // The forming bar takes the last of the `length` places.
+        let closed = length - 1;
+        Self {
+            line,
+            output,
+            length,
+            level: 0.0,
+            prev_close: None,
+            levels: RollingWeightedSum::new(closed),
+            spread: RollingVariance::new(closed),
+            volumes: RollingSum::new(closed),
+        }
+    }
+
+    /// One more bar than the window, for the close the first step compares to.
+    fn lookback(params: &Params, tf: Timeframe) -> Lookback {
+        Lookback {
+            minutes: BarAggregator::window_minutes(tf),
+            bars: params.count(LENGTH).max(3) + 1,
+            span: MinuteSpan::Forming,
+        }
+    }
+
+    /// What `bar` adds to the line; `None` for the first bar of the history.
+    fn step(&self, bar: &Kline) -> Option<f64> {
+        let volume = bar.volume.to_f64();
+        match self.line {
+            Line::OnBalance => {
+                let prev_close = self.prev_close?;
+                Some(if bar.close > prev_close {
+                    volume
+                } else if bar.close < prev_close {
+                    -volume
+                } else {
+                    0.0
+                })
+            }
+            Line::AccumulationDistribution => {
+                let range = (bar.high - bar.low).to_f64();
+                let position = ((bar.close - bar.low) - (bar.high - bar.close)).to_f64();
+                Some(if range > 0.0 {
+                    position / range * volume
+                } else {
+                    0.0
+                })
+            }
+        }
+    }
+
+    fn push(&mut self, bar: &Kline) {
+        if let Some(step) = self.step(bar) {
+            self.level += step;
+            self.levels.push(self.level);
+            self.spread.push(self.level);
+            self.volumes.push(bar.volume.to_f64());
+        }
+        self.prev_close = Some(bar.close);
+    }
+
+    /// Slope or z-score with `forming`'s step folded in.
+    fn trend(&self, forming: &Kline) -> Option<f32> {
+        let level = self.step(forming).map(|step| self.level + step);
+        let value = level.and_then(|level| match self.output {
+            Output::Slope => {
+                let volume = self.volumes.mean().filter(|volume| *volume > 0.0)?;
+                self.levels.is_full().then(|| {
+                    slope(
+                        self.levels.weighted_sum(),
+                        self.levels.sum(),
+                        level,
+                        self.length,
+                    ) / volume
+                })
+            }
+            Output::ZScore => {
+                let (mean, variance) = self.spread.with_next(level)?;
+                Some(if variance > 0.0 {
+                    (level - mean) / variance.sqrt()
+                } else {
+                    0.0
+                })
+            }
+        });
+        value.map(|value| shown(value, 2))
+    }
+}
+
+impl BarIndicator for VolumeTrend {
+    fn clear(&mut self) {
+        self.level = 0.0;
+        self.prev_close = None;
+        self.levels.clear();
+        self.spread.clear();
+        self.volumes.clear();
+    }
+
+    fn close_bar(&mut self, bar: &Kline, _bars: &BarAggregator) {
+        self.push(bar);
+    }
+
+    fn reading(&self, bars: &BarAggregator) -> Option<Reading> {
+        self.trend(&bars.forming()?).map(Reading::from)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use crate::types::{Kline, Price, RollingWeightedSum, Volume};
+
+    use super::{AD_LINE, Line, OBV, VolumeTrend, projected, slope};
+
+    fn bar(low: f64, high: f64, close: f64, volume: f64) -> Kline {
+        Kline {
+            open: Price::from_f64(close),
+            high: Price::from_f64(high),
+            low: Price::from_f64(low),
+            close: Price::from_f64(close),
+            volume: Volume::from_f64(volume),
+            quote_volume: Volume::from_f64(close * volume),
+            trades: 1,
+            taker_buy_volume: Volume::from_f64(volume / 2.0),
+            taker_buy_quote_volume: Volume::from_f64(close * volume / 2.0),
+            open_time: 0,
+            closed: true,
+        }
+    }
+
+    /// A line over three bars, the forming one included; output 0 is the
+    /// slope and 1 the z-score.
+    fn trend(line: Line, output: f64) -> VolumeTrend {
+        let saved: BTreeMap<String, f64> = [("length", 3.0), ("output", output)]
+            .into_iter()
+            .map(|(key, value)| (key.to_string(), value))
+            .collect();
+        let spec = match line {
+            Line::OnBalance => OBV,
+            Line::AccumulationDistribution => AD_LINE,
+        };
+        VolumeTrend::new(line, &spec.params(&saved))
+    }
+
+    #[test]
+    fn on_balance_steps_follow_the_close() {
+        let mut obv = trend(Line::OnBalance, 0.0);
+
+        // The first bar has no close to compare to and adds nothing.
+        assert_eq!(obv.step(&bar(9.0, 11.0, 10.0, 5.0)), None);
+        obv.push(&bar(9.0, 11.0, 10.0, 5.0));
+        assert_eq!(obv.level, 0.0);
+
+        assert_eq!(obv.step(&bar(9.0, 12.0, 11.0, 3.0)), Some(3.0));
+        assert_eq!(obv.step(&bar(8.0, 11.0, 9.0, 3.0)), Some(-3.0));
+        assert_eq!(obv.step(&bar(9.0, 11.0, 10.0, 3.0)), Some(0.0));
+    }
+
+    #[test]
+    fn accumulation_steps_weigh_the_close_in_its_range() {
+        let ad = trend(Line::AccumulationDistribution, 0.0);
+
+        assert_eq!(ad.step(&bar(9.0, 11.0, 11.0, 4.0)), Some(4.0));
+        assert_eq!(ad.step(&bar(9.0, 11.0, 9.0, 4.0)), Some(-4.0));
+        assert_eq!(ad.step(&bar(9.0, 13.0, 12.0, 4.0)), Some(2.0));
+        // A bar without a range adds nothing.
+        assert_eq!(ad.step(&bar(10.0, 10.0, 10.0, 4.0)), Some(0.0));
+    }
+
+    #[test]
+    fn slopes_are_in_average_bar_volumes() {
+        let mut obv = trend(Line::OnBalance, 0.0);
+        obv.push(&bar(9.0, 11.0, 10.0, 1.0));
+        obv.push(&bar(10.0, 12.0, 11.0, 1.0));
+        assert_eq!(obv.trend(&bar(11.0, 13.0, 12.0, 3.0)), None);
+
+        // Levels 1, 4 and 9 rise by 4 per bar, over an average volume of 2.
+        obv.push(&bar(11.0, 13.0, 12.0, 3.0));
+        assert_eq!(obv.trend(&bar(12.0, 14.0, 13.0, 5.0)), Some(2.0));
+        // Every bar adding or taking its whole volume: 1 and -1.
+        obv.push(&bar(12.0, 14.0, 13.0, 3.0));
+        assert_eq!(obv.trend(&bar(13.0, 15.0, 14.0, 3.0)), Some(1.0));
+    }
+
+    #[test]
+    fn scores_the_forming_level_against_the_window() {
+        let mut obv = trend(Line::OnBalance, 1.0);
+        for close in [10.0, 11.0, 12.0] {
+            obv.push(&bar(close - 1.0, close + 1.0, close, 1.0));
+        }
+
+        // Levels 1, 2 and 3: one population deviation of sqrt(2/3) above 2.
+        assert_eq!(obv.trend(&bar(12.0, 14.0, 13.0, 1.0)), Some(1.22));
+        // A flat forming bar repeats 2: (2 - 5/3) / sqrt(2/9).
+        assert_eq!(obv.trend(&bar(11.0, 13.0, 12.0, 1.0)), Some(0.71));
+
+        // A flat line has no spread to score against.
+        let mut ad = trend(Line::AccumulationDistribution, 1.0);
+        for _ in 0..3 {
+            ad.push(&bar(10.0, 10.0, 10.0, 1.0));
+        }
+        assert_eq!(ad.trend(&bar(10.0, 10.0, 10.0, 1.0)), Some(0.0));
+    }
+
+    #[test]
+    fn scales_the_forming_volume_by_elapsed_time() {
+        let hour = 3_600_000;
+        // 15 minutes into an hour bar: four times the volume so far.
+        assert_eq!(projected(50.0, hour / 4, hour), 200.0);
+        // Scaled from one minute at least, and never down past the bar's end.
+        assert_eq!(projected(2.0, 1_000, hour), 120.0);
+        assert_eq!(projected(80.0, 2 * hour, hour), 80.0);
+    }
+
+    #[test]
+    fn fits_the_slope_through_the_forming_value() {
+        let mut closed = RollingWeightedSum::new(3);
+        for value in [10.0, 12.0, 14.0] {
+            closed.push(value);
+        }
+        // 10, 12, 14, 16 rises by 2 per bar.
+        let fit = slope(closed.weighted_sum(), closed.sum(), 16.0, 4);
+        assert!((fit - 2.0).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,295 @@
+use crate::{
+    message_bus::KlineHist,
+    time::now_millis,
+    types::{Kline, Timeframe, UtcOffset},
+};
+
+use super::{
+    aggregator::BarAggregator,
+    indicator::{Anchor, BarIndicator, Lookback, MinuteSpan, Reading, Staged, shown},
+    registry::{
+        BarFeed, Feed, IndicatorId, IndicatorSpec, ParamKind, ParamSpec, Params, ThresholdSpec,
+        TimeframeSet,
+    },
+};
+
+const ANCHOR: &str = "anchor";
+const ANCHOR_AT: &str = "anchor_at";
+
+/// Anchored VWAP is the same on every timeframe, so it is one column fed
+/// from 1m bars.
+const VWAP_TIMEFRAME: Timeframe = Timeframe::M1;
+
+const PARAMS: &[ParamSpec] = &[
+    ParamSpec {
+        key: ANCHOR,
+        label: "Anchor",
+        kind: ParamKind::Choice(&["Day", "Week", "Custom"]),
+        default: 0.0,
+        min: 0.0,
+        max: 2.0,
+        step: 1.0,
+    },
+    // Used by the custom anchor, which counts from the day start while unset.
+    ParamSpec {
+        key: ANCHOR_AT,
+        label: "Custom (UTC)",
+        kind: ParamKind::Time,
+        default: 0.0,
+        min: 0.0,
+        max: 68_374_080.0, // 2100-01-01
+        step: 60.0,
+    },
+];
+
+/// Distance of the price from the VWAP since the anchor, in percent.
+pub const VWAP: IndicatorSpec = IndicatorSpec {
+    id: IndicatorId::VWAP,
+    label: "VWAP",
+    title: "Anchored VWAP",
+    feed: Feed::Bars(BarFeed {
+        lookback: |params, _| Vwap::lookback(params),
+        build: |params, _, _| Box::new(Staged::new(Vwap::new(Output::Percent, params))),
+    }),
+    timeframes: TimeframeSet::Single(VWAP_TIMEFRAME),
+    default_enabled: false,
+    default_timeframes: &[VWAP_TIMEFRAME],
+    params: PARAMS,
+    threshold: ThresholdSpec::Magnitude {
+        default: 0.0,
+        step: 0.1,
+        coarse_step: 1.0,
+        decimals: 1,
+    },
+    format: |value| format!("{value:+.2}%"),
+    width: 7,
+    event: None,
+};
+
+/// Where the price sits in the volume-weighted standard deviation bands
+/// around the anchored VWAP, e.g. `+2.0σ` on the upper second band.
+pub const VWAP_BANDS: IndicatorSpec = IndicatorSpec {
+    id: IndicatorId::VWAP_BANDS,
+    label: "VWAP σ",
+    title: "Anchored VWAP bands",
+    feed: Feed::Bars(BarFeed {
+        lookback: |params, _| Vwap::lookback(params),
+        build: |params, _, _| Box::new(Staged::new(Vwap::new(Output::Deviations, params))),
+    }),
+    timeframes: TimeframeSet::Single(VWAP_TIMEFRAME),
+    default_enabled: false,
+    default_timeframes: &[VWAP_TIMEFRAME],
+    params: PARAMS,
+    threshold: ThresholdSpec::Magnitude {
+        default: 2.0,
+        step: 0.5,
+        coarse_step: 1.0,
+        decimals: 1,
+    },
+    format: |value| format!("{value:+.1}σ"),
+    width: 6,
+    event: None,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Output {
+    Percent,
+    Deviations,
+}
+
+/// Volume-weighted sums of the typical price since the anchor.
+#[derive(Debug, Clone, Copy, Default)]
+struct Sums {
+    volume: f64,
+    price: f64,  // price times volume
+    square: f64, // squared price times volume
+}
+
+impl Sums {
+    fn with(mut self, bar: &Kline) -> Self {
+        let volume = bar.volume.to_f64();
+        let typical = (bar.high.to_f64() + bar.low.to_f64() + bar.close.to_f64()) / 3.0;
+        self.volume += volume;
+        self.price += typical * volume;
+        self.square += typical * typical * volume;
+        self
+    }
+
+    /// VWAP and the volume-weighted standard deviation around it.
+    fn vwap(&self) -> Option<(f64, f64)> {
+        if self.volume <= 0.0 {
+            return None;
+        }
+        let vwap = self.price / self.volume;
+        let variance = (self.square / self.volume - vwap * vwap).max(0.0);
+        Some((vwap, variance.sqrt()))
+    }
+}
+
+/// VWAP of 1m bars since the latest anchor; sums restart when a new day or
+/// week begins. Closed 1m bars are summed and the forming one is folded in
+/// per tick.
+#[derive(Debug, Clone)]
+pub struct Vwap {
+    output: Output,
+    anchor: Anchor,
+    anchor_open: Option<i64>, // anchor the closed sums count from
+    closed: Sums,
+    last_closed: i64,     // open time of the last 1m bar in `closed`
+    reaches_anchor: bool, // history covers the anchor, or it was still ahead
+}
+
+impl Vwap {
+    fn new(output: Output, params: &Params) -> Self {
+        Self {
+            output,
+            anchor: Self::anchor(params),
+            anchor_open: None,
+            closed: Sums::default(),
+            last_closed: i64::MIN,
+            reaches_anchor: true,
+        }
+    }
+
+    fn anchor(params: &Params) -> Anchor {
+        let at = params.get(ANCHOR_AT);
+        match params.get(ANCHOR) as usize {
+            1 => Anchor::Week,
+            2 if at > 0.0 => Anchor::At(at as i64 * 60_000),
+            _ => Anchor::Day,
+        }
+    }
+
+    /// 1m bars back to the anchor; no closed bars of a timeframe.
+    fn lookback(params: &Params) -> Lookback {
+        Lookback {
+            minutes: 1,
+            bars: 0,
+            span: MinuteSpan::Since(Self::anchor(params)),
+        }
+    }
+
+    /// Sums up to `bar` when it opens on or after the anchor; a new anchor
+    /// drops the closed sums.
+    fn sums_through(&self, bar: &Kline, offset: UtcOffset) -> Option<Sums> {
+        let open = self.anchor.open_at(bar.open_time, offset)?;
+        let closed = if self.anchor_open == Some(open) {
+            self.closed
+        } else {
+            Sums::default()
+        };
+        Some(closed.with(bar))
+    }
+}
+
+/// Whether 1m `bars` fetched at `now_ms` start at or before the anchor, so
+/// sums from them cover it. Fixed anchors further back than the fetched
+/// history would only sum its tail, so the cell stays empty instead.
+fn reaches_anchor(anchor: Anchor, bars: &[Kline], now_ms: i64) -> bool {
+    match anchor {
+        Anchor::At(anchor) => bars
+            .first()
+            .map_or(anchor > now_ms, |bar| bar.open_time <= anchor),
+        Anchor::Day | Anchor::Week => true,
+    }
+}
+
+impl BarIndicator for Vwap {
+    fn history(input: &KlineHist) -> &[Kline] {
+        &input.hist_1m
+    }
+
+    fn seed(&mut self, closed: &[Kline], bars: &BarAggregator) {
+        self.clear();
+        self.reaches_anchor = reaches_anchor(self.anchor, closed, now_millis());
+        for bar in closed {
+            self.close_bar(bar, bars);
+        }
+    }
+
+    fn clear(&mut self) {
+        self.anchor_open = None;
+        self.closed = Sums::default();
+        self.last_closed = i64::MIN;
+    }
+
+    fn close_bar(&mut self, bar: &Kline, bars: &BarAggregator) {
+        if bar.open_time <= self.last_closed {
+            return;
+        }
+        if let Some(sums) = self.sums_through(bar, bars.offset()) {
+            self.anchor_open = self.anchor.open_at(bar.open_time, bars.offset());
+            self.closed = sums;
+        }
+        self.last_closed = bar.open_time;
+    }
+
+    fn reading(&self, bars: &BarAggregator) -> Option<Reading> {
+        if !self.reaches_anchor {
+            return None;
+        }
+        let last = bars.latest()?;
+        let price = last.close.to_f64();
+        // Right after warmup the latest 1m bar can be one already summed.
+        let sums = if last.open_time <= self.last_closed {
+            self.anchor_open.map(|_| self.closed)
+        } else {
+            self.sums_through(last, bars.offset())
+        };
+        let (vwap, deviation) = sums?.vwap()?;
+        let value = match self.output {
+            Output::Percent if vwap > 0.0 => (price / vwap - 1.0) * 100.0,
+            Output::Deviations if deviation > 0.0 => (price - vwap) / deviation,
+            _ => return None,
+        };
+        Some(Reading::new(shown(value, 2)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Anchor, Sums, reaches_anchor};
+    use crate::types::{Kline, Price, Volume};
+
+    fn bar(price: f64, volume: f64) -> Kline {
+        Kline {
+            open: Price::from_f64(price),
+            high: Price::from_f64(price),
+            low: Price::from_f64(price),
+            close: Price::from_f64(price),
+            volume: Volume::from_f64(volume),
+            quote_volume: Volume::from_f64(price * volume),
+            trades: 1,
+            taker_buy_volume: Volume::from_f64(0.0),
+            taker_buy_quote_volume: Volume::from_f64(0.0),
+            open_time: 0,
+            closed: true,
+        }
+    }
+
+    #[test]
+    fn weighs_typical_prices_by_volume() {
+        let sums = Sums::default().with(&bar(10.0, 1.0)).with(&bar(20.0, 3.0));
+
+        // (10 + 60) / 4, deviating 7.5 and 2.5 with weights 1 and 3.
+        let (vwap, deviation) = sums.vwap().unwrap();
+        assert!((vwap - 17.5).abs() < 1e-9);
+        assert!((deviation - (75.0_f64 / 4.0).sqrt()).abs() < 1e-9);
+        assert!(Sums::default().vwap().is_none());
+    }
+
+    #[test]
+    fn custom_anchors_need_history_back_to_them() {
+        let mut first = bar(10.0, 1.0);
+        first.open_time = 60_000;
+        let bars = [first];
+
+        assert!(reaches_anchor(Anchor::At(60_000), &bars, 120_000));
+        assert!(!reaches_anchor(Anchor::At(0), &bars, 120_000));
+        // Still ahead at warmup: live bars sum it from the start.
+        assert!(reaches_anchor(Anchor::At(180_000), &bars, 120_000));
+        assert!(reaches_anchor(Anchor::At(180_000), &[], 120_000));
+        assert!(!reaches_anchor(Anchor::At(0), &[], 120_000));
+        assert!(reaches_anchor(Anchor::Day, &[], 120_000));
+    }
+}
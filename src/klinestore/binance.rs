@@ -0,0 +1,36 @@
+use futures_util::future::BoxFuture;
+
+use crate::{
+    adapters::binance::BinanceRest,
+    error::Result,
+    types::{FundingRate, Kline, Market, OpenInterest, Pair, Timeframe, Timestamp, UtcOffset},
+};
+
+use super::KlineStore;
+
+impl KlineStore for BinanceRest {
+    fn history<'a>(
+        &'a self,
+        market: Market,
+        pair: &'a Pair,
+        tf: Timeframe,
+        offset: UtcOffset,
+        start: Timestamp,
+        limit: u16,
+    ) -> BoxFuture<'a, Result<Vec<Kline>>> {
+        Box::pin(self.kline_history(market, pair, tf, offset, start, limit))
+    }
+
+    fn funding_rates(&self) -> BoxFuture<'_, Result<Vec<(Pair, FundingRate)>>> {
+        Box::pin(self.premium_index())
+    }
+
+    fn open_interest<'a>(
+        &'a self,
+        pair: &'a Pair,
+        period: Timeframe,
+        limit: u16,
+    ) -> BoxFuture<'a, Result<Vec<OpenInterest>>> {
+        Box::pin(self.open_interest_history(pair, period, limit))
+    }
+}
@@ -0,0 +1,234 @@
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+use futures_util::future::BoxFuture;
+use tokio::fs;
+
+use crate::{
+    error::{GlobalError, Result},
+    types::{Kline, Market, Pair, Timeframe, Timestamp, UtcOffset},
+};
+
+use super::{KlineStore, page, series_offset};
+
+/// Bars per file, so a page of history reads and rewrites at most two files.
+const CHUNK_BARS: i64 = 1_000;
+/// Files kept per series (50,000 bars); the oldest are dropped past this.
+const MAX_CHUNKS: i64 = 50;
+
+/// Closed bars cached on disk, one directory per market, pair and timeframe,
+/// and per day-start offset for the timeframes it moves. Each JSON file holds
+/// the bars of one chunk of `CHUNK_BARS` windows.
+#[derive(Debug, Clone)]
+pub struct DiskCache {
+    dir: PathBuf,
+}
+
+impl DiskCache {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    fn series_dir(&self, market: Market, pair: &Pair, tf: Timeframe, offset: UtcOffset) -> PathBuf {
+        let market = match market {
+            Market::Spot => "spot",
+            Market::UsdmFutures => "usdm",
+        };
+        let mut name = format!("{}_{}", pair.0, file_label(tf));
+        let offset = series_offset(tf, offset);
+        if offset != UtcOffset::UTC {
+            // No colon; Windows doesn't allow it in file names.
+            name.push_str(&format!("_utc{}", offset.to_string().replace(':', "")));
+        }
+        self.dir.join(market).join(name)
+    }
+
+    fn chunk_path(series_dir: &Path, chunk: i64) -> PathBuf {
+        series_dir.join(format!("{chunk}.json"))
+    }
+
+    async fn load(path: &Path) -> Result<BTreeMap<i64, Kline>> {
+        let body = match fs::read(path).await {
+            Ok(body) => body,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(BTreeMap::new()),
+            Err(err) => {
+                return Err(GlobalError::Other(format!(
+                    "kline cache read {} failed: {err}",
+                    path.display()
+                )));
+            }
+        };
+        let bars: Vec<Kline> = serde_json::from_slice(&body).map_err(|e| {
+            GlobalError::Other(format!("kline cache parse {} failed: {e}", path.display()))
+        })?;
+        Ok(bars.into_iter().map(|bar| (bar.open_time, bar)).collect())
+    }
+
+    async fn save(path: &Path, chunk: &BTreeMap<i64, Kline>) -> Result<()> {
+        let io_err =
+            |e: std::io::Error| GlobalError::Other(format!("kline cache write failed: {e}"));
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).await.map_err(io_err)?;
+        }
+        let bars: Vec<&Kline> = chunk.values().collect();
+        let body = serde_json::to_vec(&bars)
+            .map_err(|e| GlobalError::Other(format!("kline cache encode failed: {e}")))?;
+
+        // Write then rename, so readers never see a partial file.
+        let tmp = path.with_extension("json.tmp");
+        fs::write(&tmp, body).await.map_err(io_err)?;
+        fs::rename(&tmp, path).await.map_err(io_err)
+    }
+
+    /// Remove the chunks more than `MAX_CHUNKS` before `newest`.
+    async fn prune(series_dir: &Path, newest: i64) -> Result<()> {
+        let io_err =
+            |e: std::io::Error| GlobalError::Other(format!("kline cache prune failed: {e}"));
+        let mut entries = fs::read_dir(series_dir).await.map_err(io_err)?;
+        while let Some(entry) = entries.next_entry().await.map_err(io_err)? {
+            let path = entry.path();
+            let chunk = path
+                .file_stem()
+                .and_then(|stem| stem.to_str())
+                .and_then(|stem| stem.parse::<i64>().ok());
+            if chunk.is_some_and(|chunk| chunk <= newest - MAX_CHUNKS) {
+                fs::remove_file(&path).await.map_err(io_err)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Chunk holding the bar opening at `open_ms`. Months are counted as 31 days,
+/// which only makes their chunks a little shorter.
+fn chunk_of(tf: Timeframe, open_ms: i64) -> i64 {
+    open_ms.div_euclid(tf.window_millis() * CHUNK_BARS)
+}
+
+/// `1m` and `1M` differ only by case, which some file systems ignore.
+fn file_label(tf: Timeframe) -> String {
+    match tf {
+        Timeframe::Mo1 => "1mo".to_string(),
+        _ => tf.to_string(),
+    }
+}
+
+impl KlineStore for DiskCache {
+    fn history<'a>(
+        &'a self,
+        market: Market,
+        pair: &'a Pair,
+        tf: Timeframe,
+        offset: UtcOffset,
+        start: Timestamp,
+        limit: u16,
+    ) -> BoxFuture<'a, Result<Vec<Kline>>> {
+        Box::pin(async move {
+            let series_dir = self.series_dir(market, pair, tf, offset);
+            let mut chunk = chunk_of(tf, start.0);
+            let mut bars = Vec::new();
+            // Stop at the first missing chunk; the caller fetches the gap.
+            while bars.len() < limit as usize {
+                let series = Self::load(&Self::chunk_path(&series_dir, chunk)).await?;
+                if series.is_empty() {
+                    break;
+                }
+                bars.extend(page(&series, start, limit - bars.len() as u16));
+                chunk += 1;
+            }
+            Ok(bars)
+        })
+    }
+
+    fn store<'a>(
+        &'a self,
+        market: Market,
+        pair: &'a Pair,
+        tf: Timeframe,
+        offset: UtcOffset,
+        bars: &'a [Kline],
+    ) -> BoxFuture<'a, Result<()>> {
+        Box::pin(async move {
+            if bars.is_empty() {
+                return Ok(());
+            }
+            let series_dir = self.series_dir(market, pair, tf, offset);
+            let mut chunks: BTreeMap<i64, Vec<Kline>> = BTreeMap::new();
+            for bar in bars {
+                chunks
+                    .entry(chunk_of(tf, bar.open_time))
+                    .or_default()
+                    .push(*bar);
+            }
+            for (&chunk, bars) in &chunks {
+                let path = Self::chunk_path(&series_dir, chunk);
+                let mut series = Self::load(&path).await?;
+                series.extend(bars.iter().map(|bar| (bar.open_time, *bar)));
+                Self::save(&path, &series).await?;
+            }
+            match chunks.last_key_value() {
+                Some((&newest, _)) => Self::prune(&series_dir, newest).await,
+                None => Ok(()),
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CHUNK_BARS, DiskCache};
+    use crate::klinestore::KlineStore;
+    use crate::types::{Kline, Market, Pair, Price, Timeframe, Timestamp, UtcOffset, Volume};
+
+    const MINUTE: i64 = 60_000;
+
+    fn bar(minute: i64) -> Kline {
+        Kline {
+            open: Price::from_f64(1.0),
+            high: Price::from_f64(1.0),
+            low: Price::from_f64(1.0),
+            close: Price::from_f64(minute as f64),
+            volume: Volume::from_f64(1.0),
+            quote_volume: Volume::from_f64(1.0),
+            trades: 1,
+            taker_buy_volume: Volume::from_f64(0.5),
+            taker_buy_quote_volume: Volume::from_f64(0.5),
+            open_time: minute * MINUTE,
+            closed: true,
+        }
+    }
+
+    #[tokio::test]
+    async fn pages_across_chunk_files() {
+        let dir = std::env::temp_dir().join(format!("the_grid_cache_{}", std::process::id()));
+        let cache = DiskCache::new(&dir);
+        let pair = Pair("BTCUSDT".into());
+        let (m1, utc) = (Timeframe::M1, UtcOffset::UTC);
+
+        // Two pages written separately, the second spilling into the next chunk.
+        let first: Vec<Kline> = (0..CHUNK_BARS - 10).map(bar).collect();
+        let second: Vec<Kline> = (CHUNK_BARS - 10..CHUNK_BARS + 10).map(bar).collect();
+        cache
+            .store(Market::Spot, &pair, m1, utc, &first)
+            .await
+            .unwrap();
+        cache
+            .store(Market::Spot, &pair, m1, utc, &second)
+            .await
+            .unwrap();
+
+        let start = Timestamp((CHUNK_BARS - 5) * MINUTE);
+        let bars = cache
+            .history(Market::Spot, &pair, m1, utc, start, 100)
+            .await;
+        let closes: Vec<f64> = bars.unwrap().iter().map(|bar| bar.close.to_f64()).collect();
+        let expected: Vec<f64> = (CHUNK_BARS - 5..CHUNK_BARS + 10)
+            .map(|m| m as f64)
+            .collect();
+        assert_eq!(closes, expected);
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
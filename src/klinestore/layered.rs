@@ -0,0 +1,188 @@
+use futures_util::future::BoxFuture;
+use log::warn;
+
+use crate::{
+    error::Result,
+    time::now_millis,
+    types::{FundingRate, Kline, Market, OpenInterest, Pair, Timeframe, Timestamp, UtcOffset},
+};
+
+use super::{KlineStore, SharedKlineStore};
+
+/// Serves history from `cache` and fetches only what it is missing from `upstream`.
+///
+/// Closed bars fetched upstream are written back to the cache. Derivatives are
+/// always live, so they go straight upstream.
+pub struct Layered {
+    cache: SharedKlineStore,
+    upstream: SharedKlineStore,
+}
+
+impl Layered {
+    pub fn new(cache: SharedKlineStore, upstream: SharedKlineStore) -> Self {
+        Self { cache, upstream }
+    }
+}
+
+impl KlineStore for Layered {
+    fn history<'a>(
+        &'a self,
+        market: Market,
+        pair: &'a Pair,
+        tf: Timeframe,
+        offset: UtcOffset,
+        start: Timestamp,
+        limit: u16,
+    ) -> BoxFuture<'a, Result<Vec<Kline>>> {
+        Box::pin(async move {
+            let cached = match self
+                .cache
+                .history(market, pair, tf, offset, start, limit)
+                .await
+            {
+                Ok(bars) => bars,
+                Err(err) => {
+                    warn!("kline cache read failed for {}: {err}", pair.0);
+                    Vec::new()
+                }
+            };
+
+            // Only the gap-free run from the first bar at `start` is usable.
+            let bucket = tf.nearest_ms_in(start.0, offset);
+            let mut next_open = if bucket == start.0 {
+                bucket
+            } else {
+                tf.next_open_in(bucket, offset)
+            };
+            let mut bars: Vec<Kline> = Vec::with_capacity(limit as usize);
+            for bar in cached {
+                if bar.open_time != next_open {
+                    break;
+                }
+                next_open = tf.next_open_in(bar.open_time, offset);
+                bars.push(bar);
+            }
+            if bars.len() >= limit as usize {
+                return Ok(bars);
+            }
+
+            let missing = limit - bars.len() as u16;
+            let fetched = self
+                .upstream
+                .history(market, pair, tf, offset, Timestamp(next_open), missing)
+                .await?;
+
+            // The latest bar may still be forming; it must not be cached.
+            let now = now_millis();
+            let closed = fetched
+                .iter()
+                .take_while(|bar| tf.next_open_in(bar.open_time, offset) <= now)
+                .count();
+            let closed_bars = &fetched[..closed];
+            if let Err(err) = self
+                .cache
+                .store(market, pair, tf, offset, closed_bars)
+                .await
+            {
+                warn!("kline cache write failed for {}: {err}", pair.0);
+            }
+
+            bars.extend(fetched);
+            Ok(bars)
+        })
+    }
+
+    fn store<'a>(
+        &'a self,
+        market: Market,
+        pair: &'a Pair,
+        tf: Timeframe,
+        offset: UtcOffset,
+        bars: &'a [Kline],
+    ) -> BoxFuture<'a, Result<()>> {
+        self.cache.store(market, pair, tf, offset, bars)
+    }
+
+    fn funding_rates(&self) -> BoxFuture<'_, Result<Vec<(Pair, FundingRate)>>> {
+        self.upstream.funding_rates()
+    }
+
+    fn open_interest<'a>(
+        &'a self,
+        pair: &'a Pair,
+        period: Timeframe,
+        limit: u16,
+    ) -> BoxFuture<'a, Result<Vec<OpenInterest>>> {
+        self.upstream.open_interest(pair, period, limit)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::Layered;
+    use crate::klinestore::{KlineStore, MemoryStore};
+    use crate::types::{Kline, Market, Pair, Price, Timeframe, Timestamp, UtcOffset, Volume};
+
+    const MINUTE: i64 = 60_000;
+
+    fn bar(minute: i64) -> Kline {
+        Kline {
+            open: Price::from_f64(1.0),
+            high: Price::from_f64(1.0),
+            low: Price::from_f64(1.0),
+            close: Price::from_f64(minute as f64),
+            volume: Volume::from_f64(1.0),
+            quote_volume: Volume::from_f64(1.0),
+            trades: 1,
+            taker_buy_volume: Volume::from_f64(0.5),
+            taker_buy_quote_volume: Volume::from_f64(0.5),
+            open_time: minute * MINUTE,
+            closed: true,
+        }
+    }
+
+    #[tokio::test]
+    async fn fills_cache_gaps_from_upstream() {
+        let pair = Pair("BTCUSDT".into());
+        let all: Vec<Kline> = (0..10).map(bar).collect();
+        let cache = Arc::new(MemoryStore::new().with_klines(
+            Market::Spot,
+            &pair,
+            Timeframe::M1,
+            &[all[0], all[1], all[2], all[5]],
+        ));
+        let upstream =
+            Arc::new(MemoryStore::new().with_klines(Market::Spot, &pair, Timeframe::M1, &all));
+        let store = Layered::new(cache.clone(), upstream);
+
+        let bars = store
+            .history(
+                Market::Spot,
+                &pair,
+                Timeframe::M1,
+                UtcOffset::UTC,
+                Timestamp(0),
+                8,
+            )
+            .await
+            .unwrap();
+        let closes: Vec<f64> = bars.iter().map(|bar| bar.close.to_f64()).collect();
+        assert_eq!(closes, [0.0, 1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0]);
+
+        // Upstream bars were written back, so the cache now covers the range.
+        let cached = cache
+            .history(
+                Market::Spot,
+                &pair,
+                Timeframe::M1,
+                UtcOffset::UTC,
+                Timestamp(0),
+                8,
+            )
+            .await
+            .unwrap();
+        assert_eq!(cached.len(), 8);
+    }
+}
@@ -0,0 +1,105 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::Mutex,
+};
+
+use futures_util::future::BoxFuture;
+
+use crate::{
+    error::Result,
+    types::{FundingRate, Kline, Market, OpenInterest, Pair, Timeframe, Timestamp, UtcOffset},
+};
+
+use super::{KlineStore, page, series_offset};
+
+type SeriesKey = (Market, Pair, Timeframe, UtcOffset);
+
+/// In-memory store, for fixtures in tests and as a process-local cache layer.
+#[derive(Debug, Default)]
+pub struct MemoryStore {
+    klines: Mutex<HashMap<SeriesKey, BTreeMap<i64, Kline>>>,
+    funding: Vec<(Pair, FundingRate)>,
+    open_interest: HashMap<Pair, Vec<OpenInterest>>,
+}
+
+impl MemoryStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_klines(self, market: Market, pair: &Pair, tf: Timeframe, bars: &[Kline]) -> Self {
+        self.insert(market, pair, tf, UtcOffset::UTC, bars);
+        self
+    }
+
+    pub fn with_funding(mut self, funding: Vec<(Pair, FundingRate)>) -> Self {
+        self.funding = funding;
+        self
+    }
+
+    pub fn with_open_interest(mut self, pair: &Pair, samples: Vec<OpenInterest>) -> Self {
+        self.open_interest.insert(pair.clone(), samples);
+        self
+    }
+
+    fn insert(
+        &self,
+        market: Market,
+        pair: &Pair,
+        tf: Timeframe,
+        offset: UtcOffset,
+        bars: &[Kline],
+    ) {
+        let mut klines = self.klines.lock().unwrap_or_else(|e| e.into_inner());
+        let key = (market, pair.clone(), tf, series_offset(tf, offset));
+        let series = klines.entry(key).or_default();
+        series.extend(bars.iter().map(|bar| (bar.open_time, *bar)));
+    }
+}
+
+impl KlineStore for MemoryStore {
+    fn history<'a>(
+        &'a self,
+        market: Market,
+        pair: &'a Pair,
+        tf: Timeframe,
+        offset: UtcOffset,
+        start: Timestamp,
+        limit: u16,
+    ) -> BoxFuture<'a, Result<Vec<Kline>>> {
+        let klines = self.klines.lock().unwrap_or_else(|e| e.into_inner());
+        let bars = klines
+            .get(&(market, pair.clone(), tf, series_offset(tf, offset)))
+            .map(|series| page(series, start, limit))
+            .unwrap_or_default();
+        Box::pin(async move { Ok(bars) })
+    }
+
+    fn store<'a>(
+        &'a self,
+        market: Market,
+        pair: &'a Pair,
+        tf: Timeframe,
+        offset: UtcOffset,
+        bars: &'a [Kline],
+    ) -> BoxFuture<'a, Result<()>> {
+        self.insert(market, pair, tf, offset, bars);
+        Box::pin(async { Ok(()) })
+    }
+
+    fn funding_rates(&self) -> BoxFuture<'_, Result<Vec<(Pair, FundingRate)>>> {
+        let funding = self.funding.clone();
+        Box::pin(async move { Ok(funding) })
+    }
+
+    fn open_interest<'a>(
+        &'a self,
+        pair: &'a Pair,
+        _period: Timeframe,
+        limit: u16,
+    ) -> BoxFuture<'a, Result<Vec<OpenInterest>>> {
+        let samples = self.open_interest.get(pair).cloned().unwrap_or_default();
+        let skip = samples.len().saturating_sub(limit as usize);
+        Box::pin(async move { Ok(samples[skip..].to_vec()) })
+    }
+}
@@ -1,51 +1,87 @@
-use std::sync::{Arc, OnceLock};
+mod binance;
+mod disk;
+mod layered;
+mod memory;
+
+use std::{collections::BTreeMap, sync::Arc};
+
+use futures_util::future::BoxFuture;
 
 use crate::{
-    adapters::binance::BinanceRest,
     error::{GlobalError, Result},
-    types::{Kline, Pair, Timeframe, Timestamp},
+    types::{FundingRate, Kline, Market, OpenInterest, Pair, Timeframe, Timestamp, UtcOffset},
 };
 
-static STORE: OnceLock<Arc<KlineStore>> = OnceLock::new();
+pub use disk::DiskCache;
+pub use layered::Layered;
+pub use memory::MemoryStore;
 
-/// Simple façade over the Binance REST adapter to fetch klines.
-/// Initialized once at startup and accessed via free functions.
-#[derive(Clone)]
-pub struct KlineStore {
-    binance: BinanceRest,
-}
+/// Shared handle to the history source the services fetch from.
+pub type SharedKlineStore = Arc<dyn KlineStore>;
+
+/// Source of kline history and derivatives data.
+///
+/// Implemented by the Binance REST adapter, an on-disk cache and an in-memory
+/// fixture; [`Layered`] stacks a cache over another store.
+pub trait KlineStore: Send + Sync {
+    /// Up to `limit` ascending bars opening at or after `start`, with days
+    /// starting at midnight in the `offset` timezone.
+    fn history<'a>(
+        &'a self,
+        market: Market,
+        pair: &'a Pair,
+        tf: Timeframe,
+        offset: UtcOffset,
+        start: Timestamp,
+        limit: u16,
+    ) -> BoxFuture<'a, Result<Vec<Kline>>>;
 
-impl KlineStore {
-    /// Initialize the store once (idempotent). Calling again returns the first instance.
-    pub fn init(binance: BinanceRest) -> Arc<Self> {
-        STORE.get_or_init(|| Arc::new(Self { binance })).clone()
+    /// Keep closed bars fetched elsewhere. Stores that only read ignore them.
+    fn store<'a>(
+        &'a self,
+        _market: Market,
+        _pair: &'a Pair,
+        _tf: Timeframe,
+        _offset: UtcOffset,
+        _bars: &'a [Kline],
+    ) -> BoxFuture<'a, Result<()>> {
+        Box::pin(async { Ok(()) })
     }
 
-    fn global() -> Result<Arc<Self>> {
-        STORE
-            .get()
-            .cloned()
-            .ok_or_else(|| GlobalError::Other("KlineStore not initialized".into()))
+    /// Current funding of every USD-M perpetual.
+    fn funding_rates(&self) -> BoxFuture<'_, Result<Vec<(Pair, FundingRate)>>> {
+        Box::pin(async { Err(unsupported("funding rates")) })
     }
 
-    async fn history_inner(
-        &self,
-        pair: &Pair,
-        tf: Timeframe,
-        start: Timestamp,
-        limit: u16,
-    ) -> Result<Vec<Kline>> {
-        self.binance.kline_history(pair, tf, start, limit).await
+    /// Open interest history of a USD-M perpetual.
+    fn open_interest<'a>(
+        &'a self,
+        _pair: &'a Pair,
+        _period: Timeframe,
+        _limit: u16,
+    ) -> BoxFuture<'a, Result<Vec<OpenInterest>>> {
+        Box::pin(async { Err(unsupported("open interest")) })
+    }
+}
+
+fn unsupported(what: &str) -> GlobalError {
+    GlobalError::Other(format!("{what} not available from this kline store"))
+}
+
+/// Offset a series is kept under: windows it doesn't move share the UTC series.
+fn series_offset(tf: Timeframe, offset: UtcOffset) -> UtcOffset {
+    if offset.shifts(tf) {
+        offset
+    } else {
+        UtcOffset::UTC
     }
 }
 
-/// Fetch klines using the globally initialized store.
-pub async fn history(
-    pair: &Pair,
-    tf: Timeframe,
-    start: Timestamp,
-    limit: u16,
-) -> Result<Vec<Kline>> {
-    let store = KlineStore::global()?;
-    store.history_inner(pair, tf, start, limit).await
+/// Up to `limit` bars of an ordered series opening at or after `start`.
+fn page(series: &BTreeMap<i64, Kline>, start: Timestamp, limit: u16) -> Vec<Kline> {
+    series
+        .range(start.0..)
+        .take(limit as usize)
+        .map(|(_, bar)| *bar)
+        .collect()
 }
@@ -4,6 +4,7 @@ mod adapters;
 mod engine;
 mod env;
 mod error;
+mod export;
 mod history;
 mod indicators;
 mod klinestore;
@@ -20,11 +21,13 @@ use crate::env as app_env;
 use adapters::binance::BinanceRest;
 use engine::Engine;
 use error::GlobalError;
+use export::{EXPORT_KLINES, ExportArgs, export_klines};
 use history::HistoryService;
-use klinestore::KlineStore;
+use klinestore::{DiskCache, Layered, SharedKlineStore};
 use log::info;
 use logger::initialize_logger;
 use message_bus::{EngineBus, HistoryBus, UiBus, WsBus};
+use std::{path::PathBuf, sync::Arc};
 use tokio::runtime::Builder;
 use tui::run_tui;
 use ws::WsClient;
@@ -43,8 +46,23 @@ fn main() -> Result<()> {
         .build()
         .map_err(|e| GlobalError::Other(format!("runtime build error: {e}")))?;
 
-    let binance = BinanceRest::builder().build();
-    KlineStore::init(binance);
+    let binance: SharedKlineStore = Arc::new(BinanceRest::builder().build());
+    let kline_store: SharedKlineStore = match kline_cache_dir() {
+        Some(dir) => Arc::new(Layered::new(Arc::new(DiskCache::new(dir)), binance)),
+        None => binance,
+    };
+
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.first().map(String::as_str) == Some(EXPORT_KLINES) {
+        let result = ExportArgs::parse(&args[1..]).and_then(|export_args| {
+            runtime.block_on(export_klines(&export_args, kline_store.as_ref()))
+        });
+        if let Err(err) = result {
+            eprintln!("{err}");
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
 
     // let ws_config = config.clone();
 
@@ -56,6 +74,7 @@ fn main() -> Result<()> {
     let engine_tx_ui = engine_bus.engine_sender();
     let engine_tx_history = engine_bus.engine_sender();
     let history_tx_ui = history_bus.history_sender();
+    let ui_tx_history = ui_bus.ui_sender();
     let (engine_tx_ws, engine_rx) = engine_bus.into_engine();
     let (history_tx_engine, history_rx) = history_bus.into_engine();
     let (ws_tx_ui, ws_rx) = ws_bus.into_engine();
@@ -70,7 +89,7 @@ fn main() -> Result<()> {
             .await
     });
     let hist_handle = handle.spawn(async move {
-        HistoryService::new(history_rx, engine_tx_history)
+        HistoryService::new(history_rx, engine_tx_history, ui_tx_history, kline_store)
             .run()
             .await
     });
@@ -99,3 +118,11 @@ fn main() -> Result<()> {
     let _ = watcher.join();
     Ok(())
 }
+
+/// Closed klines are cached next to the presets file.
+fn kline_cache_dir() -> Option<PathBuf> {
+    confy::get_configuration_file_path("the_grid", tui::settings::PRESET_CONFIG_NAME)
+        .ok()?
+        .parent()
+        .map(|dir| dir.join("klines"))
+}
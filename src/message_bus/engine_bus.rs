@@ -1,7 +1,7 @@
 use tokio::sync::mpsc;
 
-use crate::indicators::IndicatorName;
-use crate::types::{AppConfig, Bar1m, Kline, Pair, Timeframe};
+use crate::indicators::IndicatorId;
+use crate::types::{AppConfig, Bar1m, FundingRate, Kline, OpenInterest, Pair, Timeframe};
 
 /// Primary facade for cross-module communication.
 /// Starts with a single ws -> engine channel and can grow with more channels later.
@@ -90,16 +90,27 @@ pub enum EngineMessage {
     Kline(KlineEvent),
     Reboot(RebootEvent),
     KHistBundle(Vec<KlineHist>),
+    Derivatives(Vec<DerivativesEvent>),
     Config(AppConfig),
 }
 
 #[derive(Clone, Debug)]
 pub struct KlineHist {
+    pub generation: u64, // config the history was fetched for
     pub pair: Pair,
-    pub indicator: IndicatorName,
+    pub indicator: IndicatorId,
     pub indicator_tf: Timeframe, // e.g., 5m/1h for the indicator
     pub hist_1m: Vec<Bar1m>,     // 1m history
     pub hist_tf: Vec<Kline>,     // history at indicator TF
+    pub head: Option<Kline>,     // forming TF bar before hist_1m, on TFs longer than a day
+}
+
+/// Perpetual metrics for one pair, polled by the history service.
+#[derive(Clone, Debug)]
+pub struct DerivativesEvent {
+    pub pair: Pair,
+    pub funding: Option<FundingRate>,
+    pub open_interest: Vec<OpenInterest>, // oldest first
 }
 
 #[derive(Clone, Debug)]
@@ -90,6 +90,7 @@ impl HistoryRx {
 #[derive(Clone, Debug)]
 pub enum HistoryMessage {
     WarmUp(WarmUpEvent),
+    Priority(PriorityEvent),
     Config(AppConfig),
 }
 
@@ -107,3 +108,10 @@ impl WarmUpEvent {
         }
     }
 }
+
+/// Pairs the dashboard wants warmed up first.
+#[derive(Clone, Debug, Default)]
+pub struct PriorityEvent {
+    pub visible: Vec<Pair>,
+    pub pinned: Vec<Pair>,
+}
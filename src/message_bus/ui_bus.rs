@@ -1,6 +1,10 @@
-use ratatui::style::Color;
 use tokio::sync::mpsc;
 
+use crate::{
+    indicators::{IndicatorId, Reading},
+    types::Pair,
+};
+
 /// Primary facade for cross-module communication.
 /// Starts with a single ws -> engine channel and can grow with more channels later.
 #[derive(Debug)]
@@ -86,87 +90,42 @@ impl UiRx {
 #[derive(Clone, Debug)]
 pub enum UiMessage {
     IndicatorResults(Vec<(usize, IndicatorValue)>), // index,value
+    WarmupProgress(WarmupProgress),
 }
 
+/// Warmup lifecycle of a single pair, in the order it normally happens.
 #[derive(Clone, Debug)]
-pub enum IndicatorValue {
-    Volatility(f32),
-    Rsi(f32),
-}
-
-#[derive(Clone, Debug)]
-pub enum IndicatorThresholds {
-    Volatility { threshold: f32 },
-    Rsi { oversold: f32, overbought: f32 },
+pub enum WarmupProgress {
+    Requested(Pair), // engine asked the history service
+    Fetched(Pair),   // history downloaded, bundle on its way to the engine
+    Ready(Pair),     // engine seeded the indicators
+    Failed { pair: Pair, reason: String },
 }
 
+/// Latest reading of one dashboard cell; formatted by its indicator's spec.
 #[derive(Clone, Copy, Debug)]
-pub struct IndicatorColors {
-    pub text: Color,
-    pub background: Option<Color>,
+pub struct IndicatorValue {
+    pub indicator: IndicatorId,
+    pub reading: Reading,
 }
 
 impl IndicatorValue {
-    const POSITIVE_TEXT: Color = Color::Rgb(64, 199, 122);
-    const NEGATIVE_TEXT: Color = Color::Rgb(230, 82, 82);
-    const POSITIVE_BG: Color = Color::Rgb(33, 178, 125);
-    const NEGATIVE_BG: Color = Color::Rgb(186, 64, 117);
+    pub fn new(indicator: IndicatorId, reading: impl Into<Reading>) -> Self {
+        Self {
+            indicator,
+            reading: reading.into(),
+        }
+    }
 
     pub fn display(&self) -> String {
-        match self {
-            IndicatorValue::Volatility(v) => format!("{:+.1}%", v),
-            IndicatorValue::Rsi(v) => format!("{:.1}", v),
+        let value = self.indicator.spec().format(self.reading.value);
+        match self.reading.direction {
+            Some(direction) => format!("{value} {}", direction.arrow()),
+            None => value,
         }
     }
 
-    pub fn colors(&self, thresholds: Option<&IndicatorThresholds>) -> IndicatorColors {
-        match self {
-            IndicatorValue::Volatility(value) => {
-                let mut background = None;
-                if let Some(IndicatorThresholds::Volatility { threshold }) = thresholds {
-                    if *threshold > 0.0 && value.abs() >= *threshold {
-                        background = Some(if *value >= 0.0 {
-                            Self::POSITIVE_BG
-                        } else {
-                            Self::NEGATIVE_BG
-                        });
-                    }
-                }
-
-                let text = if background.is_some() {
-                    Color::White
-                } else if *value >= 0.0 {
-                    Self::POSITIVE_TEXT
-                } else {
-                    Self::NEGATIVE_TEXT
-                };
-
-                IndicatorColors { text, background }
-            }
-            IndicatorValue::Rsi(value) => {
-                let mut background = None;
-                if let Some(IndicatorThresholds::Rsi {
-                    oversold,
-                    overbought,
-                }) = thresholds
-                {
-                    if value <= oversold {
-                        background = Some(Self::NEGATIVE_BG);
-                    } else if value >= overbought {
-                        background = Some(Self::POSITIVE_BG);
-                    }
-                }
-
-                let text = if background.is_some() {
-                    Color::White
-                } else if *value >= 50.0 {
-                    Self::POSITIVE_TEXT
-                } else {
-                    Self::NEGATIVE_TEXT
-                };
-
-                IndicatorColors { text, background }
-            }
-        }
+    pub fn reading(&self) -> Reading {
+        self.reading
     }
 }
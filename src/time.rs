@@ -7,3 +7,90 @@ pub fn now_millis() -> i64 {
         .expect("time went backwards")
         .as_millis() as i64
 }
+
+/// Parse a UTC time as Unix milliseconds.
+///
+/// Accepts raw milliseconds, `YYYY-MM-DD`, or `YYYY-MM-DDTHH:MM[:SS]` with an
+/// optional trailing `Z` (a space works in place of the `T`).
+pub fn parse_utc_millis(value: &str) -> Option<i64> {
+    let value = value.trim();
+    if !value.is_empty() && value.bytes().all(|b| b.is_ascii_digit()) {
+        return value.parse().ok();
+    }
+
+    let value = value.strip_suffix('Z').unwrap_or(value);
+    let (date, time) = match value.split_once(['T', ' ']) {
+        Some((date, time)) => (date, Some(time)),
+        None => (value, None),
+    };
+
+    let mut date_parts = date.splitn(3, '-');
+    let year: i64 = date_parts.next()?.parse().ok()?;
+    let month: u32 = date_parts.next()?.parse().ok()?;
+    let day: u32 = date_parts.next()?.parse().ok()?;
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return None;
+    }
+
+    let (mut hour, mut minute, mut second) = (0i64, 0i64, 0i64);
+    if let Some(time) = time {
+        let mut time_parts = time.splitn(3, ':');
+        hour = time_parts.next()?.parse().ok()?;
+        minute = time_parts.next()?.parse().ok()?;
+        second = match time_parts.next() {
+            Some(second) => second.parse().ok()?,
+            None => 0,
+        };
+        if hour > 23 || minute > 59 || second > 59 {
+            return None;
+        }
+    }
+
+    let days = days_from_civil(year, month, day);
+    Some(((days * 24 + hour) * 60 + minute) * 60_000 + second * 1_000)
+}
+
+/// Days since 1970-01-01 of a proleptic Gregorian date (Howard Hinnant's algorithm).
+pub const fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let month = month as i64;
+    let day_of_year =
+        (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + day as i64 - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// Year, month and day of the date `days` after 1970-01-01; inverse of [`days_from_civil`].
+pub const fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * mp + 2) / 5 + 1) as u32;
+    let month = (if mp < 10 { mp + 3 } else { mp - 9 }) as u32;
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_utc_millis;
+
+    #[test]
+    fn parses_utc_times() {
+        assert_eq!(parse_utc_millis("1704067200000"), Some(1_704_067_200_000));
+        assert_eq!(parse_utc_millis("2024-01-01"), Some(1_704_067_200_000));
+        assert_eq!(
+            parse_utc_millis("2024-03-01T12:30Z"),
+            Some(1_709_296_200_000)
+        );
+        assert_eq!(parse_utc_millis("1969-12-31 23:59:59"), Some(-1_000));
+        assert_eq!(parse_utc_millis("2024-13-01"), None);
+        assert_eq!(parse_utc_millis("yesterday"), None);
+    }
+}
@@ -1,19 +1,23 @@
-use std::collections::BTreeMap;
+use std::{
+    collections::{BTreeMap, HashMap},
+    ops::Range,
+};
 
 use ratatui::{layout::Rect, widgets::TableState};
 
 use crate::{
+    indicators::{IndicatorId, IndicatorSpec, REGISTRY, Reading, Threshold},
     message_bus::{
-        EngineTx, HistoryTx, IndicatorThresholds, IndicatorValue, UiMessage, UiRx, WsTx,
+        EngineTx, HistoryMessage, HistoryTx, PriorityEvent, UiMessage, UiRx, WarmupProgress, WsTx,
     },
     tui::{
         data::{
-            DashboardData, IndicatorConfig, IndicatorKind, IndicatorState, PairRow,
+            DashboardData, IndicatorConfig, IndicatorState, PairRow, WarmupState,
             default_indicator_state,
         },
-        settings::{ALL_TIMEFRAMES, DEFAULT_PRESET_LABEL, PresetStore, SettingsForm},
+        settings::{DEFAULT_PRESET_LABEL, PresetStore, SettingsForm},
     },
-    types::{AppConfig, Timeframe, config},
+    types::{AppConfig, Pair, Timeframe, config},
 };
 
 use super::{
@@ -33,10 +37,11 @@ pub struct DashboardApp {
     data: DashboardData,
     active_config: Option<AppConfig>,
     default_lookup: config::IndexLookup,
-    indicator_values: Vec<f32>,
+    indicator_values: Vec<Reading>,
     indicator_labels: Vec<String>,
-    indicator_thresholds: BTreeMap<(IndicatorKind, Timeframe), IndicatorThresholds>,
+    indicator_thresholds: BTreeMap<(IndicatorId, Timeframe), Threshold>,
     indicator_state: IndicatorState,
+    selected_indicator: IndicatorId,
     settings: SettingsForm,
     settings_draft: SettingsForm,
     preset_store: PresetStore,
@@ -49,6 +54,9 @@ pub struct DashboardApp {
     view: ViewMode,
     focus_idx: usize,
     table_state: TableState,
+    visible_pairs: Range<usize>,
+    priority_dirty: bool,
+    warmup: HashMap<String, WarmupState>,
 }
 
 impl DashboardApp {
@@ -68,7 +76,7 @@ impl DashboardApp {
         let data = dashboard_from_settings(&settings);
         let indicator_state = default_indicator_state(&data.indicator_config);
         let indicator_thresholds = indicator_thresholds_from_settings(&settings);
-        let default_lookup = config::IndexLookup::new(&[], false, &[], false, &[]);
+        let default_lookup = config::IndexLookup::new(&[], &[]);
 
         Self {
             engine_tx,
@@ -83,6 +91,7 @@ impl DashboardApp {
             indicator_labels: Vec::new(),
             indicator_thresholds,
             indicator_state,
+            selected_indicator: REGISTRY[0].id,
             settings: settings.clone(),
             settings_draft: settings,
             preset_store,
@@ -95,34 +104,117 @@ impl DashboardApp {
             view: ViewMode::Dashboard,
             focus_idx: 0,
             table_state: TableState::default(),
+            visible_pairs: 0..0,
+            priority_dirty: false,
+            warmup: HashMap::new(),
         }
     }
 
     pub fn poll_updates(&mut self) {
-        while let Ok(UiMessage::IndicatorResults(batch)) = self.rx.try_recv() {
-            for (idx, val) in batch {
-                if idx >= self.indicator_values.len() {
-                    self.indicator_values.resize(idx + 1, 0.0);
-                    self.indicator_labels
-                        .resize(idx + 1, IndicatorValue::Volatility(0.0).display());
+        while let Ok(message) = self.rx.try_recv() {
+            match message {
+                UiMessage::IndicatorResults(batch) => {
+                    for (idx, val) in batch {
+                        if idx >= self.indicator_values.len() {
+                            self.indicator_values.resize(idx + 1, Reading::default());
+                            self.indicator_labels.resize(idx + 1, String::new());
+                        }
+                        let display = val.display();
+                        self.indicator_values[idx] = val.reading();
+                        self.indicator_labels[idx] = display;
+                    }
                 }
-                let display = val.display();
-                let numeric = match val {
-                    IndicatorValue::Volatility(v) | IndicatorValue::Rsi(v) => v,
-                };
-                self.indicator_values[idx] = numeric;
-                self.indicator_labels[idx] = display;
+                UiMessage::WarmupProgress(progress) => self.apply_warmup_progress(progress),
+            }
+        }
+    }
+
+    fn apply_warmup_progress(&mut self, progress: WarmupProgress) {
+        let (pair, state) = match progress {
+            WarmupProgress::Requested(pair) | WarmupProgress::Fetched(pair) => {
+                (pair, WarmupState::Warming)
             }
+            WarmupProgress::Ready(pair) => (pair, WarmupState::Ready),
+            WarmupProgress::Failed { pair, reason } => (pair, WarmupState::Failed(reason)),
+        };
+        // Ignore stragglers for pairs that are no longer configured.
+        if let Some(entry) = self.warmup.get_mut(&pair.0) {
+            *entry = state;
         }
     }
 
-    pub fn on_tick(&mut self) {}
+    /// Mark every configured pair as pending; called when a config is activated.
+    pub fn reset_warmup(&mut self) {
+        self.warmup = self
+            .data
+            .pairs
+            .iter()
+            .map(|row| (row.pair.clone(), WarmupState::Pending))
+            .collect();
+    }
+
+    pub fn warmup_state(&self, pair: &str) -> Option<&WarmupState> {
+        self.warmup.get(pair)
+    }
+
+    pub fn warmup_states(&self) -> impl Iterator<Item = &WarmupState> {
+        self.warmup.values()
+    }
+
+    pub fn on_tick(&mut self) {
+        if self.priority_dirty && self.active_config.is_some() {
+            self.priority_dirty = false;
+            self.send_warmup_priority();
+        }
+    }
+
+    /// Tell the history service which pairs to warm up first.
+    fn send_warmup_priority(&self) {
+        let visible = self
+            .data
+            .pairs
+            .get(self.visible_pairs.clone())
+            .unwrap_or_default()
+            .iter()
+            .map(|row| Pair(row.pair.clone()))
+            .collect();
+        let pinned = self
+            .settings
+            .pinned_pairs
+            .iter()
+            .cloned()
+            .map(Pair)
+            .collect();
+        let tx = self.history_tx.clone();
+        self.rt_handle.spawn(async move {
+            if let Err(err) = tx
+                .send(HistoryMessage::Priority(PriorityEvent { visible, pinned }))
+                .await
+            {
+                log::error!("failed to send warmup priority to history: {err}");
+            }
+        });
+    }
+
+    pub fn set_visible_pairs(&mut self, range: Range<usize>) {
+        if self.visible_pairs != range {
+            self.visible_pairs = range;
+            self.priority_dirty = true;
+        }
+    }
+
+    pub fn mark_priority_dirty(&mut self) {
+        self.priority_dirty = true;
+    }
+
+    pub fn is_pinned(&self, pair: &str) -> bool {
+        self.settings.pinned_pairs.iter().any(|p| p == pair)
+    }
 
     pub fn mark_quit(&mut self) {
         self.should_quit = true;
     }
 
-
     pub fn set_view(&mut self, view: ViewMode) {
         self.view = view;
     }
@@ -142,15 +234,18 @@ impl DashboardApp {
             SettingsField::ClonePreset,
         ];
         fields.push(SettingsField::PairsInput);
-        fields.push(SettingsField::VolatilityEnabled);
-        for tf in ALL_TIMEFRAMES {
-            fields.push(SettingsField::VolatilityTf(tf));
+        fields.push(SettingsField::CustomTimeframes);
+        fields.push(SettingsField::Market);
+        fields.push(SettingsField::WarmupConcurrency);
+        fields.push(SettingsField::UtcOffset);
+        fields.push(SettingsField::Indicators);
+        let id = self.selected_indicator;
+        let spec = id.spec();
+        for index in 0..spec.params.len() {
+            fields.push(SettingsField::IndicatorParam(id, index));
         }
-        fields.push(SettingsField::RsiEnabled);
-        fields.push(SettingsField::RsiLength);
-        fields.push(SettingsField::RsiSource);
-        for tf in ALL_TIMEFRAMES {
-            fields.push(SettingsField::RsiTf(tf));
+        for tf in spec.timeframes(&self.settings_draft().custom_timeframes()) {
+            fields.push(SettingsField::IndicatorTf(id, tf));
         }
         fields.push(SettingsField::Save);
         fields.push(SettingsField::Cancel);
@@ -227,24 +322,31 @@ impl DashboardApp {
         &self.indicator_state
     }
 
-    pub fn indicator_thresholds(
-        &self,
-    ) -> &BTreeMap<(IndicatorKind, Timeframe), IndicatorThresholds> {
+    pub fn indicator_thresholds(&self) -> &BTreeMap<(IndicatorId, Timeframe), Threshold> {
         &self.indicator_thresholds
     }
 
     pub fn set_indicator_thresholds(
         &mut self,
-        thresholds: BTreeMap<(IndicatorKind, Timeframe), IndicatorThresholds>,
+        thresholds: BTreeMap<(IndicatorId, Timeframe), Threshold>,
     ) {
         self.indicator_thresholds = thresholds;
     }
 
-    pub fn indicator_values(&self) -> &[f32] {
+    /// Indicator whose settings the settings panel shows.
+    pub fn selected_indicator(&self) -> IndicatorId {
+        self.selected_indicator
+    }
+
+    pub fn set_selected_indicator(&mut self, id: IndicatorId) {
+        self.selected_indicator = id;
+    }
+
+    pub fn indicator_values(&self) -> &[Reading] {
         &self.indicator_values
     }
 
-    pub fn set_indicator_buffers(&mut self, values: Vec<f32>, labels: Vec<String>) {
+    pub fn set_indicator_buffers(&mut self, values: Vec<Reading>, labels: Vec<String>) {
         self.indicator_values = values;
         self.indicator_labels = labels;
     }
@@ -257,6 +359,10 @@ impl DashboardApp {
         &mut self.table_state
     }
 
+    pub fn table_state_ref(&self) -> &TableState {
+        &self.table_state
+    }
+
     pub fn active_index_lookup(&self) -> &config::IndexLookup {
         self.active_config
             .as_ref()
@@ -381,12 +487,12 @@ impl DashboardApp {
             .map(|p| p.pair.len())
             .max()
             .unwrap_or(4);
-        let padding = 2;
+        let padding = 4; // room for the pin marker
         let desired = (max_pair + padding) as u16;
         desired.min(area.width.saturating_sub(10)).max(8)
     }
 
-    pub fn value_width(&self) -> u16 {
-        6
+    pub fn value_width(&self, spec: &IndicatorSpec) -> u16 {
+        spec.width
     }
 }
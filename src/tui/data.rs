@@ -1,22 +1,6 @@
 use std::collections::BTreeMap;
 
-use crate::types::Timeframe;
-
-/// Indicators supported on the dashboard.
-#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
-pub enum IndicatorKind {
-    Volatility,
-    Rsi,
-}
-
-impl IndicatorKind {
-    pub const fn label(&self) -> &'static str {
-        match self {
-            IndicatorKind::Volatility => "VOLATILITY",
-            IndicatorKind::Rsi => "RSI",
-        }
-    }
-}
+use crate::{indicators::IndicatorId, types::Timeframe};
 
 /// UI metrics for the dashboard layout.
 #[derive(Clone, Copy, Debug)]
@@ -48,12 +32,22 @@ impl Default for UiMetrics {
 
 #[derive(Clone, Debug)]
 pub struct IndicatorConfig {
-    pub kind: IndicatorKind,
+    pub id: IndicatorId,
     pub enabled: bool,
     pub timeframes: Vec<Timeframe>,
     pub thresholds: BTreeMap<Timeframe, f32>,
 }
 
+/// Warmup state of a dashboard row.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum WarmupState {
+    #[default]
+    Pending,
+    Warming,
+    Ready,
+    Failed(String),
+}
+
 #[derive(Clone, Debug)]
 pub struct PairRow {
     pub pair: String,
@@ -65,16 +59,16 @@ pub struct DashboardData {
     pub indicator_config: Vec<IndicatorConfig>,
 }
 
-pub type IndicatorState = BTreeMap<IndicatorKind, bool>;
+pub type IndicatorState = BTreeMap<IndicatorId, bool>;
 
 pub fn default_indicator_state(config: &[IndicatorConfig]) -> IndicatorState {
-    config.iter().map(|cfg| (cfg.kind, cfg.enabled)).collect()
+    config.iter().map(|cfg| (cfg.id, cfg.enabled)).collect()
 }
 
 fn default_indicator_config() -> Vec<IndicatorConfig> {
     vec![
         IndicatorConfig {
-            kind: IndicatorKind::Volatility,
+            id: IndicatorId::VOLATILITY,
             enabled: true,
             timeframes: vec![Timeframe::M15, Timeframe::H1, Timeframe::H4, Timeframe::D1],
             thresholds: thresholds_for(&[
@@ -85,7 +79,7 @@ fn default_indicator_config() -> Vec<IndicatorConfig> {
             ]),
         },
         IndicatorConfig {
-            kind: IndicatorKind::Rsi,
+            id: IndicatorId::RSI,
             enabled: true,
             timeframes: vec![Timeframe::M15, Timeframe::H1, Timeframe::H4, Timeframe::D1],
             thresholds: BTreeMap::new(),
@@ -116,24 +110,15 @@ impl DashboardDataBuilder {
         self
     }
 
-    pub fn indicator(
-        mut self,
-        kind: IndicatorKind,
-        enabled: bool,
-        timeframes: Vec<Timeframe>,
-    ) -> Self {
+    pub fn indicator(mut self, id: IndicatorId, enabled: bool, timeframes: Vec<Timeframe>) -> Self {
         let thresholds = thresholds_for(&timeframes);
-        if let Some(cfg) = self
-            .indicator_config
-            .iter_mut()
-            .find(|cfg| cfg.kind == kind)
-        {
+        if let Some(cfg) = self.indicator_config.iter_mut().find(|cfg| cfg.id == id) {
             cfg.enabled = enabled;
             cfg.timeframes = timeframes;
             cfg.thresholds = thresholds;
         } else {
             self.indicator_config.push(IndicatorConfig {
-                kind,
+                id,
                 enabled,
                 timeframes,
                 thresholds,
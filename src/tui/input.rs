@@ -1,12 +1,13 @@
 use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
 
 use crate::{
-    message_bus::{EngineMessage, HistoryMessage, IndicatorValue, WsMessage},
+    indicators::{IndicatorId, REGISTRY, Reading, ThresholdSpec, TimeframeSet},
+    message_bus::{EngineMessage, HistoryMessage, WsMessage},
     tui::{
         layout,
-        settings::{ALL_TIMEFRAMES, SettingsForm, VolatilityTimeframeSetting},
+        settings::{MAX_WARMUP_CONCURRENCY, SettingsForm},
     },
-    types::{AppConfig, KlineSource, Timeframe},
+    types::{AppConfig, Market, Timeframe, UtcOffset},
 };
 
 use super::{
@@ -31,6 +32,7 @@ impl DashboardApp {
             KeyCode::Char('q') => self.mark_quit(),
             KeyCode::Char('s') => self.open_settings(),
             KeyCode::Char('l') => self.open_layout(),
+            KeyCode::Char('p') => self.toggle_pin_selected(),
             KeyCode::Up => self.move_selection_up(),
             KeyCode::Down => self.move_selection_down(),
             _ => {}
@@ -78,62 +80,64 @@ impl DashboardApp {
                 }
                 _ => {}
             },
-            SettingsField::VolatilityEnabled => {
-                if matches!(key.code, KeyCode::Enter | KeyCode::Char(' ')) {
-                    self.settings_draft_mut().volatility_enabled =
-                        !self.settings_draft().volatility_enabled
+            SettingsField::CustomTimeframes => match key.code {
+                KeyCode::Char(c)
+                    if !key.modifiers.contains(KeyModifiers::CONTROL)
+                        && (c.is_ascii_digit() || "mhd@:, ".contains(c)) =>
+                {
+                    self.settings_draft_mut().custom_timeframes.push(c);
                 }
-            }
-            SettingsField::VolatilityTf(tf) => match key.code {
-                KeyCode::Enter | KeyCode::Char(' ') => {
-                    let entry = self
-                        .settings_draft_mut()
-                        .volatility_timeframes
-                        .entry(tf)
-                        .or_insert(VolatilityTimeframeSetting {
-                            enabled: false,
-                            threshold: 0.0,
-                        });
-                    entry.enabled = !entry.enabled;
+                KeyCode::Backspace => {
+                    self.settings_draft_mut().custom_timeframes.pop();
                 }
-                KeyCode::Left => self.adjust_threshold(tf, -0.5),
-                KeyCode::Right => self.adjust_threshold(tf, 0.5),
-                KeyCode::Up => self.adjust_threshold(tf, 5.0),
-                KeyCode::Down => self.adjust_threshold(tf, -5.0),
                 _ => {}
             },
-            SettingsField::RsiEnabled => {
-                if matches!(key.code, KeyCode::Enter | KeyCode::Char(' ')) {
-                    self.settings_draft_mut().rsi_enabled = !self.settings_draft().rsi_enabled
+            SettingsField::Market => {
+                if matches!(
+                    key.code,
+                    KeyCode::Left | KeyCode::Right | KeyCode::Enter | KeyCode::Char(' ')
+                ) {
+                    self.cycle_market();
                 }
             }
-            SettingsField::RsiLength => match key.code {
-                KeyCode::Left | KeyCode::Down => {
-                    if self.settings_draft().rsi_length > 1 {
-                        self.settings_draft_mut().rsi_length -= 1;
-                    }
-                }
-                KeyCode::Right | KeyCode::Up => {
-                    self.settings_draft_mut().rsi_length =
-                        (self.settings_draft().rsi_length + 1).min(250);
-                }
+            SettingsField::WarmupConcurrency => match key.code {
+                KeyCode::Left | KeyCode::Down => self.adjust_warmup_concurrency(-1),
+                KeyCode::Right | KeyCode::Up => self.adjust_warmup_concurrency(1),
                 _ => {}
             },
-            SettingsField::RsiSource => {
-                if matches!(key.code, KeyCode::Left | KeyCode::Right | KeyCode::Enter) {
-                    self.cycle_source();
-                }
-            }
-            SettingsField::RsiTf(tf) => {
-                if matches!(key.code, KeyCode::Enter | KeyCode::Char(' ')) {
-                    let entry = self
-                        .settings_draft_mut()
-                        .rsi_timeframes
-                        .entry(tf)
-                        .or_insert(false);
-                    *entry = !*entry;
+            // Quarter hours reach every offset Binance accepts, e.g. +05:45.
+            SettingsField::UtcOffset => match key.code {
+                KeyCode::Left => self.adjust_utc_offset(-15),
+                KeyCode::Right => self.adjust_utc_offset(15),
+                KeyCode::Down => self.adjust_utc_offset(-60),
+                KeyCode::Up => self.adjust_utc_offset(60),
+                _ => {}
+            },
+            SettingsField::Indicators => match key.code {
+                KeyCode::Left | KeyCode::Up => self.cycle_indicator(-1),
+                KeyCode::Right | KeyCode::Down => self.cycle_indicator(1),
+                KeyCode::Enter | KeyCode::Char(' ') => {
+                    let id = self.selected_indicator();
+                    let entry = self.settings_draft_mut().indicator_mut(id);
+                    entry.enabled = !entry.enabled;
                 }
-            }
+                _ => {}
+            },
+            SettingsField::IndicatorParam(id, index) => match key.code {
+                KeyCode::Left => self.adjust_param(id, index, -1, false),
+                KeyCode::Right | KeyCode::Enter => self.adjust_param(id, index, 1, false),
+                KeyCode::Up => self.adjust_param(id, index, 1, true),
+                KeyCode::Down => self.adjust_param(id, index, -1, true),
+                _ => {}
+            },
+            SettingsField::IndicatorTf(id, tf) => match key.code {
+                KeyCode::Enter | KeyCode::Char(' ') => self.toggle_timeframe(id, tf),
+                KeyCode::Left => self.adjust_threshold(id, tf, -1.0, false),
+                KeyCode::Right => self.adjust_threshold(id, tf, 1.0, false),
+                KeyCode::Up => self.adjust_threshold(id, tf, 1.0, true),
+                KeyCode::Down => self.adjust_threshold(id, tf, -1.0, true),
+                _ => {}
+            },
             SettingsField::Save => {
                 if matches!(key.code, KeyCode::Enter) {
                     self.save_settings();
@@ -243,6 +247,38 @@ impl DashboardApp {
         }
     }
 
+    fn toggle_pin_selected(&mut self) {
+        let Some(selected) = self.table_state().selected() else {
+            return;
+        };
+        let Some(pair) = self.pairs().get(selected).map(|row| row.pair.clone()) else {
+            return;
+        };
+
+        let mut pinned = self.settings().pinned_pairs.clone();
+        if let Some(pos) = pinned.iter().position(|p| *p == pair) {
+            pinned.remove(pos);
+        } else {
+            pinned.push(pair);
+        }
+
+        let mut settings = self.settings().clone();
+        settings.pinned_pairs = pinned.clone();
+        self.set_settings(settings);
+        self.settings_draft_mut().pinned_pairs = pinned.clone();
+
+        // Persist only the pins, leaving any unsaved settings edits alone.
+        if let Some(label) = self.active_preset_label().cloned()
+            && let Some(mut preset) = self.preset_store().get(&label).cloned()
+        {
+            preset.settings.pinned_pairs = pinned;
+            let store = self.preset_store_mut();
+            store.upsert(label, preset.settings);
+            store.save();
+        }
+        self.mark_priority_dirty();
+    }
+
     fn move_selection_up(&mut self) {
         let total = self.pairs().len();
         if total == 0 {
@@ -357,26 +393,69 @@ impl DashboardApp {
         self.select_preset(next_label);
     }
 
-    fn adjust_threshold(&mut self, tf: Timeframe, delta: f32) {
-        let entry = self
-            .settings_draft_mut()
-            .volatility_timeframes
-            .entry(tf)
-            .or_insert(VolatilityTimeframeSetting {
-                enabled: false,
-                threshold: 0.0,
-            });
-        entry.threshold = (entry.threshold + delta).abs();
+    fn cycle_indicator(&mut self, delta: isize) {
+        let current = REGISTRY
+            .iter()
+            .position(|spec| spec.id == self.selected_indicator())
+            .unwrap_or(0);
+        let next = (current as isize + delta).rem_euclid(REGISTRY.len() as isize) as usize;
+        self.set_selected_indicator(REGISTRY[next].id);
+    }
+
+    fn adjust_param(&mut self, id: IndicatorId, index: usize, steps: i32, coarse: bool) {
+        let spec = id.spec();
+        let Some(param) = spec.params.get(index) else {
+            return;
+        };
+        let entry = self.settings_draft_mut().indicator_mut(id);
+        let value = param.stepped(entry.param(spec, index), steps, coarse);
+        entry.params.insert(param.key.to_string(), value);
+    }
+
+    fn toggle_timeframe(&mut self, id: IndicatorId, tf: Timeframe) {
+        let spec = id.spec();
+        // A single column follows its indicator's toggle.
+        if matches!(spec.timeframes, TimeframeSet::Single(_)) {
+            return;
+        }
+        let entry = self.settings_draft_mut().indicator_mut(id);
+        let mut setting = entry.timeframe(spec, tf);
+        setting.enabled = !setting.enabled;
+        entry.timeframes.insert(tf, setting);
+    }
+
+    fn adjust_threshold(&mut self, id: IndicatorId, tf: Timeframe, sign: f32, coarse: bool) {
+        let spec = id.spec();
+        let Some((step, coarse_step)) = spec.threshold.steps() else {
+            return;
+        };
+        let decimals = spec.threshold.decimals();
+        let delta = sign * if coarse { coarse_step } else { step };
+        let entry = self.settings_draft_mut().indicator_mut(id);
+        let mut setting = entry.timeframe(spec, tf);
+        // Round to the display precision so repeated steps don't drift.
+        let scale = 10_f32.powi(decimals as i32);
+        setting.threshold = (((setting.threshold + delta) * scale).round() / scale).abs();
+        entry.timeframes.insert(tf, setting);
+    }
+
+    fn adjust_warmup_concurrency(&mut self, delta: isize) {
+        let draft = self.settings_draft_mut();
+        let next = (draft.warmup_concurrency as isize + delta).max(1) as usize;
+        draft.warmup_concurrency = next.min(MAX_WARMUP_CONCURRENCY);
+    }
+
+    fn adjust_utc_offset(&mut self, delta: i32) {
+        let draft = self.settings_draft_mut();
+        draft.utc_offset = UtcOffset::from_minutes(draft.utc_offset.minutes() as i32 + delta);
     }
 
-    fn cycle_source(&mut self) {
-        let next = match self.settings_draft().rsi_source {
-            KlineSource::Open => KlineSource::High,
-            KlineSource::High => KlineSource::Low,
-            KlineSource::Low => KlineSource::Close,
-            KlineSource::Close => KlineSource::Open,
+    fn cycle_market(&mut self) {
+        let next = match self.settings_draft().market {
+            Market::Spot => Market::UsdmFutures,
+            Market::UsdmFutures => Market::Spot,
         };
-        self.settings_draft_mut().rsi_source = next;
+        self.settings_draft_mut().market = next;
     }
 
     fn select_preset(&mut self, label: String) {
@@ -437,10 +516,12 @@ impl DashboardApp {
             let pair_stride = config.index_lookup().pair_stride();
             let total_slots = config.index_lookup().pair_count() * pair_stride;
             self.set_indicator_buffers(
-                vec![0.0; total_slots],
-                vec![IndicatorValue::Volatility(0.0).display(); total_slots],
+                vec![Reading::default(); total_slots],
+                vec![String::new(); total_slots],
             );
             self.set_active_config(Some(config.clone()));
+            self.mark_priority_dirty();
+            self.reset_warmup();
 
             rt_handle.spawn(async move {
                 let (engine_res, history_res, ws_res) = tokio::join!(
@@ -3,17 +3,60 @@ use ratatui::{
     layout::{Alignment, Constraint, Direction, Flex, Layout, Rect},
     style::{Color, Modifier, Style},
     text::{Span, Text},
-    widgets::{Block, Borders, Cell, Paragraph, Row, Table, TableState, Wrap},
+    widgets::{Block, Borders, Cell, LineGauge, Paragraph, Row, Table, TableState, Wrap},
 };
 
-use crate::tui::data::{IndicatorConfig, IndicatorKind, PairRow};
+use crate::tui::data::{IndicatorConfig, PairRow, WarmupState};
 
-use super::{intro::render_intro, super::app::DashboardApp};
 use super::super::util::{
-    HEADER_COLOR, INDICATOR_GROUP_BG, PAIR_COLOR, lookup_value, tf_label, value_style,
+    FIELD_ACTIVE, FIELD_INACTIVE, HEADER_COLOR, INDICATOR_GROUP_BG, NEGATIVE_TEXT, PAIR_COLOR,
+    lookup_value, value_style,
 };
+use super::{super::app::DashboardApp, intro::render_intro};
+
+const WARMING_COLOR: Color = Color::Rgb(230, 190, 80);
+
+/// Warmup progress bar; hidden once every pair is ready.
+pub fn render_header(frame: &mut Frame, area: Rect, app: &DashboardApp) {
+    let (mut total, mut ready, mut warming, mut failed) = (0, 0, 0, 0);
+    for state in app.warmup_states() {
+        total += 1;
+        match state {
+            WarmupState::Pending => {}
+            WarmupState::Warming => warming += 1,
+            WarmupState::Ready => ready += 1,
+            WarmupState::Failed(_) => failed += 1,
+        }
+    }
+    if total == 0 || (ready == total && failed == 0) {
+        return;
+    }
+
+    let mut label = format!("Warmup {ready}/{total} ready");
+    if warming > 0 {
+        label.push_str(&format!(" • {warming} warming"));
+    }
+    if failed > 0 {
+        label.push_str(&format!(" • {failed} failed"));
+    }
+    // Surface the reason when the selected row is the one that failed.
+    let selected = app
+        .table_state_ref()
+        .selected()
+        .and_then(|idx| app.pairs().get(idx));
+    if let Some(row) = selected
+        && let Some(WarmupState::Failed(reason)) = app.warmup_state(&row.pair)
+    {
+        label.push_str(&format!(" • {}: {reason}", row.pair));
+    }
 
-pub fn render_header(_frame: &mut Frame, _area: Rect, _app: &DashboardApp) {}
+    let gauge = LineGauge::default()
+        .ratio(ready as f64 / total as f64)
+        .label(label)
+        .filled_style(Style::default().fg(FIELD_ACTIVE))
+        .unfilled_style(Style::default().fg(FIELD_INACTIVE));
+    frame.render_widget(gauge, area);
+}
 
 pub fn render_dashboard(frame: &mut Frame, area: Rect, app: &mut DashboardApp) {
     let active = app
@@ -28,7 +71,7 @@ pub fn render_dashboard(frame: &mut Frame, area: Rect, app: &mut DashboardApp) {
     widths.push(Constraint::Length(app.pair_width(&area)));
     for cfg in active.iter() {
         for _ in &cfg.timeframes {
-            widths.push(Constraint::Length(app.value_width()));
+            widths.push(Constraint::Length(app.value_width(cfg.id.spec())));
         }
     }
     let column_widths: Vec<u16> = widths
@@ -108,6 +151,10 @@ pub fn render_dashboard(frame: &mut Frame, area: Rect, app: &mut DashboardApp) {
         let state = app.table_state();
         (state.selected(), state.offset())
     };
+    let visible_end = offset_pair
+        .saturating_add(total_visible_pairs)
+        .min(app.pairs().len());
+    app.set_visible_pairs(offset_pair.min(visible_end)..visible_end);
 
     for idx in 0..table_count as usize {
         let column_area = table_columns[idx];
@@ -134,11 +181,12 @@ pub fn render_dashboard(frame: &mut Frame, area: Rect, app: &mut DashboardApp) {
             .row_highlight_style(Style::default().add_modifier(Modifier::REVERSED));
 
         let mut table_state = TableState::default();
-        if let Some(selected) = selected_pair {
-            if selected >= start_pair && selected < start_pair + visible_pairs {
-                let local = selected - start_pair;
-                table_state.select(Some(local.saturating_mul(2).saturating_add(1)));
-            }
+        if let Some(selected) = selected_pair
+            && selected >= start_pair
+            && selected < start_pair + visible_pairs
+        {
+            let local = selected - start_pair;
+            table_state.select(Some(local.saturating_mul(2).saturating_add(1)));
         }
 
         frame.render_stateful_widget(table, body_area, &mut table_state);
@@ -157,7 +205,7 @@ fn timeframe_header(active: &[IndicatorConfig]) -> Row<'static> {
     for (idx, cfg) in active.iter().enumerate() {
         for tf in &cfg.timeframes {
             let tf_text = Text::from(Span::styled(
-                tf_label(*tf),
+                cfg.id.spec().column_label(*tf),
                 Style::default()
                     .fg(HEADER_COLOR)
                     .add_modifier(Modifier::BOLD),
@@ -223,7 +271,7 @@ fn render_indicator_groups(
         } else {
             Borders::RIGHT
         };
-        let indicator_header = Paragraph::new(cfg.kind.label())
+        let indicator_header = Paragraph::new(cfg.id.spec().label)
             .block(
                 Block::default()
                     .borders(border)
@@ -290,30 +338,38 @@ fn dashboard_rows_range(
 
 fn dashboard_row(app: &DashboardApp, pair: &PairRow, active: &[IndicatorConfig]) -> Row<'static> {
     let mut cells = Vec::new();
-    cells.push(Cell::from(Span::styled(
-        pair.pair.clone(),
-        Style::default().fg(PAIR_COLOR),
-    )));
+    let pair_color = match app.warmup_state(&pair.pair) {
+        Some(WarmupState::Pending) => FIELD_INACTIVE,
+        Some(WarmupState::Warming) => WARMING_COLOR,
+        Some(WarmupState::Failed(_)) => NEGATIVE_TEXT,
+        Some(WarmupState::Ready) | None => PAIR_COLOR,
+    };
+    let (label, pair_style) = if app.is_pinned(&pair.pair) {
+        (
+            format!("{} *", pair.pair),
+            Style::default().fg(pair_color).add_modifier(Modifier::BOLD),
+        )
+    } else {
+        (pair.pair.clone(), Style::default().fg(pair_color))
+    };
+    cells.push(Cell::from(Span::styled(label, pair_style)));
 
     for (idx, cfg) in active.iter().enumerate() {
+        let spec = cfg.id.spec();
         for tf in &cfg.timeframes {
-            let (value, display) = lookup_value(
+            let (reading, display) = lookup_value(
                 &pair.pair,
-                cfg.kind,
+                cfg.id,
                 *tf,
                 app.active_index_lookup(),
                 app.indicator_values(),
                 app.indicator_labels(),
             );
-            let threshold = app.indicator_thresholds().get(&(cfg.kind, *tf));
-            let indicator_value = match cfg.kind {
-                IndicatorKind::Volatility => crate::message_bus::IndicatorValue::Volatility(value),
-                IndicatorKind::Rsi => crate::message_bus::IndicatorValue::Rsi(value),
-            };
-            let style = value_style(&indicator_value, threshold);
+            let threshold = app.indicator_thresholds().get(&(cfg.id, *tf));
+            let style = value_style(spec, reading, threshold);
             let text = display
                 .map(|d| d.to_string())
-                .unwrap_or_else(|| indicator_value.display());
+                .unwrap_or_else(|| spec.format(reading.value));
             let cell_text = Text::from(Span::styled(text, style)).centered();
             cells.push(Cell::from(cell_text));
         }
@@ -336,7 +392,8 @@ fn separator_row(column_widths: &[u16]) -> Row<'static> {
 }
 
 pub fn render_footer(frame: &mut Frame, area: Rect) {
-    let footer = Paragraph::new("↑/↓ to scroll rows • s: settings • l: layout • q: quit")
-        .wrap(Wrap { trim: true });
+    let footer =
+        Paragraph::new("↑/↓ to scroll rows • p: pin pair • s: settings • l: layout • q: quit")
+            .wrap(Wrap { trim: true });
     frame.render_widget(footer, area);
 }
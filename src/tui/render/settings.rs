@@ -8,14 +8,18 @@ use ratatui::{
     },
 };
 
-use crate::tui::settings::{ALL_TIMEFRAMES, MAX_PAIRS, VolatilityTimeframeSetting};
+use crate::{
+    indicators::{REGISTRY, ThresholdSpec},
+    tui::settings::MAX_PAIRS,
+    types::Timeframe,
+};
 
 use super::super::{
     app::DashboardApp,
     types::SettingsField,
     util::{
         ACTIVE_ROW_BG, FIELD_ACTIVE, FIELD_INACTIVE, HEADER_COLOR, PAIR_COLOR, centered_rect,
-        kline_source_label, pair_count, tf_label, toggle_label,
+        pair_count, toggle_label,
     },
 };
 
@@ -95,17 +99,20 @@ fn render_detail(frame: &mut Frame, area: Rect, app: &DashboardApp) {
         .constraints([
             Constraint::Length(3),
             Constraint::Length(17),
-            Constraint::Min(7),
-            Constraint::Min(7),
+            Constraint::Min(14),
             Constraint::Length(3),
         ])
         .split(area);
 
     render_top_bar(frame, sections[0], app);
-    render_pairs(frame, sections[1], app);
-    render_volatility(frame, sections[2], app);
-    render_rsi(frame, sections[3], app);
-    render_actions(frame, sections[4], app);
+    let pairs_section = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(3), Constraint::Length(3)])
+        .split(sections[1]);
+    render_pairs(frame, pairs_section[0], app);
+    render_custom_timeframes(frame, pairs_section[1], app);
+    render_indicators(frame, sections[2], app);
+    render_actions(frame, sections[3], app);
 }
 
 fn render_top_bar(frame: &mut Frame, area: Rect, app: &DashboardApp) {
@@ -114,6 +121,8 @@ fn render_top_bar(frame: &mut Frame, area: Rect, app: &DashboardApp) {
         .constraints([
             Constraint::Length(18),
             Constraint::Min(1),
+            Constraint::Length(18),
+            Constraint::Length(18),
             Constraint::Length(12),
         ])
         .split(area);
@@ -146,11 +155,36 @@ fn render_top_bar(frame: &mut Frame, area: Rect, app: &DashboardApp) {
     if focus_clone {
         clone_style = clone_style.add_modifier(Modifier::BOLD | Modifier::UNDERLINED);
     }
+    let focus_market = matches!(app.focus_field(), SettingsField::Market);
+    let market_style = field_style(true, focus_market);
+    let market_label = format!("Market: {} v", app.settings_draft().market.label());
+    let market_button = Paragraph::new(market_label)
+        .alignment(Alignment::Center)
+        .style(market_style)
+        .block(Block::default().borders(Borders::ALL));
+    frame.render_widget(market_button, row[1]);
+
+    let focus_warmup = matches!(app.focus_field(), SettingsField::WarmupConcurrency);
+    let warmup_label = format!("Warmup jobs: {}", app.settings_draft().warmup_concurrency);
+    let warmup_button = Paragraph::new(warmup_label)
+        .alignment(Alignment::Center)
+        .style(field_style(true, focus_warmup))
+        .block(Block::default().borders(Borders::ALL));
+    frame.render_widget(warmup_button, row[2]);
+
+    let focus_offset = matches!(app.focus_field(), SettingsField::UtcOffset);
+    let offset_label = format!("Day: UTC{}", app.settings_draft().utc_offset);
+    let offset_button = Paragraph::new(offset_label)
+        .alignment(Alignment::Center)
+        .style(field_style(true, focus_offset))
+        .block(Block::default().borders(Borders::ALL));
+    frame.render_widget(offset_button, row[3]);
+
     let clone_button = Paragraph::new("Clone")
         .alignment(Alignment::Center)
         .style(clone_style)
         .block(Block::default().borders(Borders::ALL));
-    frame.render_widget(clone_button, row[2]);
+    frame.render_widget(clone_button, row[4]);
 }
 
 fn render_pairs(frame: &mut Frame, area: Rect, app: &DashboardApp) {
@@ -186,270 +220,191 @@ fn render_pairs(frame: &mut Frame, area: Rect, app: &DashboardApp) {
     frame.render_widget(Paragraph::new(count_line), layout[1]);
 }
 
-fn render_volatility(frame: &mut Frame, area: Rect, app: &DashboardApp) {
-    let focus = app.focus_field();
-    let block = Block::default().borders(Borders::ALL).title("Volatility");
-    let inner = block.inner(area);
-    frame.render_widget(block, area);
-
-    let layout = Layout::default()
-        .direction(Direction::Vertical)
-        .constraints([
-            Constraint::Length(1),
-            Constraint::Length(1),
-            Constraint::Length(1),
-            Constraint::Min(1),
-        ])
-        .split(inner);
-
-    frame.render_widget(Paragraph::new(""), layout[0]);
+fn render_custom_timeframes(frame: &mut Frame, area: Rect, app: &DashboardApp) {
+    let focus = matches!(app.focus_field(), SettingsField::CustomTimeframes);
+    let input = &app.settings_draft().custom_timeframes;
+    let filled = !input.trim().is_empty();
+    let display = if filled {
+        input.to_string()
+    } else {
+        "e.g. 7m,45m,90m,2d or 90m@13:30 for a session start (UTC)".to_string()
+    };
 
-    let mut toggle_style = if app.settings_draft().volatility_enabled {
-        Style::default().fg(FIELD_ACTIVE)
+    // Entries that don't parse (or name an exchange timeframe) are ignored; say so.
+    let invalid: Vec<&str> = input
+        .split(',')
+        .map(str::trim)
+        .filter(|label| !label.is_empty())
+        .filter(|label| !Timeframe::from_label(label).is_some_and(|tf| tf.is_custom()))
+        .collect();
+    let title = if invalid.is_empty() {
+        "Custom timeframes".to_string()
     } else {
-        Style::default().fg(FIELD_INACTIVE)
+        format!("Custom timeframes (ignored: {})", invalid.join(", "))
     };
-    if matches!(focus, SettingsField::VolatilityEnabled) {
-        toggle_style = toggle_style.add_modifier(Modifier::UNDERLINED);
-    }
-    let header_line = Line::from(vec![
-        Span::raw("Enabled "),
-        Span::styled(toggle_label(app.settings_draft().volatility_enabled), toggle_style),
-    ]);
-    frame.render_widget(Paragraph::new(header_line), layout[1]);
-    frame.render_widget(Paragraph::new(""), layout[2]);
-
-    let widths = [10, 12, 8];
-    let spacing = 0;
-    let table_width = widths.iter().sum::<u16>();
-    let separator_style = Style::default().fg(Color::DarkGray);
 
-    let header = Row::new(vec![
-        Cell::from(cell_text(
-            "Timeframe",
-            widths[0],
-            Style::default().fg(HEADER_COLOR).add_modifier(Modifier::BOLD),
-            separator_style,
-        )),
-        Cell::from(cell_text(
-            "Threshold",
-            widths[1],
-            Style::default().fg(HEADER_COLOR).add_modifier(Modifier::BOLD),
-            separator_style,
-        )),
-        Cell::from(cell_text(
-            "Active",
-            widths[2],
-            Style::default().fg(HEADER_COLOR).add_modifier(Modifier::BOLD),
-            separator_style,
-        )),
-    ])
-    .height(2);
+    let widget = Paragraph::new(display)
+        .style(field_style(filled, focus))
+        .block(Block::default().borders(Borders::ALL).title(title));
+    frame.render_widget(widget, area);
+}
 
-    let rows = ALL_TIMEFRAMES.iter().map(|tf| {
-        let entry = app
-            .settings_draft()
-            .volatility_timeframes
-            .get(tf)
-            .cloned()
-            .unwrap_or(VolatilityTimeframeSetting {
-                enabled: false,
-                threshold: 0.0,
-            });
-        let is_focus = matches!(focus, SettingsField::VolatilityTf(active) if active == *tf);
-        let mut value_style = if entry.enabled {
-            Style::default().fg(FIELD_ACTIVE)
-        } else {
-            Style::default().fg(FIELD_INACTIVE)
-        };
-        if is_focus {
-            value_style = value_style.add_modifier(Modifier::UNDERLINED);
-        }
-        let mut tf_style = if entry.enabled {
-            Style::default().fg(FIELD_ACTIVE)
-        } else {
-            Style::default().fg(FIELD_INACTIVE)
-        };
-        if is_focus {
-            tf_style = tf_style.add_modifier(Modifier::UNDERLINED);
-        }
-        if entry.enabled {
-            value_style = value_style.bg(ACTIVE_ROW_BG);
-            tf_style = tf_style.bg(ACTIVE_ROW_BG);
-        }
+fn render_indicators(frame: &mut Frame, area: Rect, app: &DashboardApp) {
+    let columns = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Length(24), Constraint::Min(1)])
+        .split(area);
+    render_indicator_list(frame, columns[0], app);
+    render_indicator(frame, columns[1], app);
+}
 
-        Row::new(vec![
-            Cell::from(cell_text(
-                tf_label(*tf),
-                widths[0],
-                tf_style,
-                separator_style,
-            )),
-            Cell::from(cell_text(
-                format!("{:.1}", entry.threshold),
-                widths[1],
-                value_style,
-                separator_style,
-            )),
-            Cell::from(cell_text(
-                if entry.enabled { "Yes" } else { "No" },
-                widths[2],
-                value_style,
-                separator_style,
-            )),
-        ])
-        .height(2)
-    });
-
-    let table = Table::new(
-        rows,
-        [
-            Constraint::Length(widths[0]),
-            Constraint::Length(widths[1]),
-            Constraint::Length(widths[2]),
-        ],
-    )
-    .header(header)
-    .column_spacing(spacing);
+fn render_indicator_list(frame: &mut Frame, area: Rect, app: &DashboardApp) {
+    let draft = app.settings_draft();
+    let focus = matches!(app.focus_field(), SettingsField::Indicators);
+    let items: Vec<ListItem> = REGISTRY
+        .iter()
+        .map(|spec| {
+            let enabled = draft.indicator(spec.id).enabled;
+            let style = if enabled {
+                Style::default().fg(FIELD_ACTIVE)
+            } else {
+                Style::default().fg(FIELD_INACTIVE)
+            };
+            ListItem::new(Line::from(vec![
+                Span::styled(format!("{:<6}", toggle_label(enabled)), style),
+                Span::raw(spec.title),
+            ]))
+        })
+        .collect();
 
-    let table_area = Layout::default()
-        .direction(Direction::Horizontal)
-        .constraints([Constraint::Length(table_width), Constraint::Min(0)])
-        .split(layout[3])[0];
-    frame.render_widget(table, table_area);
+    let mut state = ListState::default();
+    state.select(
+        REGISTRY
+            .iter()
+            .position(|spec| spec.id == app.selected_indicator()),
+    );
+
+    let highlight_style = if focus {
+        Style::default()
+            .bg(Color::DarkGray)
+            .add_modifier(Modifier::BOLD)
+    } else {
+        Style::default().bg(Color::Rgb(30, 30, 30))
+    };
+
+    let list = List::new(items)
+        .block(Block::default().borders(Borders::ALL).title("Indicators"))
+        .highlight_style(highlight_style);
+    frame.render_stateful_widget(list, area, &mut state);
 }
 
-fn render_rsi(frame: &mut Frame, area: Rect, app: &DashboardApp) {
+/// Parameters and timeframes of the selected indicator, laid out from its spec.
+fn render_indicator(frame: &mut Frame, area: Rect, app: &DashboardApp) {
     let focus = app.focus_field();
-    let block = Block::default().borders(Borders::ALL).title("RSI");
+    let draft = app.settings_draft();
+    let spec = app.selected_indicator().spec();
+    let saved = draft.indicator(spec.id);
+    let block = Block::default().borders(Borders::ALL).title(spec.title);
     let inner = block.inner(area);
     frame.render_widget(block, area);
 
+    let params_height = if spec.params.is_empty() { 0 } else { 3 };
     let layout = Layout::default()
         .direction(Direction::Vertical)
         .constraints([
             Constraint::Length(1),
-            Constraint::Length(1),
-            Constraint::Length(1),
-            Constraint::Length(3),
+            Constraint::Length(params_height),
             Constraint::Length(1),
             Constraint::Min(1),
         ])
         .split(inner);
 
-    frame.render_widget(Paragraph::new(""), layout[0]);
-
-    let mut toggle_style = if app.settings_draft().rsi_enabled {
-        Style::default().fg(FIELD_ACTIVE)
-    } else {
-        Style::default().fg(FIELD_INACTIVE)
+    let hint = match spec.threshold {
+        _ if spec.futures_only() && !draft.market.is_futures() => {
+            "Switch the market to USD-M Perpetuals to show this column".to_string()
+        }
+        _ if spec.futures_only() => "Refreshes every minute".to_string(),
+        ThresholdSpec::Band { lower, upper } => {
+            format!("Highlighted at or below {lower} and at or above {upper}")
+        }
+        ThresholdSpec::Magnitude { .. } => "Threshold 0 turns the highlight off".to_string(),
+        ThresholdSpec::Recency { .. } => {
+            "Highlights the bars up to the threshold after a cross; 0 turns it off".to_string()
+        }
+        ThresholdSpec::Plain => "Not highlighted; values don't compare across pairs".to_string(),
     };
-    if matches!(focus, SettingsField::RsiEnabled) {
-        toggle_style = toggle_style.add_modifier(Modifier::UNDERLINED);
-    }
-    let header_line = Line::from(vec![
-        Span::raw("Enabled "),
-        Span::styled(toggle_label(app.settings_draft().rsi_enabled), toggle_style),
-    ]);
-    frame.render_widget(Paragraph::new(header_line), layout[1]);
-    frame.render_widget(Paragraph::new(""), layout[2]);
+    frame.render_widget(
+        Paragraph::new(Span::styled(hint, Style::default().fg(FIELD_INACTIVE))),
+        layout[0],
+    );
 
+    let mut constraints = vec![Constraint::Length(16); spec.params.len()];
+    constraints.push(Constraint::Min(1));
     let fields = Layout::default()
         .direction(Direction::Horizontal)
-        .constraints([Constraint::Length(14), Constraint::Length(20), Constraint::Min(1)])
-        .split(layout[3]);
-
-    let length_focus = matches!(focus, SettingsField::RsiLength);
-    let length_style = field_style(true, length_focus);
-    let length_input = Paragraph::new(app.settings_draft().rsi_length.to_string())
-        .alignment(Alignment::Center)
-        .style(length_style)
-        .block(Block::default().borders(Borders::ALL).title("Length"));
-    frame.render_widget(length_input, fields[0]);
-
-    let source_focus = matches!(focus, SettingsField::RsiSource);
-    let source_style = field_style(true, source_focus);
-    let source_label = format!("{} v", kline_source_label(app.settings_draft().rsi_source));
-    let source_input = Paragraph::new(source_label)
-        .alignment(Alignment::Center)
-        .style(source_style)
-        .block(Block::default().borders(Borders::ALL).title("Source"));
-    frame.render_widget(source_input, fields[1]);
-    frame.render_widget(Paragraph::new(""), layout[4]);
+        .constraints(constraints)
+        .split(layout[1]);
+    for (index, param) in spec.params.iter().enumerate() {
+        let param_focus = focus == SettingsField::IndicatorParam(spec.id, index);
+        let input = Paragraph::new(param.display(saved.param(spec, index)))
+            .alignment(Alignment::Center)
+            .style(field_style(true, param_focus))
+            .block(Block::default().borders(Borders::ALL).title(param.label));
+        frame.render_widget(input, fields[index]);
+    }
 
-    let widths = [10, 8];
-    let spacing = 0;
+    let editable = spec.threshold.is_editable();
+    let widths: &[u16] = if editable { &[10, 12, 8] } else { &[10, 8] };
     let table_width = widths.iter().sum::<u16>();
     let separator_style = Style::default().fg(Color::DarkGray);
+    let header_style = Style::default()
+        .fg(HEADER_COLOR)
+        .add_modifier(Modifier::BOLD);
 
-    let header = Row::new(vec![
-        Cell::from(cell_text(
-            "Timeframe",
-            widths[0],
-            Style::default().fg(HEADER_COLOR).add_modifier(Modifier::BOLD),
-            separator_style,
-        )),
-        Cell::from(cell_text(
-            "Active",
-            widths[1],
-            Style::default().fg(HEADER_COLOR).add_modifier(Modifier::BOLD),
-            separator_style,
-        )),
-    ])
+    let mut header = vec!["Timeframe"];
+    if editable {
+        header.push("Threshold");
+    }
+    header.push("Active");
+    let header = Row::new(header.iter().zip(widths).map(|(label, width)| {
+        Cell::from(cell_text(*label, *width, header_style, separator_style))
+    }))
     .height(2);
 
-    let rows = ALL_TIMEFRAMES.iter().map(|tf| {
-        let enabled = *app.settings_draft().rsi_timeframes.get(tf).unwrap_or(&false);
-        let is_focus = matches!(focus, SettingsField::RsiTf(active) if active == *tf);
-        let mut value_style = if enabled {
-            Style::default().fg(FIELD_ACTIVE)
-        } else {
-            Style::default().fg(FIELD_INACTIVE)
-        };
-        if is_focus {
-            value_style = value_style.add_modifier(Modifier::UNDERLINED);
-        }
-        let mut tf_style = if enabled {
-            Style::default().fg(FIELD_ACTIVE)
-        } else {
-            Style::default().fg(FIELD_INACTIVE)
-        };
-        if is_focus {
-            tf_style = tf_style.add_modifier(Modifier::UNDERLINED);
-        }
-        if enabled {
-            value_style = value_style.bg(ACTIVE_ROW_BG);
-            tf_style = tf_style.bg(ACTIVE_ROW_BG);
-        }
-
-        Row::new(vec![
-            Cell::from(cell_text(
-                tf_label(*tf),
-                widths[0],
-                tf_style,
-                separator_style,
-            )),
-            Cell::from(cell_text(
-                if enabled { "Yes" } else { "No" },
-                widths[1],
-                value_style,
-                separator_style,
-            )),
-        ])
-        .height(2)
-    });
-
-    let table = Table::new(
-        rows,
-        [Constraint::Length(widths[0]), Constraint::Length(widths[1])],
-    )
-    .header(header)
-    .column_spacing(spacing);
+    let decimals = spec.threshold.decimals();
+    let timeframes = spec.timeframes(&draft.custom_timeframes());
+    let rows =
+        timeframes.iter().map(|tf| {
+            let entry = saved.timeframe(spec, *tf);
+            let is_focus = focus == SettingsField::IndicatorTf(spec.id, *tf);
+            let mut value_style = if entry.enabled {
+                Style::default().fg(FIELD_ACTIVE).bg(ACTIVE_ROW_BG)
+            } else {
+                Style::default().fg(FIELD_INACTIVE)
+            };
+            if is_focus {
+                value_style = value_style.add_modifier(Modifier::UNDERLINED);
+            }
+
+            let mut cells = vec![spec.column_label(*tf)];
+            if editable {
+                cells.push(format!("{:.*}", decimals, entry.threshold));
+            }
+            cells.push(if entry.enabled { "Yes" } else { "No" }.to_string());
+            Row::new(cells.into_iter().zip(widths).map(|(text, width)| {
+                Cell::from(cell_text(text, *width, value_style, separator_style))
+            }))
+            .height(2)
+        });
+
+    let table = Table::new(rows, widths.iter().map(|width| Constraint::Length(*width)))
+        .header(header)
+        .column_spacing(0);
 
     let table_area = Layout::default()
         .direction(Direction::Horizontal)
         .constraints([Constraint::Length(table_width), Constraint::Min(0)])
-        .split(layout[5])[0];
+        .split(layout[3])[0];
     frame.render_widget(table, table_area);
 }
 
@@ -515,7 +470,11 @@ fn render_clone_modal(frame: &mut Frame, app: &DashboardApp, area: Rect) {
 
     let buttons = Layout::default()
         .direction(Direction::Horizontal)
-        .constraints([Constraint::Length(10), Constraint::Length(12), Constraint::Min(1)])
+        .constraints([
+            Constraint::Length(10),
+            Constraint::Length(12),
+            Constraint::Min(1),
+        ])
         .split(layout[2]);
 
     let ok_focus = matches!(app.focus_field(), SettingsField::CloneConfirm);
@@ -39,12 +39,11 @@ pub fn run_tui(
         let timeout = tick_rate
             .checked_sub(last_tick.elapsed())
             .unwrap_or(Duration::from_millis(0));
-        if event::poll(timeout).map_err(|e| GlobalError::Other(e.to_string()))? {
-            if let Event::Key(key) = event::read().map_err(|e| GlobalError::Other(e.to_string()))? {
-                if key.kind == KeyEventKind::Press {
-                    app.handle_key(key);
-                }
-            }
+        if event::poll(timeout).map_err(|e| GlobalError::Other(e.to_string()))?
+            && let Event::Key(key) = event::read().map_err(|e| GlobalError::Other(e.to_string()))?
+            && key.kind == KeyEventKind::Press
+        {
+            app.handle_key(key);
         }
 
         if last_tick.elapsed() >= tick_rate {
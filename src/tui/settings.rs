@@ -5,87 +5,255 @@ use log::error;
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    tui::data::{IndicatorConfig, IndicatorKind},
-    types::{KlineSource, Timeframe, config::DEFAULT_RSI_LENGTH},
+    indicators::{IndicatorId, IndicatorSpec, REGISTRY, TimeframeSet},
+    tui::data::IndicatorConfig,
+    types::{KlineSource, Market, Timeframe, UtcOffset, config::FUNDING_TIMEFRAME},
 };
 
 pub const DEFAULT_PRESET_LABEL: &str = "Default";
 pub const PRESET_CONFIG_NAME: &str = "dashboard_presets";
 pub const MAX_PAIRS: usize = 200;
+pub const DEFAULT_WARMUP_CONCURRENCY: usize = 4;
+pub const MAX_WARMUP_CONCURRENCY: usize = 16;
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
-pub struct VolatilityTimeframeSetting {
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct TimeframeSetting {
     pub enabled: bool,
     pub threshold: f32,
 }
 
+/// Saved settings of one indicator, keyed by its registry id in the preset.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct IndicatorSettings {
+    pub enabled: bool,
+    /// Parameter values by key; missing ones take the spec default.
+    pub params: BTreeMap<String, f64>,
+    pub timeframes: BTreeMap<Timeframe, TimeframeSetting>,
+}
+
+/// Settings of an indicator missing from a preset.
+static DISABLED: IndicatorSettings = IndicatorSettings {
+    enabled: false,
+    params: BTreeMap::new(),
+    timeframes: BTreeMap::new(),
+};
+
+impl IndicatorSettings {
+    /// What a new preset starts with.
+    pub fn defaults(spec: &IndicatorSpec) -> Self {
+        let threshold = spec.threshold.default_value();
+        Self {
+            enabled: spec.default_enabled,
+            params: spec
+                .params
+                .iter()
+                .map(|param| (param.key.to_string(), param.default))
+                .collect(),
+            timeframes: spec
+                .timeframes(&[])
+                .into_iter()
+                .map(|tf| {
+                    let enabled = spec.default_timeframes.contains(&tf);
+                    (tf, TimeframeSetting { enabled, threshold })
+                })
+                .collect(),
+        }
+    }
+
+    /// Saved value of a parameter, or its default.
+    pub fn param(&self, spec: &IndicatorSpec, index: usize) -> f64 {
+        spec.params.get(index).map_or(0.0, |param| {
+            param.resolve(self.params.get(param.key).copied())
+        })
+    }
+
+    pub fn timeframe(&self, spec: &IndicatorSpec, tf: Timeframe) -> TimeframeSetting {
+        let mut setting = self
+            .timeframes
+            .get(&tf)
+            .cloned()
+            .unwrap_or(TimeframeSetting {
+                enabled: false,
+                threshold: spec.threshold.default_value(),
+            });
+        // A single column is on whenever its indicator is.
+        if matches!(spec.timeframes, TimeframeSet::Single(_)) {
+            setting.enabled = true;
+        }
+        setting
+    }
+
+    /// Enabled timeframes the indicator can run on; custom timeframes removed
+    /// from the input keep their toggles and are skipped.
+    pub fn enabled_timeframes(&self, spec: &IndicatorSpec, custom: &[Timeframe]) -> Vec<Timeframe> {
+        if let TimeframeSet::Single(tf) = spec.timeframes {
+            return vec![tf];
+        }
+        let available = spec.timeframes(custom);
+        self.timeframes
+            .iter()
+            .filter_map(|(tf, setting)| setting.enabled.then_some(*tf))
+            .filter(|tf| available.contains(tf))
+            .collect()
+    }
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 #[serde(default)]
 pub struct SettingsForm {
+    pub market: Market,
     pub pairs_input: String,
-    pub volatility_enabled: bool,
-    pub volatility_timeframes: BTreeMap<Timeframe, VolatilityTimeframeSetting>,
-    pub rsi_enabled: bool,
-    pub rsi_length: usize,
-    pub rsi_source: KlineSource,
-    pub rsi_timeframes: BTreeMap<Timeframe, bool>,
+    pub custom_timeframes: String,
+    /// Per indicator, keyed by registry id; unknown ids are kept as saved.
+    pub indicators: BTreeMap<String, IndicatorSettings>,
+    pub warmup_concurrency: usize,
+    /// Timezone whose midnight starts daily and longer bars.
+    pub utc_offset: UtcOffset,
+    pub pinned_pairs: Vec<String>,
     pub layout_column_spacing: u16,
     pub layout_table_count: u16,
     pub layout_table_spacing: u16,
+    /// Per-indicator fields of presets saved before the registry.
+    #[serde(flatten, skip_serializing)]
+    legacy: LegacyIndicatorSettings,
 }
 
 impl Default for SettingsForm {
     fn default() -> Self {
-        let volatility_timeframes = default_volatility_timeframes();
-        let rsi_timeframes = default_timeframe_toggles(&[Timeframe::M5, Timeframe::M15]);
         Self {
+            market: Market::Spot,
             pairs_input: "BTCUSDT,ETHUSDT".to_string(),
-            volatility_enabled: true,
-            volatility_timeframes,
-            rsi_enabled: true,
-            rsi_length: DEFAULT_RSI_LENGTH,
-            rsi_source: KlineSource::Close,
-            rsi_timeframes,
+            custom_timeframes: String::new(),
+            indicators: REGISTRY
+                .iter()
+                .map(|spec| {
+                    let key = spec.id.as_str().to_string();
+                    (key, IndicatorSettings::defaults(spec))
+                })
+                .collect(),
+            warmup_concurrency: DEFAULT_WARMUP_CONCURRENCY,
+            utc_offset: UtcOffset::UTC,
+            pinned_pairs: Vec::new(),
             layout_column_spacing: 0,
             layout_table_count: 1,
             layout_table_spacing: 2,
+            legacy: LegacyIndicatorSettings::default(),
         }
     }
 }
 
 impl SettingsForm {
+    pub fn indicator(&self, id: IndicatorId) -> &IndicatorSettings {
+        self.indicators.get(id.as_str()).unwrap_or(&DISABLED)
+    }
+
+    pub fn indicator_mut(&mut self, id: IndicatorId) -> &mut IndicatorSettings {
+        self.indicators
+            .entry(id.as_str().to_string())
+            .or_insert_with(|| IndicatorSettings {
+                enabled: false,
+                ..IndicatorSettings::defaults(id.spec())
+            })
+    }
+
     pub fn indicator_config(&self) -> Vec<IndicatorConfig> {
-        let volatility_timeframes: Vec<Timeframe> = self
-            .volatility_timeframes
+        let custom = self.custom_timeframes();
+        let futures = self.market.is_futures();
+        REGISTRY
             .iter()
-            .filter_map(|(tf, setting)| setting.enabled.then_some(*tf))
-            .collect();
-        let volatility_thresholds: BTreeMap<Timeframe, f32> = self
-            .volatility_timeframes
-            .iter()
-            .filter_map(|(tf, setting)| setting.enabled.then_some((*tf, setting.threshold.abs())))
-            .collect();
+            .map(|spec| {
+                let saved = self.indicator(spec.id);
+                let timeframes = saved.enabled_timeframes(spec, &custom);
+                let thresholds = timeframes
+                    .iter()
+                    .map(|&tf| (tf, saved.timeframe(spec, tf).threshold.abs()))
+                    .collect();
+                IndicatorConfig {
+                    id: spec.id,
+                    enabled: saved.enabled && (futures || !spec.futures_only()),
+                    timeframes,
+                    thresholds,
+                }
+            })
+            .collect()
+    }
 
-        let rsi_timeframes: Vec<Timeframe> = self
-            .rsi_timeframes
-            .iter()
-            .filter_map(|(tf, enabled)| (*enabled).then_some(*tf))
-            .collect();
-
-        vec![
-            IndicatorConfig {
-                kind: IndicatorKind::Volatility,
-                enabled: self.volatility_enabled,
-                timeframes: volatility_timeframes,
-                thresholds: volatility_thresholds,
-            },
-            IndicatorConfig {
-                kind: IndicatorKind::Rsi,
-                enabled: self.rsi_enabled,
-                timeframes: rsi_timeframes,
-                thresholds: BTreeMap::new(),
-            },
-        ]
+    /// Move settings saved in the old per-indicator fields into `indicators`.
+    fn migrate_legacy(&mut self) {
+        let legacy = std::mem::take(&mut self.legacy);
+
+        if let Some(enabled) = legacy.volatility_enabled {
+            self.indicator_mut(IndicatorId::VOLATILITY).enabled = enabled;
+        }
+        if let Some(timeframes) = legacy.volatility_timeframes {
+            self.indicator_mut(IndicatorId::VOLATILITY).timeframes = timeframes;
+        }
+
+        let rsi = self.indicator_mut(IndicatorId::RSI);
+        if let Some(enabled) = legacy.rsi_enabled {
+            rsi.enabled = enabled;
+        }
+        let params = [
+            ("length", legacy.rsi_length.map(|length| length as f64)),
+            ("seed", legacy.rsi_seed.map(|seed| seed as f64)),
+            (
+                "source",
+                legacy.rsi_source.map(|source| source as u8 as f64),
+            ),
+        ];
+        for (key, value) in params {
+            if let Some(value) = value {
+                rsi.params.insert(key.to_string(), value);
+            }
+        }
+        if let Some(timeframes) = legacy.rsi_timeframes {
+            rsi.timeframes = timeframes
+                .into_iter()
+                .map(|(tf, enabled)| {
+                    let threshold = 0.0;
+                    (tf, TimeframeSetting { enabled, threshold })
+                })
+                .collect();
+        }
+
+        let funding = self.indicator_mut(IndicatorId::FUNDING);
+        if let Some(enabled) = legacy.funding_enabled {
+            funding.enabled = enabled;
+        }
+        if let Some(threshold) = legacy.funding_threshold {
+            funding.timeframes.insert(
+                FUNDING_TIMEFRAME,
+                TimeframeSetting {
+                    enabled: true,
+                    threshold,
+                },
+            );
+        }
+
+        if let Some(enabled) = legacy.open_interest_enabled {
+            self.indicator_mut(IndicatorId::OPEN_INTEREST).enabled = enabled;
+        }
+        if let Some(timeframes) = legacy.open_interest_timeframes {
+            self.indicator_mut(IndicatorId::OPEN_INTEREST).timeframes = timeframes;
+        }
+    }
+
+    /// Valid custom timeframes from the comma separated input, in input order.
+    /// Labels naming an exchange timeframe (`60m` is `1h`) are skipped.
+    pub fn custom_timeframes(&self) -> Vec<Timeframe> {
+        let mut timeframes = Vec::new();
+        for tf in self
+            .custom_timeframes
+            .split(',')
+            .filter_map(|label| Timeframe::from_label(label.trim()))
+            .filter(Timeframe::is_custom)
+        {
+            if !timeframes.contains(&tf) {
+                timeframes.push(tf);
+            }
+        }
+        timeframes
     }
 
     pub fn pairs(&self) -> Vec<String> {
@@ -100,6 +268,22 @@ impl SettingsForm {
     }
 }
 
+/// Indicator fields as presets stored them before the registry.
+#[derive(Clone, Debug, Default, Deserialize)]
+struct LegacyIndicatorSettings {
+    volatility_enabled: Option<bool>,
+    volatility_timeframes: Option<BTreeMap<Timeframe, TimeframeSetting>>,
+    rsi_enabled: Option<bool>,
+    rsi_length: Option<usize>,
+    rsi_seed: Option<usize>,
+    rsi_source: Option<KlineSource>,
+    rsi_timeframes: Option<BTreeMap<Timeframe, bool>>,
+    funding_enabled: Option<bool>,
+    funding_threshold: Option<f32>,
+    open_interest_enabled: Option<bool>,
+    open_interest_timeframes: Option<BTreeMap<Timeframe, TimeframeSetting>>,
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct StoredPreset {
     pub settings: SettingsForm,
@@ -150,6 +334,9 @@ impl PresetStore {
             .or_insert(StoredPreset {
                 settings: SettingsForm::default(),
             });
+        for preset in self.presets.values_mut() {
+            preset.settings.migrate_legacy();
+        }
 
         self
     }
@@ -170,38 +357,31 @@ impl PresetStore {
     }
 }
 
-pub fn default_volatility_timeframes() -> BTreeMap<Timeframe, VolatilityTimeframeSetting> {
-    ALL_TIMEFRAMES
-        .iter()
-        .map(|&tf| {
-            let enabled = matches!(
-                tf,
-                Timeframe::M15 | Timeframe::H1 | Timeframe::H4 | Timeframe::D1
-            );
-            (
-                tf,
-                VolatilityTimeframeSetting {
-                    enabled,
-                    threshold: 0.0,
-                },
-            )
-        })
-        .collect()
-}
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-pub fn default_timeframe_toggles(enabled: &[Timeframe]) -> BTreeMap<Timeframe, bool> {
-    ALL_TIMEFRAMES
-        .iter()
-        .map(|&tf| (tf, enabled.contains(&tf)))
-        .collect()
-}
+    #[test]
+    fn legacy_indicator_fields_migrate_into_the_registry_map() {
+        let mut json = serde_json::to_value(SettingsForm::default()).unwrap();
+        let form = json.as_object_mut().unwrap();
+        form.remove("indicators");
+        form.insert("rsi_length".into(), 21.into());
+        form.insert("rsi_timeframes".into(), serde_json::json!({ "1h": true }));
+        form.insert("funding_enabled".into(), false.into());
+        form.insert("funding_threshold".into(), 0.1.into());
 
-pub const ALL_TIMEFRAMES: [Timeframe; 7] = [
-    Timeframe::M1,
-    Timeframe::M5,
-    Timeframe::M15,
-    Timeframe::M30,
-    Timeframe::H1,
-    Timeframe::H4,
-    Timeframe::D1,
-];
+        let mut settings: SettingsForm = serde_json::from_value(json).unwrap();
+        settings.migrate_legacy();
+
+        let rsi = settings.indicator(IndicatorId::RSI);
+        assert_eq!(rsi.params.get("length"), Some(&21.0));
+        assert!(
+            rsi.timeframe(IndicatorId::RSI.spec(), Timeframe::H1)
+                .enabled
+        );
+        let funding = settings.indicator(IndicatorId::FUNDING);
+        assert!(!funding.enabled);
+        assert!((funding.timeframes[&FUNDING_TIMEFRAME].threshold - 0.1).abs() < 1e-6);
+    }
+}
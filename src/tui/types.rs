@@ -1,4 +1,4 @@
-use crate::types::Timeframe;
+use crate::{indicators::IndicatorId, types::Timeframe};
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum ViewMode {
@@ -13,12 +13,14 @@ pub enum SettingsField {
     ActivatePreset,
     ClonePreset,
     PairsInput,
-    VolatilityEnabled,
-    VolatilityTf(Timeframe),
-    RsiEnabled,
-    RsiLength,
-    RsiSource,
-    RsiTf(Timeframe),
+    CustomTimeframes,
+    Market,
+    WarmupConcurrency,
+    UtcOffset,
+    /// Indicator list; picks the indicator the fields below edit.
+    Indicators,
+    IndicatorParam(IndicatorId, usize),
+    IndicatorTf(IndicatorId, Timeframe),
     CloneName,
     CloneConfirm,
     CloneCancel,
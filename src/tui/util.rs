@@ -6,20 +6,21 @@ use ratatui::{
 };
 
 use crate::{
-    message_bus::{IndicatorThresholds, IndicatorValue},
+    indicators::{
+        Direction, IndicatorId, IndicatorSpec, REGISTRY, Reading, Signal, Threshold, ThresholdSpec,
+    },
     tui::{
-        data::{
-            DashboardData, DashboardDataBuilder, IndicatorConfig, IndicatorKind, IndicatorState,
-        },
-        settings::{ALL_TIMEFRAMES, MAX_PAIRS, SettingsForm, VolatilityTimeframeSetting},
+        data::{DashboardData, DashboardDataBuilder, IndicatorConfig, IndicatorState},
+        settings::{MAX_PAIRS, SettingsForm},
     },
-    types::{KlineSource, Timeframe, config},
+    types::{Timeframe, config},
 };
 
 pub const POSITIVE_TEXT: Color = Color::Rgb(64, 199, 122);
 pub const NEGATIVE_TEXT: Color = Color::Rgb(230, 82, 82);
 pub const POSITIVE_BG: Color = Color::Rgb(33, 178, 125);
 pub const NEGATIVE_BG: Color = Color::Rgb(186, 64, 117);
+pub const SQUEEZE_BG: Color = Color::Rgb(222, 178, 62);
 pub const HEADER_COLOR: Color = Color::Rgb(90, 200, 255);
 pub const PAIR_COLOR: Color = Color::Rgb(200, 200, 200);
 pub const INDICATOR_GROUP_BG: Color = Color::Rgb(90, 200, 255);
@@ -33,77 +34,71 @@ pub fn active_indicators<'a>(
 ) -> Vec<&'a IndicatorConfig> {
     config
         .iter()
-        .filter(|cfg| *state.get(&cfg.kind).unwrap_or(&cfg.enabled))
+        .filter(|cfg| *state.get(&cfg.id).unwrap_or(&cfg.enabled))
         .collect()
 }
 
+/// Value and label of a cell; `None` until the engine sent one.
 pub fn lookup_value<'a>(
     pair: &str,
-    kind: IndicatorKind,
+    id: IndicatorId,
     timeframe: Timeframe,
     index_lookup: &config::IndexLookup,
-    indicator_values: &[f32],
+    indicator_values: &[Reading],
     indicator_labels: &'a [String],
-) -> (f32, Option<&'a str>) {
-    let key = match kind {
-        IndicatorKind::Volatility => config::IndicatorKey::Volatility,
-        IndicatorKind::Rsi => config::IndicatorKey::Rsi,
-    };
-
-    if let Some(idx) = index_lookup.index(pair, key, timeframe) {
-        let value = indicator_values.get(idx).copied().unwrap_or(0.0);
-        let display = indicator_labels.get(idx).map(|s| s.as_str());
+) -> (Reading, Option<&'a str>) {
+    if let Some(idx) = index_lookup.index(pair, id, timeframe) {
+        let value = indicator_values.get(idx).copied().unwrap_or_default();
+        let display = indicator_labels
+            .get(idx)
+            .map(|s| s.as_str())
+            .filter(|s| !s.is_empty());
         (value, display)
     } else {
-        (0.0, None)
+        (Reading::default(), None)
     }
 }
 
-pub fn value_style(value: &IndicatorValue, thresholds: Option<&IndicatorThresholds>) -> Style {
-    match value {
-        IndicatorValue::Volatility(v) => {
-            let mut background = None;
-            if let Some(IndicatorThresholds::Volatility { threshold }) = thresholds {
-                if *threshold > 0.0 && v.abs() >= *threshold {
-                    background = Some(if *v >= 0.0 { POSITIVE_BG } else { NEGATIVE_BG });
-                }
-            }
-
-            let mut style = Style::default();
-            if let Some(bg) = background {
-                style = style.bg(bg).fg(Color::Black);
-            } else if *v >= 0.0 {
-                style = style.fg(POSITIVE_TEXT);
+/// Cell style from the indicator's threshold semantics.
+pub fn value_style(spec: &IndicatorSpec, reading: Reading, threshold: Option<&Threshold>) -> Style {
+    let value = reading.value;
+    let background = match threshold {
+        // Signals are highlighted whatever the threshold.
+        _ if reading.signal.is_some() => reading.signal.map(|signal| match signal {
+            Signal::Cross(Direction::Up) => POSITIVE_BG,
+            Signal::Cross(Direction::Down) => NEGATIVE_BG,
+            Signal::Squeeze => SQUEEZE_BG,
+        }),
+        Some(Threshold::Magnitude(threshold)) if *threshold > 0.0 && value.abs() >= *threshold => {
+            Some(if value >= 0.0 {
+                POSITIVE_BG
             } else {
-                style = style.fg(NEGATIVE_TEXT);
-            }
-            style
+                NEGATIVE_BG
+            })
         }
-        IndicatorValue::Rsi(v) => {
-            let mut background = None;
-            if let Some(IndicatorThresholds::Rsi {
-                oversold,
-                overbought,
-            }) = thresholds
-            {
-                if v <= oversold {
-                    background = Some(NEGATIVE_BG);
-                } else if v >= overbought {
-                    background = Some(POSITIVE_BG);
-                }
-            }
-
-            let mut style = Style::default();
-            if let Some(bg) = background {
-                style = style.bg(bg).fg(Color::Black);
-            } else if *v >= 50.0 {
-                style = style.fg(POSITIVE_TEXT);
+        Some(Threshold::Band { lower, .. }) if value <= *lower => Some(NEGATIVE_BG),
+        Some(Threshold::Band { upper, .. }) if value >= *upper => Some(POSITIVE_BG),
+        Some(Threshold::Recency(bars)) if *bars > 0.0 && value.abs() <= *bars => {
+            Some(if value >= 0.0 {
+                POSITIVE_BG
             } else {
-                style = style.fg(NEGATIVE_TEXT);
-            }
-            style
+                NEGATIVE_BG
+            })
         }
+        _ => None,
+    };
+
+    let mut style = Style::default();
+    if let Some(bg) = background {
+        style = style.bg(bg).fg(Color::Black);
+    } else if matches!(spec.threshold, ThresholdSpec::Plain) {
+        // Plain values keep the default text color.
+    } else if value >= spec.threshold.midpoint() {
+        style = style.fg(POSITIVE_TEXT);
+    } else {
+        style = style.fg(NEGATIVE_TEXT);
     }
+    style
 }
 
 pub fn toggle_label(enabled: bool) -> &'static str {
@@ -147,40 +142,17 @@ pub fn tf_label(tf: Timeframe) -> String {
     tf.to_string().to_owned()
 }
 
-pub fn kline_source_label(source: KlineSource) -> &'static str {
-    match source {
-        KlineSource::Open => "Open",
-        KlineSource::High => "High",
-        KlineSource::Low => "Low",
-        KlineSource::Close => "Close",
-    }
-}
-
 pub fn indicator_thresholds_from_settings(
     settings: &SettingsForm,
-) -> BTreeMap<(IndicatorKind, Timeframe), IndicatorThresholds> {
+) -> BTreeMap<(IndicatorId, Timeframe), Threshold> {
+    let custom = settings.custom_timeframes();
     let mut thresholds = BTreeMap::new();
 
-    for (&tf, cfg) in &settings.volatility_timeframes {
-        if cfg.enabled {
-            thresholds.insert(
-                (IndicatorKind::Volatility, tf),
-                IndicatorThresholds::Volatility {
-                    threshold: cfg.threshold.abs(),
-                },
-            );
-        }
-    }
-
-    for (&tf, enabled) in &settings.rsi_timeframes {
-        if *enabled {
-            thresholds.insert(
-                (IndicatorKind::Rsi, tf),
-                IndicatorThresholds::Rsi {
-                    oversold: 30.0,
-                    overbought: 70.0,
-                },
-            );
+    for spec in REGISTRY {
+        let saved = settings.indicator(spec.id);
+        for tf in saved.enabled_timeframes(spec, &custom) {
+            let threshold = saved.timeframe(spec, tf).threshold;
+            thresholds.insert((spec.id, tf), spec.threshold.resolve(threshold));
         }
     }
 
@@ -1,92 +1,54 @@
-use std::collections::HashMap;
+use std::{
+    collections::HashMap,
+    sync::atomic::{AtomicU64, Ordering},
+};
 
 use crate::{
     error::{ConfigError, Result},
+    indicators::{IndicatorId, IndicatorSpec, Params, REGISTRY},
     tui::settings::SettingsForm,
-    types::{KlineSource, Timeframe},
+    types::{Market, Timeframe, UtcOffset},
 };
 
-pub const DEFAULT_RSI_LENGTH: usize = 14;
-
-const TIMEFRAME_COUNT: usize = 7;
-
-#[repr(u8)]
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
-pub enum IndicatorKey {
-    Volatility = 0,
-    Rsi = 1,
-}
-
-impl IndicatorKey {
-    pub const COUNT: usize = 2;
-
-    pub const fn as_index(self) -> usize {
-        self as usize
-    }
-}
+/// Funding has no timeframe of its own; its single column lives in this slot.
+pub const FUNDING_TIMEFRAME: Timeframe = Timeframe::M1;
 
 #[derive(Debug, Clone)]
 pub struct IndexLookup {
     pair_to_id: HashMap<String, u16>,
-    // slot_offsets[indicator][timeframe] -> slot within a pair
-    slot_offsets: [[Option<usize>; TIMEFRAME_COUNT]; IndicatorKey::COUNT],
+    // (indicator, timeframe) -> slot within a pair; custom timeframes make this open-ended.
+    slot_offsets: HashMap<(IndicatorId, Timeframe), usize>,
     pair_stride: usize,
 }
 
 impl IndexLookup {
-    pub fn new(
-        pairs: &[String],
-        volatility_enabled: bool,
-        volatility_timeframes: &[Timeframe],
-        rsi_enabled: bool,
-        rsi_timeframes: &[Timeframe],
-    ) -> Self {
+    /// Build slots for each enabled indicator group, in the order given.
+    pub fn new(pairs: &[String], groups: &[(IndicatorId, &[Timeframe])]) -> Self {
         let mut pair_to_id = HashMap::new();
         for (idx, pair) in pairs.iter().enumerate() {
             // Reuse the first id if the same pair appears multiple times.
             pair_to_id.entry(pair.clone()).or_insert(idx as u16);
         }
 
-        let mut slot_offsets = [[None; TIMEFRAME_COUNT]; IndicatorKey::COUNT];
-        let mut next_slot = 0usize;
-
-        if volatility_enabled {
-            for &tf in volatility_timeframes {
-                let tf_idx = timeframe_index(tf);
-                if slot_offsets[IndicatorKey::Volatility.as_index()][tf_idx].is_none() {
-                    slot_offsets[IndicatorKey::Volatility.as_index()][tf_idx] = Some(next_slot);
-                    next_slot += 1;
-                }
-            }
-        }
-
-        if rsi_enabled {
-            for &tf in rsi_timeframes {
-                let tf_idx = timeframe_index(tf);
-                if slot_offsets[IndicatorKey::Rsi.as_index()][tf_idx].is_none() {
-                    slot_offsets[IndicatorKey::Rsi.as_index()][tf_idx] = Some(next_slot);
-                    next_slot += 1;
-                }
+        let mut slot_offsets = HashMap::new();
+        for (indicator, timeframes) in groups {
+            for &tf in timeframes.iter() {
+                let next_slot = slot_offsets.len();
+                slot_offsets.entry((*indicator, tf)).or_insert(next_slot);
             }
         }
 
         Self {
             pair_to_id,
+            pair_stride: slot_offsets.len(),
             slot_offsets,
-            pair_stride: next_slot,
         }
     }
 
     #[inline]
-    pub fn index(
-        &self,
-        pair: &str,
-        indicator: IndicatorKey,
-        timeframe: Timeframe,
-    ) -> Option<usize> {
+    pub fn index(&self, pair: &str, indicator: IndicatorId, timeframe: Timeframe) -> Option<usize> {
         let pair_id = *self.pair_to_id.get(pair)? as usize;
-        let tf_idx = timeframe_index(timeframe);
-        let slot = self.slot_offsets[indicator.as_index()][tf_idx]?;
+        let slot = *self.slot_offsets.get(&(indicator, timeframe))?;
         Some(pair_id * self.pair_stride + slot)
     }
 
@@ -99,131 +61,120 @@ impl IndexLookup {
     }
 }
 
+/// Counts the configs built, so services can tell data made for an older one.
+static GENERATION: AtomicU64 = AtomicU64::new(0);
+
 #[derive(Debug, Clone)]
 pub struct AppConfig {
+    generation: u64,
+    market: Market,
     pairs: Vec<String>,
-    indicators: IndicatorConfig,
+    indicators: Vec<IndicatorSetup>,
     index_lookup: IndexLookup,
+    warmup_concurrency: usize,
+    utc_offset: UtcOffset,
+    pinned_pairs: Vec<String>,
 }
 
 impl AppConfig {
     pub fn from_settings(settings: &SettingsForm) -> Self {
-        let volatility_timeframes: Vec<Timeframe> = settings
-            .volatility_timeframes
+        let custom = settings.custom_timeframes();
+        // Perpetual metrics only exist on the futures market.
+        let futures = settings.market.is_futures();
+        let indicators: Vec<IndicatorSetup> = REGISTRY
             .iter()
-            .filter_map(|(tf, cfg)| cfg.enabled.then_some(*tf))
+            .filter(|spec| futures || !spec.futures_only())
+            .filter_map(|spec| {
+                let saved = settings.indicator(spec.id);
+                saved.enabled.then(|| IndicatorSetup {
+                    spec,
+                    params: spec.params(&saved.params),
+                    timeframes: saved.enabled_timeframes(spec, &custom),
+                })
+            })
             .collect();
 
-        let rsi_timeframes: Vec<Timeframe> = settings
-            .rsi_timeframes
+        let groups: Vec<(IndicatorId, &[Timeframe])> = indicators
             .iter()
-            .filter_map(|(tf, enabled)| (*enabled).then_some(*tf))
+            .map(|setup| (setup.id(), setup.timeframes()))
             .collect();
-
         let pairs = settings.pairs();
-        let index_lookup = IndexLookup::new(
-            &pairs,
-            settings.volatility_enabled,
-            &volatility_timeframes,
-            settings.rsi_enabled,
-            &rsi_timeframes,
-        );
+        let index_lookup = IndexLookup::new(&pairs, &groups);
 
         Self {
+            generation: GENERATION.fetch_add(1, Ordering::Relaxed) + 1,
+            market: settings.market,
             pairs,
-            indicators: IndicatorConfig {
-                volatility: VolatilityConfig {
-                    enabled: settings.volatility_enabled,
-                    timeframes: volatility_timeframes,
-                },
-                rsi: RsiConfig {
-                    enabled: settings.rsi_enabled,
-                    length: settings.rsi_length,
-                    source: settings.rsi_source,
-                    timeframes: rsi_timeframes,
-                },
-            },
+            indicators,
             index_lookup,
+            warmup_concurrency: settings.warmup_concurrency.max(1),
+            // Presets edited by hand may hold offsets Binance rejects.
+            utc_offset: UtcOffset::from_minutes(settings.utc_offset.minutes().into()),
+            pinned_pairs: settings.pinned_pairs.clone(),
         }
     }
 
-    pub fn pairs(&self) -> &[String] {
-        &self.pairs
+    /// Distinguishes this config from every other one built in this process.
+    pub fn generation(&self) -> u64 {
+        self.generation
     }
 
-    pub fn indicators(&self) -> &IndicatorConfig {
-        &self.indicators
+    pub fn market(&self) -> Market {
+        self.market
     }
 
-    pub fn index_lookup(&self) -> &IndexLookup {
-        &self.index_lookup
+    pub fn pairs(&self) -> &[String] {
+        &self.pairs
     }
-}
 
-#[derive(Debug, Clone)]
-pub struct IndicatorConfig {
-    volatility: VolatilityConfig,
-    rsi: RsiConfig,
-}
-
-impl IndicatorConfig {
-    pub fn volatility(&self) -> &VolatilityConfig {
-        &self.volatility
+    /// Enabled indicators, in registry order.
+    pub fn indicators(&self) -> &[IndicatorSetup] {
+        &self.indicators
     }
 
-    pub fn rsi(&self) -> &RsiConfig {
-        &self.rsi
+    /// `id`'s setup, if it is enabled.
+    pub fn indicator(&self, id: IndicatorId) -> Option<&IndicatorSetup> {
+        self.indicators.iter().find(|setup| setup.id() == id)
     }
-}
 
-#[derive(Debug, Clone)]
-pub struct VolatilityConfig {
-    enabled: bool,
-    timeframes: Vec<Timeframe>,
-}
+    pub fn index_lookup(&self) -> &IndexLookup {
+        &self.index_lookup
+    }
 
-impl VolatilityConfig {
-    pub fn enabled(&self) -> bool {
-        self.enabled
+    /// Maximum number of pairs warming up at the same time.
+    pub fn warmup_concurrency(&self) -> usize {
+        self.warmup_concurrency
     }
 
-    pub fn timeframes(&self) -> &[Timeframe] {
-        &self.timeframes
+    /// Day-start offset the bars of this preset align to.
+    pub fn utc_offset(&self) -> UtcOffset {
+        self.utc_offset
     }
-}
 
-const fn timeframe_index(tf: Timeframe) -> usize {
-    match tf {
-        Timeframe::M1 => 0,
-        Timeframe::M5 => 1,
-        Timeframe::M15 => 2,
-        Timeframe::M30 => 3,
-        Timeframe::H1 => 4,
-        Timeframe::H4 => 5,
-        Timeframe::D1 => 6,
+    pub fn pinned_pairs(&self) -> &[String] {
+        &self.pinned_pairs
     }
 }
 
+/// An enabled indicator with its resolved parameters and timeframes.
 #[derive(Debug, Clone)]
-pub struct RsiConfig {
-    enabled: bool,
-    length: usize,
-    source: KlineSource,
-    // RSI shares the windowed 1m buffer; indicator is calculated per timeframe listed here.
+pub struct IndicatorSetup {
+    spec: &'static IndicatorSpec,
+    params: Params,
     timeframes: Vec<Timeframe>,
 }
 
-impl RsiConfig {
-    pub fn enabled(&self) -> bool {
-        self.enabled
+impl IndicatorSetup {
+    pub fn id(&self) -> IndicatorId {
+        self.spec.id
     }
 
-    pub fn length(&self) -> usize {
-        self.length
+    pub fn spec(&self) -> &'static IndicatorSpec {
+        self.spec
     }
 
-    pub fn source(&self) -> KlineSource {
-        self.source
+    pub fn params(&self) -> &Params {
+        &self.params
     }
 
     pub fn timeframes(&self) -> &[Timeframe] {
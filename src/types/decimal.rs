@@ -0,0 +1,129 @@
+//! Exact prices and volumes.
+//!
+//! Binance quotes both as decimal strings. Keeping them as fixed-point decimals
+//! from parse to display avoids float rounding, which put truncated values a
+//! tick off on low-priced tokens.
+
+use std::{
+    fmt,
+    ops::{Add, Sub},
+    str::FromStr,
+};
+
+use rust_decimal::{Decimal, prelude::ToPrimitive};
+use serde::{Deserialize, Serialize};
+
+/// Declares a decimal newtype with parsing, display and the arithmetic bars need.
+macro_rules! decimal_newtype {
+    ($($(#[$meta:meta])* $name:ident;)+) => {$(
+        $(#[$meta])*
+        #[derive(
+            Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize,
+            Deserialize,
+        )]
+        #[serde(transparent)]
+        pub struct $name(pub Decimal);
+
+        impl $name {
+            pub const ZERO: $name = $name(Decimal::ZERO);
+
+            /// Nearest decimal to `value`, for fixtures and derived values.
+            pub fn from_f64(value: f64) -> Self {
+                Self(Decimal::from_f64_retain(value).unwrap_or_default().normalize())
+            }
+
+            /// Lossy; for math that is floating point anyway.
+            pub fn to_f64(self) -> f64 {
+                self.0.to_f64().unwrap_or_default()
+            }
+
+            pub fn is_zero(self) -> bool {
+                self.0.is_zero()
+            }
+        }
+
+        impl FromStr for $name {
+            type Err = rust_decimal::Error;
+
+            /// Parses the exchange string as is, keeping its scale (`"0.10"`
+            /// displays as `0.10`).
+            fn from_str(raw: &str) -> Result<Self, Self::Err> {
+                Decimal::from_str_exact(raw).map($name)
+            }
+        }
+
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                self.0.fmt(f)
+            }
+        }
+
+        impl Add for $name {
+            type Output = $name;
+
+            fn add(self, rhs: $name) -> $name {
+                $name(self.0 + rhs.0)
+            }
+        }
+
+        impl Sub for $name {
+            type Output = $name;
+
+            fn sub(self, rhs: $name) -> $name {
+                $name(self.0 - rhs.0)
+            }
+        }
+    )+};
+}
+
+decimal_newtype! {
+    /// Price in the quote asset.
+    Price;
+    /// Amount of the base or quote asset.
+    Volume;
+}
+
+impl Price {
+    /// `(self - base) / base` in percent, truncated toward zero to `decimals`
+    /// places. `None` when `base` is zero.
+    pub fn percent_change(self, base: Price, decimals: u32) -> Option<Decimal> {
+        let change = (self.0 - base.0).checked_div(base.0)?;
+        Some((change * Decimal::ONE_HUNDRED).trunc_with_scale(decimals))
+    }
+}
+
+impl Volume {
+    /// Share of `total` this volume makes up; `None` without volume.
+    pub fn share_of(self, total: Volume) -> Option<f64> {
+        (!total.is_zero()).then(|| (self.0 / total.0).to_f64().unwrap_or_default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Price, Volume};
+
+    #[test]
+    fn keeps_exchange_precision() {
+        let low: Price = "0.00000125".parse().unwrap();
+        let high: Price = "0.00000126".parse().unwrap();
+        assert_eq!(low.to_string(), "0.00000125");
+        assert_eq!((high - low).to_string(), "0.00000001");
+
+        // Exactly 0.8%; the same math in f64 truncates to 0.7.
+        assert_eq!(high.percent_change(low, 1).unwrap().to_string(), "0.8");
+        assert_eq!(low.percent_change(high, 2).unwrap().to_string(), "-0.79");
+        assert_eq!(high.percent_change(Price::ZERO, 1), None);
+
+        let volume: Volume = "12.50".parse().unwrap();
+        let taker: Volume = "7.5".parse().unwrap();
+        assert_eq!((volume + taker).to_string(), "20.00");
+        assert_eq!(taker.share_of(volume), Some(0.6));
+        assert!("1e-3".parse::<Price>().is_err());
+
+        // Serialized as strings; caches written with floats still load.
+        assert_eq!(serde_json::to_string(&low).unwrap(), r#""0.00000125""#);
+        let cached: Price = serde_json::from_str("42283.58").unwrap();
+        assert_eq!(cached.to_string(), "42283.58");
+    }
+}
@@ -8,33 +8,82 @@ pub enum KlineSource {
     Close,
 }
 
-#[derive(Clone, Debug, Eq, PartialEq, Hash)]
-pub struct Pair(pub String);
+/// Binance market the grid streams from.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub enum Market {
+    #[default]
+    Spot,
+    UsdmFutures,
+}
 
-#[derive(Clone, Copy, Debug, Default, PartialEq)]
-pub struct Price(pub f64);
+impl Market {
+    pub const fn label(&self) -> &'static str {
+        match self {
+            Market::Spot => "Spot",
+            Market::UsdmFutures => "USD-M Perpetuals",
+        }
+    }
 
-#[derive(Clone, Copy, Debug, Default, PartialEq)]
-pub struct Volume(pub f64);
+    pub const fn is_futures(&self) -> bool {
+        matches!(self, Market::UsdmFutures)
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub struct Pair(pub String);
 
 #[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
 pub struct Timestamp(pub i64); // ms
 
+mod decimal;
 mod ring_buffer;
+mod rolling;
 
+pub use decimal::{Price, Volume};
 pub use ring_buffer::*;
+pub use rolling::{
+    RollingMax, RollingMin, RollingSum, RollingVariance, RollingWeightedSum, Wilder, true_range,
+};
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
 pub struct Kline {
-    pub open: f64,
-    pub high: f64,
-    pub low: f64,
-    pub close: f64,
-    pub volume: f64,
-    pub open_time: i64, // in ms
+    pub open: Price,
+    pub high: Price,
+    pub low: Price,
+    pub close: Price,
+    pub volume: Volume,       // base asset
+    pub quote_volume: Volume, // quote asset
+    pub trades: u64,
+    pub taker_buy_volume: Volume,       // base asset bought by takers
+    pub taker_buy_quote_volume: Volume, // quote asset spent by taker buys
+    pub open_time: i64,                 // in ms
     pub closed: bool,
 }
 
+impl Kline {
+    /// Share of base volume bought by takers (0.0..=1.0); `None` without volume.
+    pub fn taker_buy_ratio(&self) -> Option<f64> {
+        self.taker_buy_volume.share_of(self.volume)
+    }
+
+    /// Extend this bar with a later one: keeps the open, takes the later close.
+    pub fn merge(&self, next: &Kline) -> Kline {
+        Kline {
+            open: self.open,
+            high: self.high.max(next.high),
+            low: self.low.min(next.low),
+            close: next.close,
+            volume: self.volume + next.volume,
+            quote_volume: self.quote_volume + next.quote_volume,
+            trades: self.trades + next.trades,
+            taker_buy_volume: self.taker_buy_volume + next.taker_buy_volume,
+            taker_buy_quote_volume: self.taker_buy_quote_volume + next.taker_buy_quote_volume,
+            open_time: self.open_time,
+            closed: next.closed,
+        }
+    }
+}
+
 #[derive(Clone, Copy, Debug)]
 pub struct AggregatedBar {
     pub closed_agg: Option<Kline>, // aggregate of closed 1m bars in this tf window
@@ -43,9 +92,22 @@ pub struct AggregatedBar {
 
 pub type Bar1m = Kline;
 
+/// Current funding snapshot of a perpetual contract.
+#[derive(Clone, Copy, Debug)]
+pub struct FundingRate {
+    pub rate: f64, // fraction, e.g. 0.0001 = 0.01%
+}
+
+/// Open interest sample (base asset) at the end of a period.
+#[derive(Clone, Copy, Debug)]
+pub struct OpenInterest {
+    pub open_interest: f64,
+    pub timestamp: i64, // in ms
+}
+
 mod timeframe;
 
-pub use timeframe::Timeframe;
+pub use timeframe::{CustomTimeframe, MAX_CUSTOM_MINUTES, Timeframe, UtcOffset};
 
 pub mod config;
 
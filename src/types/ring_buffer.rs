@@ -41,6 +41,10 @@ impl<T> RingBuffer<T> {
         Some(last)
     }
 
+    pub fn get(&self, index: usize) -> Option<&T> {
+        self.buffer.get(index)
+    }
+
     pub fn front(&self) -> Option<&T> {
         self.buffer.front()
     }
@@ -64,10 +68,15 @@ impl<T> RingBuffer<T> {
 }
 
 impl RingBuffer<Kline> {
-    pub fn retain_by_open_time<F>(&mut self, mut predicate: F)
-    where
-        F: FnMut(i64) -> bool,
-    {
-        self.buffer.retain(|bar| predicate(bar.open_time));
+    /// Drop bars opening at or before `cutoff`. Bars are kept in open time
+    /// order, so this only looks at the front.
+    pub fn expire_through(&mut self, cutoff: i64) {
+        while self
+            .buffer
+            .front()
+            .is_some_and(|bar| bar.open_time <= cutoff)
+        {
+            self.buffer.pop_front();
+        }
     }
 }
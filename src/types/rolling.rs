@@ -0,0 +1,445 @@
+use std::collections::VecDeque;
+
+use super::{Kline, Price};
+
+/// Largest value of a sliding window, in O(1) amortized per push.
+///
+/// Values are keyed by open time and pushed in ascending key order; `expire`
+/// drops the ones that left the window. Ties keep the earliest key.
+#[derive(Clone, Debug, Default)]
+pub struct RollingMax<T> {
+    deque: VecDeque<(i64, T)>,
+}
+
+/// Smallest value of a sliding window; see [`RollingMax`].
+#[derive(Clone, Debug, Default)]
+pub struct RollingMin<T> {
+    deque: VecDeque<(i64, T)>,
+}
+
+macro_rules! monotonic_deque {
+    ($name:ident, $dominated:tt) => {
+        impl<T: PartialOrd> $name<T> {
+            pub fn new() -> Self {
+                Self {
+                    deque: VecDeque::new(),
+                }
+            }
+
+            pub fn push(&mut self, key: i64, value: T) {
+                // Older values the new one beats can never be the extreme again.
+                while self
+                    .deque
+                    .back()
+                    .is_some_and(|(_, back)| *back $dominated value)
+                {
+                    self.deque.pop_back();
+                }
+                self.deque.push_back((key, value));
+            }
+
+            /// Drop values keyed before `first_key`.
+            pub fn expire(&mut self, first_key: i64) {
+                while self.deque.front().is_some_and(|(key, _)| *key < first_key) {
+                    self.deque.pop_front();
+                }
+            }
+
+            /// Current extreme and its key.
+            pub fn get(&self) -> Option<(i64, &T)> {
+                self.deque.front().map(|(key, value)| (*key, value))
+            }
+
+            pub fn clear(&mut self) {
+                self.deque.clear();
+            }
+        }
+    };
+}
+
+monotonic_deque!(RollingMax, <);
+monotonic_deque!(RollingMin, >);
+
+/// Sum of the last `len` values, in O(1) per push.
+///
+/// The sum is recomputed once per `len` pushes so float error can't build up.
+#[derive(Clone, Debug)]
+pub struct RollingSum {
+    values: VecDeque<f64>,
+    len: usize,
+    sum: f64,
+    since_resum: usize,
+}
+
+impl RollingSum {
+    pub fn new(len: usize) -> Self {
+        let len = len.max(1);
+        Self {
+            values: VecDeque::with_capacity(len),
+            len,
+            sum: 0.0,
+            since_resum: 0,
+        }
+    }
+
+    pub fn push(&mut self, value: f64) {
+        if self.values.len() == self.len
+            && let Some(evicted) = self.values.pop_front()
+        {
+            self.sum -= evicted;
+        }
+        self.values.push_back(value);
+        self.sum += value;
+
+        self.since_resum += 1;
+        if self.since_resum >= self.len {
+            self.sum = self.values.iter().sum();
+            self.since_resum = 0;
+        }
+    }
+
+    pub fn sum(&self) -> f64 {
+        self.sum
+    }
+
+    /// `None` until the window is full.
+    pub fn mean(&self) -> Option<f64> {
+        self.is_full().then(|| self.sum / self.len as f64)
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.values.len() == self.len
+    }
+
+    pub fn clear(&mut self) {
+        self.values.clear();
+        self.sum = 0.0;
+        self.since_resum = 0;
+    }
+}
+
+/// Linearly weighted sum of the last `len` values, the newest weighing most,
+/// in O(1) per push.
+///
+/// Once the window is full the oldest value weighs 1 and the newest `len`.
+/// Recomputed once per `len` pushes, like [`RollingSum`].
+#[derive(Clone, Debug)]
+pub struct RollingWeightedSum {
+    values: VecDeque<f64>,
+    len: usize,
+    sum: f64,
+    weighted: f64,
+    since_resum: usize,
+}
+
+impl RollingWeightedSum {
+    pub fn new(len: usize) -> Self {
+        let len = len.max(1);
+        Self {
+            values: VecDeque::with_capacity(len),
+            len,
+            sum: 0.0,
+            weighted: 0.0,
+            since_resum: 0,
+        }
+    }
+
+    pub fn push(&mut self, value: f64) {
+        if self.values.len() == self.len
+            && let Some(evicted) = self.values.pop_front()
+        {
+            // Every kept value drops one weight; the evicted one drops to zero.
+            self.weighted += self.len as f64 * value - self.sum;
+            self.sum += value - evicted;
+        } else {
+            self.weighted += (self.values.len() + 1) as f64 * value;
+            self.sum += value;
+        }
+        self.values.push_back(value);
+
+        self.since_resum += 1;
+        if self.since_resum >= self.len {
+            self.sum = self.values.iter().sum();
+            self.weighted = (1..).zip(&self.values).map(|(w, v)| w as f64 * v).sum();
+            self.since_resum = 0;
+        }
+    }
+
+    pub fn sum(&self) -> f64 {
+        self.sum
+    }
+
+    pub fn weighted_sum(&self) -> f64 {
+        self.weighted
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.values.len() == self.len
+    }
+
+    pub fn clear(&mut self) {
+        self.values.clear();
+        self.sum = 0.0;
+        self.weighted = 0.0;
+        self.since_resum = 0;
+    }
+}
+
+/// Mean and population variance of the last `len` values, in O(1) per push.
+///
+/// Sliding Welford update; like [`RollingSum`] it is recomputed from the
+/// window once per `len` pushes.
+#[derive(Clone, Debug)]
+pub struct RollingVariance {
+    values: VecDeque<f64>,
+    len: usize,
+    mean: f64,
+    m2: f64, // sum of squared deviations from the mean
+    since_resum: usize,
+}
+
+impl RollingVariance {
+    pub fn new(len: usize) -> Self {
+        let len = len.max(1);
+        Self {
+            values: VecDeque::with_capacity(len),
+            len,
+            mean: 0.0,
+            m2: 0.0,
+            since_resum: 0,
+        }
+    }
+
+    pub fn push(&mut self, value: f64) {
+        let old_mean = self.mean;
+        if self.values.len() == self.len
+            && let Some(evicted) = self.values.pop_front()
+        {
+            self.values.push_back(value);
+            self.mean += (value - evicted) / self.len as f64;
+            self.m2 += (value - evicted) * (value - self.mean + evicted - old_mean);
+        } else {
+            self.values.push_back(value);
+            self.mean += (value - old_mean) / self.values.len() as f64;
+            self.m2 += (value - old_mean) * (value - self.mean);
+        }
+
+        self.since_resum += 1;
+        if self.since_resum >= self.len {
+            self.resum();
+        }
+    }
+
+    /// `None` until the window is full.
+    pub fn mean(&self) -> Option<f64> {
+        self.is_full().then_some(self.mean)
+    }
+
+    /// Population variance; `None` until the window is full.
+    pub fn variance(&self) -> Option<f64> {
+        self.is_full().then(|| (self.m2 / self.len as f64).max(0.0))
+    }
+
+    pub fn std_dev(&self) -> Option<f64> {
+        self.variance().map(f64::sqrt)
+    }
+
+    /// Mean and population variance of the full window plus `value`, without
+    /// pushing it; for a bar still forming on top of the closed ones.
+    pub fn with_next(&self, value: f64) -> Option<(f64, f64)> {
+        if !self.is_full() {
+            return None;
+        }
+        let count = (self.len + 1) as f64;
+        let delta = value - self.mean;
+        let mean = self.mean + delta / count;
+        let m2 = self.m2 + delta * (value - mean);
+        Some((mean, (m2 / count).max(0.0)))
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.values.len() == self.len
+    }
+
+    pub fn clear(&mut self) {
+        self.values.clear();
+        self.mean = 0.0;
+        self.m2 = 0.0;
+        self.since_resum = 0;
+    }
+
+    fn resum(&mut self) {
+        let count = self.values.len() as f64;
+        self.mean = self.values.iter().sum::<f64>() / count;
+        self.m2 = self.values.iter().map(|v| (v - self.mean).powi(2)).sum();
+        self.since_resum = 0;
+    }
+}
+
+/// Wilder's moving average of `len` values, seeded with the simple average of
+/// the first `len`; each later value moves it by `1/len`.
+#[derive(Clone, Debug)]
+pub struct Wilder {
+    len: usize,
+    average: Option<f64>,
+    seed: RollingSum,
+}
+
+impl Wilder {
+    pub fn new(len: usize) -> Self {
+        let len = len.max(1);
+        Self {
+            len,
+            average: None,
+            seed: RollingSum::new(len),
+        }
+    }
+
+    /// One smoothing step of `average` towards `value`.
+    pub fn step(average: f64, value: f64, len: usize) -> f64 {
+        let len = len.max(1) as f64;
+        (average * (len - 1.0) + value) / len
+    }
+
+    /// `None` until the seed is full.
+    pub fn average(&self) -> Option<f64> {
+        self.average
+    }
+
+    /// The average with `value` folded in, without pushing it; for a bar
+    /// still forming on top of the closed ones. `None` until seeded.
+    pub fn with_next(&self, value: f64) -> Option<f64> {
+        self.average
+            .map(|average| Self::step(average, value, self.len))
+    }
+
+    pub fn push(&mut self, value: f64) {
+        self.average = match self.with_next(value) {
+            Some(average) => Some(average),
+            None => {
+                self.seed.push(value);
+                self.seed.mean()
+            }
+        };
+    }
+
+    pub fn clear(&mut self) {
+        self.average = None;
+        self.seed.clear();
+    }
+}
+
+/// Range of `bar` stretched to cover a gap from the previous close.
+pub fn true_range(bar: &Kline, prev_close: Price) -> f64 {
+    let range = (bar.high - bar.low).to_f64();
+    let up = (bar.high - prev_close).to_f64().abs();
+    let down = (bar.low - prev_close).to_f64().abs();
+    range.max(up).max(down)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        RollingMax, RollingMin, RollingSum, RollingVariance, RollingWeightedSum, Wilder, true_range,
+    };
+    use crate::types::{Kline, Price, Volume};
+
+    #[test]
+    fn extremes_follow_the_window() {
+        let values = [3.0, 1.0, 4.0, 1.0, 5.0, 9.0, 2.0, 6.0, 5.0, 3.0];
+        let mut max = RollingMax::new();
+        let mut min = RollingMin::new();
+
+        for (key, &value) in values.iter().enumerate() {
+            let key = key as i64;
+            max.push(key, value);
+            min.push(key, value);
+            max.expire(key - 3);
+            min.expire(key - 3);
+
+            let window = &values[(key - 3).max(0) as usize..=key as usize];
+            let expected_max = window.iter().copied().fold(f64::MIN, f64::max);
+            let expected_min = window.iter().copied().fold(f64::MAX, f64::min);
+            assert_eq!(max.get().map(|(_, v)| *v), Some(expected_max));
+            assert_eq!(min.get().map(|(_, v)| *v), Some(expected_min));
+        }
+
+        // Ties keep the earliest key.
+        let mut min = RollingMin::new();
+        min.push(1, 1.0);
+        min.push(3, 1.0);
+        assert_eq!(min.get(), Some((1, &1.0)));
+    }
+
+    #[test]
+    fn sum_and_variance_match_the_window() {
+        let values: Vec<f64> = (0..50).map(|i| ((i * 37) % 11) as f64 + 0.1).collect();
+        let mut sum = RollingSum::new(7);
+        let mut variance = RollingVariance::new(7);
+        let mut weighted = RollingWeightedSum::new(7);
+
+        for (i, &value) in values.iter().enumerate() {
+            sum.push(value);
+            variance.push(value);
+            weighted.push(value);
+            if i < 6 {
+                assert_eq!(variance.variance(), None);
+                continue;
+            }
+
+            let window = &values[i - 6..=i];
+            let mean = window.iter().sum::<f64>() / 7.0;
+            let expected = window.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / 7.0;
+            let expected_weighted: f64 = (1..).zip(window).map(|(w, v)| w as f64 * v).sum();
+            assert!((sum.sum() - mean * 7.0).abs() < 1e-9);
+            assert!((weighted.weighted_sum() - expected_weighted).abs() < 1e-9);
+            assert!((variance.mean().unwrap() - mean).abs() < 1e-9);
+            assert!((variance.variance().unwrap() - expected).abs() < 1e-9);
+
+            let next = (i * 13 % 7) as f64;
+            let (mean, var) = variance.with_next(next).unwrap();
+            let extended: Vec<f64> = window.iter().copied().chain([next]).collect();
+            let expected_mean = extended.iter().sum::<f64>() / 8.0;
+            let expected_var = extended
+                .iter()
+                .map(|v| (v - expected_mean).powi(2))
+                .sum::<f64>()
+                / 8.0;
+            assert!((mean - expected_mean).abs() < 1e-9);
+            assert!((var - expected_var).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn wilder_seeds_then_smooths() {
+        let mut wilder = Wilder::new(2);
+        wilder.push(4.0);
+        assert_eq!(wilder.with_next(1.0), None);
+        wilder.push(6.0);
+        assert_eq!(wilder.average(), Some(5.0));
+        assert_eq!(wilder.with_next(1.0), Some(3.0));
+        wilder.push(1.0);
+        assert_eq!(wilder.average(), Some(3.0));
+    }
+
+    #[test]
+    fn true_range_covers_gaps() {
+        let bar = |low: f64, high: f64| Kline {
+            open: Price::from_f64(low),
+            high: Price::from_f64(high),
+            low: Price::from_f64(low),
+            close: Price::from_f64(high),
+            volume: Volume::from_f64(1.0),
+            quote_volume: Volume::from_f64(high),
+            trades: 1,
+            taker_buy_volume: Volume::from_f64(0.5),
+            taker_buy_quote_volume: Volume::from_f64(high / 2.0),
+            open_time: 0,
+            closed: true,
+        };
+        let prev_close = Price::from_f64(10.0);
+        assert_eq!(true_range(&bar(9.0, 11.0), prev_close), 2.0);
+        assert_eq!(true_range(&bar(12.0, 13.0), prev_close), 3.0);
+        assert_eq!(true_range(&bar(7.0, 8.0), prev_close), 3.0);
+    }
+}
@@ -1,61 +1,346 @@
-use serde::{Deserialize, Serialize};
-
-#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash, Ord, PartialOrd, Serialize, Deserialize)]
-pub enum Timeframe {
-    M1,
-    M5,
-    M15,
-    M30,
-    H1,
-    H4,
-    D1,
+use std::fmt;
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer, de};
+
+use crate::time::{civil_from_days, days_from_civil};
+
+const DAY_MS: i64 = 24 * 60 * 60_000;
+/// Weeks open on Monday; the epoch was a Thursday.
+const WEEK_OFFSET_MS: i64 = 4 * DAY_MS;
+/// Longest custom timeframe.
+pub const MAX_CUSTOM_MINUTES: u32 = 7 * 24 * 60;
+
+/// Declares `Timeframe` and every per-timeframe table from one list, so a new
+/// interval can't be half-wired. Entries are variant, Binance label and nominal
+/// minutes, shortest first.
+macro_rules! timeframes {
+    ($($variant:ident => $label:literal, $minutes:expr;)+) => {
+        #[derive(Clone, Copy, Debug, Eq, PartialEq, Hash, Ord, PartialOrd)]
+        pub enum Timeframe {
+            $($variant,)+
+            /// Not offered by the exchange; built from 1m bars.
+            Custom(CustomTimeframe),
+        }
+
+        impl Timeframe {
+            /// Every exchange timeframe, shortest first.
+            pub const ALL: [Timeframe; [$($label),+].len()] = [$(Timeframe::$variant),+];
+            pub const COUNT: usize = Self::ALL.len();
+
+            /// Nominal window; months count as the longest month.
+            pub const fn window_minutes(&self) -> usize {
+                match self {
+                    $(Timeframe::$variant => $minutes,)+
+                    Timeframe::Custom(custom) => custom.minutes as usize,
+                }
+            }
+
+            /// Interval label Binance knows this timeframe by.
+            pub const fn binance_interval(&self) -> Option<&'static str> {
+                match self {
+                    $(Timeframe::$variant => Some($label),)+
+                    Timeframe::Custom(_) => None,
+                }
+            }
+
+            /// Parse a Binance interval label such as `1m` or `4h`, or a custom
+            /// one such as `90m` or `45m@13:30`.
+            pub fn from_label(label: &str) -> Option<Timeframe> {
+                match label {
+                    $($label => Some(Timeframe::$variant),)+
+                    _ => CustomTimeframe::parse(label).map(CustomTimeframe::into_timeframe),
+                }
+            }
+
+            // Presets store exchange timeframes by variant name.
+            const fn variant_name(&self) -> Option<&'static str> {
+                match self {
+                    $(Timeframe::$variant => Some(stringify!($variant)),)+
+                    Timeframe::Custom(_) => None,
+                }
+            }
+
+            fn from_variant_name(name: &str) -> Option<Timeframe> {
+                match name {
+                    $(stringify!($variant) => Some(Timeframe::$variant),)+
+                    _ => None,
+                }
+            }
+        }
+    };
+}
+
+timeframes! {
+    M1 => "1m", 1;
+    M3 => "3m", 3;
+    M5 => "5m", 5;
+    M15 => "15m", 15;
+    M30 => "30m", 30;
+    H1 => "1h", 60;
+    H2 => "2h", 2 * 60;
+    H4 => "4h", 4 * 60;
+    H6 => "6h", 6 * 60;
+    H8 => "8h", 8 * 60;
+    H12 => "12h", 12 * 60;
+    D1 => "1d", 24 * 60;
+    D3 => "3d", 3 * 24 * 60;
+    W1 => "1w", 7 * 24 * 60;
+    Mo1 => "1M", 31 * 24 * 60;
 }
 
 impl Timeframe {
-    pub const fn window_minutes(&self) -> usize {
+    pub const fn window_millis(&self) -> i64 {
+        self.window_minutes() as i64 * 60_000
+    }
+
+    /// Open time of the bar containing `now_ms`. Weeks start on Monday and
+    /// months on the 1st, both UTC, like Binance's candles; custom timeframes
+    /// count from their anchor.
+    pub const fn nearest_ms(&self, now_ms: i64) -> i64 {
+        self.nearest_ms_in(now_ms, UtcOffset::UTC)
+    }
+
+    /// Like [`Timeframe::nearest_ms`], with days starting at midnight in the
+    /// `offset` timezone. Custom timeframes keep their own anchor.
+    pub const fn nearest_ms_in(&self, now_ms: i64, offset: UtcOffset) -> i64 {
+        let window = self.window_millis();
+        let shift = offset.millis();
         match self {
-            Timeframe::M1 => 1,
-            Timeframe::M5 => 5,
-            Timeframe::M15 => 15,
-            Timeframe::M30 => 30,
-            Timeframe::H1 => 60,
-            Timeframe::H4 => 4 * 60,
-            Timeframe::D1 => 24 * 60,
+            Timeframe::W1 => now_ms - (now_ms + shift - WEEK_OFFSET_MS).rem_euclid(window),
+            Timeframe::Mo1 => month_start(now_ms + shift, 0) - shift,
+            Timeframe::Custom(custom) => {
+                now_ms - (now_ms - custom.anchor_millis()).rem_euclid(window)
+            }
+            _ => now_ms - (now_ms + shift).rem_euclid(window),
         }
     }
-    pub const fn window_millis(&self) -> i64 {
+
+    /// Open time of the bar after the one opening at `open_ms`.
+    pub const fn next_open(&self, open_ms: i64) -> i64 {
+        self.next_open_in(open_ms, UtcOffset::UTC)
+    }
+
+    /// Like [`Timeframe::next_open`], for bars aligned to `offset`.
+    pub const fn next_open_in(&self, open_ms: i64, offset: UtcOffset) -> i64 {
         match self {
-            Timeframe::M1 => 60_000,
-            Timeframe::M5 => 5 * 60_000,
-            Timeframe::M15 => 15 * 60_000,
-            Timeframe::M30 => 30 * 60_000,
-            Timeframe::H1 => 60 * 60_000,
-            Timeframe::H4 => 4 * 60 * 60_000,
-            Timeframe::D1 => 24 * 60 * 60_000,
+            Timeframe::Mo1 => month_start(open_ms + offset.millis(), 1) - offset.millis(),
+            _ => open_ms + self.window_millis(),
+        }
+    }
+
+    /// Largest exchange timeframe whose bars tile this one's windows and are
+    /// not moved by `offset`, so history can be resampled from it.
+    pub fn tiling_base(&self, offset: UtcOffset) -> Timeframe {
+        if let Timeframe::Custom(custom) = self {
+            return custom.base(offset);
         }
+        Timeframe::ALL
+            .into_iter()
+            .filter(|tf| !matches!(tf, Timeframe::W1 | Timeframe::Mo1))
+            .filter(|tf| tf.window_minutes() < self.window_minutes())
+            .filter(|tf| self.window_minutes().is_multiple_of(tf.window_minutes()))
+            .filter(|tf| !offset.shifts(*tf))
+            .max_by_key(Timeframe::window_minutes)
+            .unwrap_or(Timeframe::M1)
     }
 
-    pub const fn to_string(&self) -> &str {
+    pub const fn is_custom(&self) -> bool {
+        matches!(self, Timeframe::Custom(_))
+    }
+}
+
+impl fmt::Display for Timeframe {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            Timeframe::M1 => "1m",
-            Timeframe::M5 => "5m",
-            Timeframe::M15 => "15m",
-            Timeframe::M30 => "30m",
-            Timeframe::H1 => "1h",
-            Timeframe::H4 => "4h",
-            Timeframe::D1 => "1d",
+            Timeframe::Custom(custom) => custom.fmt(f),
+            exchange => f.write_str(exchange.binance_interval().unwrap_or_default()),
         }
     }
+}
 
-    pub const fn nearest_ms(&self, now_ms: i64) -> i64 {
-        let window = self.window_millis();
-        now_ms - (now_ms % window)
+impl Serialize for Timeframe {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self.variant_name() {
+            Some(name) => serializer.serialize_str(name),
+            None => serializer.collect_str(self),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Timeframe {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let raw = String::deserialize(deserializer)?;
+        Timeframe::from_variant_name(&raw)
+            .or_else(|| Timeframe::from_label(&raw))
+            .ok_or_else(|| de::Error::custom(format!("unknown timeframe {raw}")))
     }
 }
 
+/// A timeframe of `minutes`, with windows counted from `anchor` minutes after
+/// UTC midnight (0 anchors at the epoch, otherwise at a session start).
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash, Ord, PartialOrd)]
+pub struct CustomTimeframe {
+    minutes: u32,
+    anchor: u16,
+}
+
+impl CustomTimeframe {
+    /// Parse `<n>m`, `<n>h` or `<n>d`, optionally followed by `@HH:MM`.
+    pub fn parse(label: &str) -> Option<Self> {
+        let (length, anchor) = match label.trim().split_once('@') {
+            Some((length, anchor)) => (length, Some(anchor)),
+            None => (label.trim(), None),
+        };
+
+        let (unit_at, _) = length.char_indices().next_back()?;
+        let count: u32 = length[..unit_at].parse().ok()?;
+        let minutes = match &length[unit_at..] {
+            "m" => count,
+            "h" => count.checked_mul(60)?,
+            "d" => count.checked_mul(24 * 60)?,
+            _ => return None,
+        };
+        if minutes == 0 || minutes > MAX_CUSTOM_MINUTES {
+            return None;
+        }
+
+        let anchor = match anchor {
+            Some(anchor) => {
+                let (hour, minute) = anchor.split_once(':')?;
+                let (hour, minute): (u16, u16) = (hour.parse().ok()?, minute.parse().ok()?);
+                if hour > 23 || minute > 59 {
+                    return None;
+                }
+                hour * 60 + minute
+            }
+            None => 0,
+        };
+
+        Some(Self { minutes, anchor })
+    }
+
+    pub fn minutes(&self) -> u32 {
+        self.minutes
+    }
+
+    /// Largest exchange timeframe whose bars tile this one exactly and are not
+    /// moved by `offset`, so history can be resampled from it instead of from
+    /// 1m bars.
+    pub fn base(&self, offset: UtcOffset) -> Timeframe {
+        Timeframe::ALL
+            .into_iter()
+            .filter(|tf| !matches!(tf, Timeframe::W1 | Timeframe::Mo1))
+            .filter(|tf| {
+                let base = tf.window_minutes() as u32;
+                self.minutes.is_multiple_of(base) && (self.anchor as u32).is_multiple_of(base)
+            })
+            .filter(|tf| !offset.shifts(*tf))
+            .max_by_key(Timeframe::window_minutes)
+            .unwrap_or(Timeframe::M1)
+    }
+
+    /// The exchange timeframe with the same windows, if there is one.
+    fn into_timeframe(self) -> Timeframe {
+        Timeframe::ALL
+            .into_iter()
+            .filter(|tf| !matches!(tf, Timeframe::W1 | Timeframe::Mo1))
+            .find(|tf| self.anchor == 0 && tf.window_minutes() as u32 == self.minutes)
+            .unwrap_or(Timeframe::Custom(self))
+    }
+
+    const fn anchor_millis(&self) -> i64 {
+        self.anchor as i64 * 60_000
+    }
+}
+
+impl fmt::Display for CustomTimeframe {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let day = 24 * 60;
+        if self.minutes.is_multiple_of(day) {
+            write!(f, "{}d", self.minutes / day)?;
+        } else if self.minutes.is_multiple_of(60) {
+            write!(f, "{}h", self.minutes / 60)?;
+        } else {
+            write!(f, "{}m", self.minutes)?;
+        }
+        if self.anchor != 0 {
+            write!(f, "@{:02}:{:02}", self.anchor / 60, self.anchor % 60)?;
+        }
+        Ok(())
+    }
+}
+
+/// UTC offset of the timezone whose midnight starts the day, like Binance's
+/// `timeZone` kline parameter. Moves every window it doesn't divide.
+#[derive(
+    Clone, Copy, Debug, Default, Eq, PartialEq, Hash, Ord, PartialOrd, Serialize, Deserialize,
+)]
+pub struct UtcOffset(i16);
+
+impl UtcOffset {
+    pub const UTC: UtcOffset = UtcOffset(0);
+    /// Range Binance accepts, in minutes.
+    pub const MIN_MINUTES: i16 = -12 * 60;
+    pub const MAX_MINUTES: i16 = 14 * 60;
+
+    pub fn from_minutes(minutes: i32) -> Self {
+        Self(minutes.clamp(Self::MIN_MINUTES as i32, Self::MAX_MINUTES as i32) as i16)
+    }
+
+    /// Parse `+09:00`, `-5` or `5:45`, within Binance's range.
+    pub fn from_label(label: &str) -> Option<Self> {
+        let (sign, rest) = match label.strip_prefix('-') {
+            Some(rest) => (-1, rest),
+            None => (1, label.strip_prefix('+').unwrap_or(label)),
+        };
+        let (hours, minutes) = rest.split_once(':').unwrap_or((rest, "0"));
+        let (hours, minutes): (i32, i32) = (hours.parse().ok()?, minutes.parse().ok()?);
+        if !(0..60).contains(&minutes) {
+            return None;
+        }
+        let total = sign * (hours * 60 + minutes);
+        (Self::MIN_MINUTES as i32..=Self::MAX_MINUTES as i32)
+            .contains(&total)
+            .then_some(Self(total as i16))
+    }
+
+    pub const fn minutes(self) -> i16 {
+        self.0
+    }
+
+    pub const fn millis(self) -> i64 {
+        self.0 as i64 * 60_000
+    }
+
+    /// Whether `tf` windows open at other times than they do in UTC.
+    pub const fn shifts(self, tf: Timeframe) -> bool {
+        match tf {
+            Timeframe::Custom(_) => false,
+            Timeframe::W1 | Timeframe::Mo1 => self.0 != 0,
+            _ => self.millis().rem_euclid(tf.window_millis()) != 0,
+        }
+    }
+}
+
+impl fmt::Display for UtcOffset {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let sign = if self.0 < 0 { '-' } else { '+' };
+        let minutes = self.0.unsigned_abs();
+        write!(f, "{sign}{:02}:{:02}", minutes / 60, minutes % 60)
+    }
+}
+
+/// First millisecond of the month `months` after the one containing `ts`.
+const fn month_start(ts: i64, months: u32) -> i64 {
+    let (year, month, _) = civil_from_days(ts.div_euclid(DAY_MS));
+    let month0 = month - 1 + months;
+    let year = year + (month0 / 12) as i64;
+    days_from_civil(year, month0 % 12 + 1, 1) * DAY_MS
+}
+
 #[cfg(test)]
 mod tests {
-    use super::Timeframe;
+    use super::{Timeframe, UtcOffset};
 
     #[test]
     fn nearest_ms_floors_to_window() {
@@ -80,4 +365,132 @@ mod tests {
         );
         assert_eq!(Timeframe::H1.nearest_ms(ts), 13 * 3_600_000);
     }
+
+    #[test]
+    fn calendar_windows_follow_binance() {
+        // Wed 2024-01-31 13:56:05 UTC.
+        let ts = 1_706_709_365_000;
+        let day = 24 * 3_600_000;
+
+        // Week of Mon 2024-01-29.
+        assert_eq!(Timeframe::W1.nearest_ms(ts), 1_706_486_400_000);
+        assert_eq!(Timeframe::D1.nearest_ms(ts), 1_706_659_200_000);
+
+        let january = 1_704_067_200_000;
+        assert_eq!(Timeframe::Mo1.nearest_ms(ts), january);
+        assert_eq!(Timeframe::Mo1.next_open(january), january + 31 * day);
+        // 2024 is a leap year.
+        assert_eq!(
+            Timeframe::Mo1.next_open(january + 31 * day),
+            january + 60 * day
+        );
+        // December rolls into the next year.
+        assert_eq!(
+            Timeframe::Mo1.next_open(1_733_011_200_000),
+            1_735_689_600_000
+        );
+    }
+
+    #[test]
+    fn offset_moves_session_windows() {
+        // Wed 2024-01-31 13:56:05 UTC.
+        let ts = 1_706_709_365_000;
+        let hour = 3_600_000;
+        let new_york = UtcOffset::from_minutes(-5 * 60);
+        let tokyo = UtcOffset::from_minutes(9 * 60);
+
+        // New York's day opened at 05:00 UTC, Tokyo's at 15:00 UTC the day before.
+        let utc_day = Timeframe::D1.nearest_ms(ts);
+        assert_eq!(
+            Timeframe::D1.nearest_ms_in(ts, new_york),
+            utc_day + 5 * hour
+        );
+        assert_eq!(Timeframe::D1.nearest_ms_in(ts, tokyo), utc_day - 9 * hour);
+        assert_eq!(
+            Timeframe::H4.nearest_ms_in(ts, new_york),
+            utc_day + 13 * hour
+        );
+        // Whole-hour offsets leave hourly windows alone.
+        assert!(!tokyo.shifts(Timeframe::H1));
+        assert!(tokyo.shifts(Timeframe::H2));
+        assert_eq!(Timeframe::D1.tiling_base(tokyo), Timeframe::H1);
+        assert_eq!(Timeframe::D1.tiling_base(UtcOffset::UTC), Timeframe::H12);
+
+        // Tokyo's February opened on Jan 31 at 15:00 UTC.
+        let february = Timeframe::Mo1.nearest_ms_in(ts + 2 * hour, tokyo);
+        assert_eq!(february, 1_706_713_200_000);
+        assert_eq!(
+            Timeframe::Mo1.next_open_in(february, tokyo),
+            february + 29 * 24 * hour
+        );
+        assert_eq!(tokyo.to_string(), "+09:00");
+        assert_eq!(UtcOffset::from_minutes(-210).to_string(), "-03:30");
+        assert_eq!(
+            UtcOffset::from_label("-3:30"),
+            Some(UtcOffset::from_minutes(-210))
+        );
+        assert_eq!(UtcOffset::from_label("9"), Some(tokyo));
+        assert_eq!(UtcOffset::from_label("+15:00"), None);
+    }
+
+    #[test]
+    fn labels_round_trip() {
+        for tf in Timeframe::ALL {
+            assert_eq!(Timeframe::from_label(&tf.to_string()), Some(tf));
+        }
+
+        for label in ["7m", "45m", "90m", "2d", "45m@13:30"] {
+            let tf = Timeframe::from_label(label).unwrap();
+            assert!(tf.is_custom());
+            assert_eq!(tf.to_string(), label);
+        }
+        // Same windows as an exchange timeframe.
+        assert_eq!(Timeframe::from_label("60m"), Some(Timeframe::H1));
+        assert_eq!(Timeframe::from_label("0m"), None);
+        assert_eq!(Timeframe::from_label("90m@25:00"), None);
+    }
+
+    #[test]
+    fn rejects_non_ascii_labels() {
+        for label in ["5é", "é", "5mé", "९m", "5m@1é:00"] {
+            assert_eq!(Timeframe::from_label(label), None, "{label}");
+        }
+    }
+
+    #[test]
+    fn custom_windows_follow_anchor() {
+        let Some(Timeframe::Custom(ninety)) = Timeframe::from_label("90m") else {
+            panic!("90m is custom");
+        };
+        assert_eq!(ninety.base(UtcOffset::UTC), Timeframe::M30);
+
+        let ts = (13 * 3_600_000) + (56 * 60_000) + 5_000;
+        // Epoch anchored: 00:00, 01:30, ... 12:00, 13:30.
+        assert_eq!(
+            Timeframe::Custom(ninety).nearest_ms(ts),
+            (13 * 3_600_000) + (30 * 60_000)
+        );
+
+        // Session anchored at 13:45: ..., 12:15, 13:45.
+        let session = Timeframe::from_label("90m@13:45").unwrap();
+        assert_eq!(session.nearest_ms(ts), (13 * 3_600_000) + (45 * 60_000));
+        let Timeframe::Custom(session) = session else {
+            panic!("90m@13:45 is custom");
+        };
+        assert_eq!(session.base(UtcOffset::UTC), Timeframe::M15);
+    }
+
+    #[test]
+    fn custom_bases_skip_shifted_timeframes() {
+        // 2h bars open at odd hours in UTC+9, so 2d resamples from 1h.
+        let tokyo = UtcOffset::from_label("+09:00").unwrap();
+        let two_days = Timeframe::from_label("2d").unwrap();
+        assert_eq!(two_days.tiling_base(UtcOffset::UTC), Timeframe::D1);
+        assert_eq!(two_days.tiling_base(tokyo), Timeframe::H1);
+
+        let kathmandu = UtcOffset::from_label("+05:45").unwrap();
+        let ninety = Timeframe::from_label("90m").unwrap();
+        assert_eq!(ninety.tiling_base(kathmandu), Timeframe::M15);
+        assert!(!kathmandu.shifts(ninety.tiling_base(kathmandu)));
+    }
 }
@@ -1,4 +1,4 @@
-use std::time::Duration;
+use std::{str::FromStr, time::Duration};
 
 use futures_util::{SinkExt, StreamExt};
 use log::warn;
@@ -12,10 +12,11 @@ use tokio_tungstenite::{connect_async, tungstenite::Message};
 use crate::{
     error::{GlobalError, Result, WsError},
     message_bus::{EngineMessage, EngineTx, KlineEvent, RebootEvent, WsMessage, WsRx},
-    types::{AppConfig, Bar1m, Pair, Timeframe},
+    types::{AppConfig, Bar1m, Market, Pair, Price, Timeframe, Volume},
 };
 
 const BINANCE_WS_BASE: &str = "wss://stream.binance.com:9443/stream?streams=";
+const BINANCE_FUTURES_WS_BASE: &str = "wss://fstream.binance.com/stream?streams=";
 const RECONNECT_DELAY: Duration = Duration::from_secs(5);
 
 #[derive(Debug)]
@@ -161,14 +162,19 @@ fn build_stream_url(config: &AppConfig) -> Result<String> {
         return Err(WsError::EmptyPairs.into());
     }
 
-    Ok(format!("{BINANCE_WS_BASE}{}", streams.join("/")))
+    let base = match config.market() {
+        Market::Spot => BINANCE_WS_BASE,
+        Market::UsdmFutures => BINANCE_FUTURES_WS_BASE,
+    };
+
+    Ok(format!("{base}{}", streams.join("/")))
 }
 
 fn parse_kline(raw: &str) -> Result<Option<KlineEvent>> {
     let envelope: CombinedStream = serde_json::from_str(raw)
         .map_err(|e| GlobalError::Other(format!("deserialize error: {e}")))?;
 
-    let tf = match parse_timeframe(&envelope.data.kline.interval) {
+    let tf = match Timeframe::from_label(&envelope.data.kline.interval) {
         Some(tf) => tf,
         None => return Ok(None),
     };
@@ -179,6 +185,10 @@ fn parse_kline(raw: &str) -> Result<Option<KlineEvent>> {
         low: envelope.data.kline.low,
         close: envelope.data.kline.close,
         volume: envelope.data.kline.volume,
+        quote_volume: envelope.data.kline.quote_volume,
+        trades: envelope.data.kline.trades,
+        taker_buy_volume: envelope.data.kline.taker_buy_volume,
+        taker_buy_quote_volume: envelope.data.kline.taker_buy_quote_volume,
         open_time: envelope.data.kline.open_time,
         closed: envelope.data.kline.closed,
     };
@@ -190,19 +200,6 @@ fn parse_kline(raw: &str) -> Result<Option<KlineEvent>> {
     }))
 }
 
-fn parse_timeframe(interval: &str) -> Option<Timeframe> {
-    match interval {
-        "1m" => Some(Timeframe::M1),
-        "5m" => Some(Timeframe::M5),
-        "15m" => Some(Timeframe::M15),
-        "30m" => Some(Timeframe::M30),
-        "1h" => Some(Timeframe::H1),
-        "4h" => Some(Timeframe::H4),
-        "1d" => Some(Timeframe::D1),
-        _ => None,
-    }
-}
-
 #[derive(Debug, Deserialize)]
 struct CombinedStream {
     #[allow(dead_code)]
@@ -224,26 +221,36 @@ struct RawKline {
     interval: String,
     #[serde(rename = "t")]
     open_time: i64,
-    #[serde(rename = "o", deserialize_with = "de_str_f64")]
-    open: f64,
-    #[serde(rename = "h", deserialize_with = "de_str_f64")]
-    high: f64,
-    #[serde(rename = "l", deserialize_with = "de_str_f64")]
-    low: f64,
-    #[serde(rename = "c", deserialize_with = "de_str_f64")]
-    close: f64,
-    #[serde(rename = "v", deserialize_with = "de_str_f64")]
-    volume: f64,
+    #[serde(rename = "o", deserialize_with = "de_str_decimal")]
+    open: Price,
+    #[serde(rename = "h", deserialize_with = "de_str_decimal")]
+    high: Price,
+    #[serde(rename = "l", deserialize_with = "de_str_decimal")]
+    low: Price,
+    #[serde(rename = "c", deserialize_with = "de_str_decimal")]
+    close: Price,
+    #[serde(rename = "v", deserialize_with = "de_str_decimal")]
+    volume: Volume,
+    #[serde(rename = "q", deserialize_with = "de_str_decimal")]
+    quote_volume: Volume,
+    #[serde(rename = "n")]
+    trades: u64,
+    #[serde(rename = "V", deserialize_with = "de_str_decimal")]
+    taker_buy_volume: Volume,
+    #[serde(rename = "Q", deserialize_with = "de_str_decimal")]
+    taker_buy_quote_volume: Volume,
     #[serde(rename = "x")]
     closed: bool,
 }
 
-fn de_str_f64<'de, D>(deserializer: D) -> std::result::Result<f64, D::Error>
+/// Prices and volumes arrive as strings; parse them exactly, like REST klines.
+fn de_str_decimal<'de, D, T>(deserializer: D) -> std::result::Result<T, D::Error>
 where
     D: de::Deserializer<'de>,
+    T: FromStr<Err = rust_decimal::Error>,
 {
     let s = String::deserialize(deserializer)?;
-    s.parse::<f64>().map_err(de::Error::custom)
+    s.parse::<T>().map_err(de::Error::custom)
 }
 
 enum Restart {
@@ -251,3 +258,24 @@ enum Restart {
     ConfigUpdate,
     ChannelClosed,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_combined_stream_kline() {
+        let raw = r#"{"stream":"btcusdt@kline_1m","data":{"e":"kline","E":1700000030000,"s":"BTCUSDT","k":{"t":1700000000000,"T":1700000059999,"s":"BTCUSDT","i":"1m","f":100,"L":200,"o":"37000.10","c":"37010.00","h":"37020.00","l":"36990.00","v":"12.5","n":101,"x":false,"q":"462600.00","V":"7.5","Q":"277560.00","B":"0"}}}"#;
+
+        let event = parse_kline(raw).expect("should parse").expect("1m kline");
+        assert_eq!(event.pair.0, "BTCUSDT");
+        assert_eq!(event.timeframe, Timeframe::M1);
+        assert_eq!(event.bar.open_time, 1_700_000_000_000);
+        assert_eq!(event.bar.open.to_string(), "37000.10");
+        assert_eq!(event.bar.quote_volume.to_string(), "462600.00");
+        assert_eq!(event.bar.trades, 101);
+        assert_eq!(event.bar.taker_buy_volume.to_string(), "7.5");
+        assert_eq!(event.bar.taker_buy_quote_volume.to_string(), "277560.00");
+        assert!(!event.bar.closed);
+    }
+}